@@ -5,13 +5,15 @@
 // This file is dual-licensed under the MIT and Apache 2.0 licenses.
 // See LICENSE-MIT and LICENSE-APACHE in the repository root for full license texts.
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tauri::Emitter;
 
 // Import core scanner
-use spectra_core::{ScanStats, Scanner};
+use spectra_core::analysis::{classify_entropy, EntropyClass, EntropyThresholds};
+use spectra_core::{ScanReport, Scanner};
 
 // --- Data Models ---
 
@@ -23,9 +25,24 @@ struct TreeNode {
     #[serde(skip_serializing_if = "Option::is_none")]
     children: Option<Vec<TreeNode>>,
     entropy: f32,
-    risk_score: u8,
+    entropy_class: EntropyClass,
 }
 
+/// How a directory node's `loc` is computed.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum SizeMode {
+    /// Everything under the directory (within the scanned depth).
+    #[default]
+    Cumulative,
+    /// Only the directory's own direct files.
+    Exclusive,
+}
+
+/// Set by `cancel_scan` to stop the `scan_directory` in progress.
+#[derive(Default)]
+struct ScanCancel(AtomicBool);
+
 // --- Logic ---
 
 fn calculate_mock_entropy(path: &Path) -> f32 {
@@ -41,7 +58,13 @@ fn calculate_mock_entropy(path: &Path) -> f32 {
     }
 }
 
-fn scan_directory_recursive(path: &Path, depth: usize, max_depth: usize) -> Option<TreeNode> {
+fn scan_directory_recursive(
+    path: &Path,
+    depth: usize,
+    max_depth: usize,
+    mode: SizeMode,
+    thresholds: &EntropyThresholds,
+) -> Option<TreeNode> {
     if depth > max_depth {
         return None;
     }
@@ -60,18 +83,24 @@ fn scan_directory_recursive(path: &Path, depth: usize, max_depth: usize) -> Opti
             size: metadata.len(),
             children: None,
             entropy,
-            risk_score: (entropy * 10.0) as u8,
+            entropy_class: classify_entropy(entropy, thresholds),
         });
     } else if metadata.is_dir() {
         let mut children = Vec::new();
         let mut dir_size = 0;
+        let mut own_files_size = 0;
         let mut total_entropy = 0.0;
         let mut file_count = 0;
 
         if let Ok(entries) = fs::read_dir(path) {
             for entry in entries.flatten() {
-                if let Some(node) = scan_directory_recursive(&entry.path(), depth + 1, max_depth) {
+                if let Some(node) =
+                    scan_directory_recursive(&entry.path(), depth + 1, max_depth, mode, thresholds)
+                {
                     dir_size += node.size;
+                    if node.children.is_none() {
+                        own_files_size += node.size;
+                    }
                     total_entropy += node.entropy;
                     file_count += 1;
                     children.push(node);
@@ -87,10 +116,13 @@ fn scan_directory_recursive(path: &Path, depth: usize, max_depth: usize) -> Opti
 
         return Some(TreeNode {
             name,
-            size: dir_size,
+            size: match mode {
+                SizeMode::Cumulative => dir_size,
+                SizeMode::Exclusive => own_files_size,
+            },
             children: Some(children),
             entropy: avg_entropy,
-            risk_score: (avg_entropy * 10.0) as u8,
+            entropy_class: classify_entropy(avg_entropy, thresholds),
         });
     }
     None
@@ -98,8 +130,16 @@ fn scan_directory_recursive(path: &Path, depth: usize, max_depth: usize) -> Opti
 
 // --- Commands ---
 
+/// Build the treemap tree. `size_mode` is `"cumulative"` (default: a
+/// directory's size includes all descendants) or `"exclusive"` (only its own
+/// direct files). `thresholds` overrides the entropy cut-offs used for each
+/// node's `entropy_class`.
 #[tauri::command]
-fn get_scan_tree(path: String) -> Result<TreeNode, String> {
+fn get_scan_tree(
+    path: String,
+    size_mode: Option<SizeMode>,
+    thresholds: Option<EntropyThresholds>,
+) -> Result<TreeNode, String> {
     let root = Path::new(&path);
 
     if !root.exists() {
@@ -110,14 +150,27 @@ fn get_scan_tree(path: String) -> Result<TreeNode, String> {
         return Err(format!("Cannot access path: {}", e));
     }
 
-    scan_directory_recursive(root, 0, 3)
-        .ok_or_else(|| format!("Failed to scan path: {}. Try a subdirectory instead.", path))
+    scan_directory_recursive(
+        root,
+        0,
+        3,
+        size_mode.unwrap_or_default(),
+        &thresholds.unwrap_or_default(),
+    )
+    .ok_or_else(|| format!("Failed to scan path: {}. Try a subdirectory instead.", path))
 }
 
 /// Progressive scan with streaming progress events (#1).
-/// Emits "scan-progress" events to the frontend during scanning.
-#[tauri::command]
-fn scan_directory(app: tauri::AppHandle, path: String, limit: usize) -> Result<ScanStats, String> {
+/// Emits "scan-progress" events to the frontend during scanning. A
+/// `cancel_scan` call returns early with `cancelled` set. Runs off the main
+/// thread so that `cancel_scan` can be handled meanwhile.
+#[tauri::command(async)]
+fn scan_directory(
+    app: tauri::AppHandle,
+    cancel: tauri::State<'_, ScanCancel>,
+    path: String,
+    limit: usize,
+) -> Result<ScanReport, String> {
     let root = Path::new(&path);
 
     if !root.exists() {
@@ -133,14 +186,96 @@ fn scan_directory(app: tauri::AppHandle, path: String, limit: usize) -> Result<S
         let _ = app_handle.emit("scan-progress", &progress);
     });
 
-    scanner.scan().map_err(|e| format!("Scan failed: {}", e))
+    cancel.0.store(false, Ordering::Relaxed);
+    scanner
+        .scan_cancellable(&cancel.0)
+        .map(ScanReport::new)
+        .map_err(|e| format!("Scan failed: {}", e))
+}
+
+/// Stop the running `scan_directory`, e.g. when the user navigates away.
+#[tauri::command]
+fn cancel_scan(cancel: tauri::State<'_, ScanCancel>) {
+    cancel.0.store(true, Ordering::Relaxed);
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![get_scan_tree, scan_directory])
+        .manage(ScanCancel::default())
+        .invoke_handler(tauri::generate_handler![
+            get_scan_tree,
+            scan_directory,
+            cancel_scan
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_size_mode_controls_directory_size() {
+        let dir = tempdir().unwrap();
+        File::create(dir.path().join("own.txt"))
+            .unwrap()
+            .write_all(&[0u8; 100])
+            .unwrap();
+        fs::create_dir(dir.path().join("nested")).unwrap();
+        File::create(dir.path().join("nested/deep.txt"))
+            .unwrap()
+            .write_all(&[0u8; 400])
+            .unwrap();
+        let thresholds = EntropyThresholds::default();
+
+        let cumulative =
+            scan_directory_recursive(dir.path(), 0, 3, SizeMode::Cumulative, &thresholds).unwrap();
+        let exclusive =
+            scan_directory_recursive(dir.path(), 0, 3, SizeMode::Exclusive, &thresholds).unwrap();
+
+        assert_eq!(cumulative.size, 500);
+        assert_eq!(exclusive.size, 100);
+        let nested = |node: &TreeNode| {
+            node.children
+                .as_ref()
+                .unwrap()
+                .iter()
+                .find(|c| c.name == "nested")
+                .unwrap()
+                .size
+        };
+        assert_eq!(nested(&cumulative), 400);
+        assert_eq!(nested(&exclusive), 400);
+    }
+
+    #[test]
+    fn test_compressed_entropy_is_not_max_risk() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("bundle.zip");
+        File::create(&archive).unwrap();
+
+        // Mock entropy for .zip is 7.8: compressed, not encrypted.
+        let node = scan_directory_recursive(
+            &archive,
+            0,
+            3,
+            SizeMode::Cumulative,
+            &EntropyThresholds::default(),
+        )
+        .unwrap();
+        assert_eq!(node.entropy_class, EntropyClass::Compressed);
+
+        let strict = EntropyThresholds {
+            encrypted: 7.5,
+            ..Default::default()
+        };
+        let node = scan_directory_recursive(&archive, 0, 3, SizeMode::Cumulative, &strict).unwrap();
+        assert_eq!(node.entropy_class, EntropyClass::Encrypted);
+    }
+}
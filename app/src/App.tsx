@@ -2,7 +2,7 @@ import { useState, useCallback, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import "./App.css";
-import { ScanStats, ScanProgress, VelocityReport } from "./types";
+import { ScanReport, ScanProgress, VelocityReport } from "./types";
 import { TimeSlider } from "./TimeSlider";
 import { VelocityCard } from "./VelocityCard";
 import { SunburstChart } from "./components/SunburstChart";
@@ -31,7 +31,7 @@ function App() {
 
   // Local Scan State
   const [path, setPath] = useState("");
-  const [stats, setStats] = useState<ScanStats | null>(null);
+  const [stats, setStats] = useState<ScanReport | null>(null);
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
 
@@ -79,7 +79,7 @@ function App() {
         throw new Error('Tauri runtime not available. Please run the app using: npm run tauri dev');
       }
 
-      const result = await invoke<ScanStats>("scan_directory", {
+      const result = await invoke<ScanReport>("scan_directory", {
         path,
         limit: 10
       });
@@ -92,6 +92,14 @@ function App() {
     }
   }
 
+  async function cancelScan() {
+    try {
+      await invoke("cancel_scan");
+    } catch (e) {
+      setError(String(e));
+    }
+  }
+
   const handleRangeSelect = useCallback(async (startTime: number, endTime: number) => {
     setVelocityLoading(true);
     setVelocityReport(null);
@@ -166,6 +174,11 @@ function App() {
             <button type="submit" disabled={loading || !path}>
               {loading ? "Scanning..." : "Deep Scan"}
             </button>
+            {loading && (
+              <button type="button" className="cancel-button" onClick={cancelScan}>
+                Cancel
+              </button>
+            )}
           </form>
 
           {/* Progressive scan progress (#1) */}
@@ -184,6 +197,10 @@ function App() {
 
           {error && <div className="error-banner">Error: {error}</div>}
 
+          {stats?.cancelled && (
+            <div className="error-banner">Scan cancelled: the totals below are incomplete.</div>
+          )}
+
           {stats && (
             <div className="dashboard-grid">
               {/* Summary Card */}
@@ -63,10 +63,15 @@ export async function fetchSnapshotAtTime(
   timestamp?: number
 ): Promise<AgentSnapshot | null> {
   try {
-    const params = timestamp ? `?timestamp=${timestamp}` : '';
+    const params = timestamp ? `?at=${timestamp}` : '';
     const url = `${SERVER_URL}/api/v1/snapshot/${agentId}${params}`;
     const response = await fetch(url);
 
+    // 404 means the agent has no snapshot at or before the requested time
+    if (response.status === 404) {
+      return null;
+    }
+
     if (!response.ok) {
       throw new Error(`HTTP ${response.status}: ${response.statusText}`);
     }
@@ -1,12 +1,21 @@
 import { ResponsiveTreeMap } from '@nivo/treemap';
 import { useState } from 'react';
 
+type EntropyClass = 'text' | 'compressed' | 'encrypted';
+
+// One color per class (thresholds come from the backend's classify_entropy)
+const CLASS_COLORS: Record<EntropyClass, string> = {
+  text: '#4caf50', // Green
+  compressed: '#2196f3', // Blue (expected high entropy: archives, media)
+  encrypted: '#f44336', // Red
+};
+
 // Define the Data Shape
 interface TreeNode {
   name: string;
   loc: number; // Size
   entropy: number;
-  risk_score: number;
+  entropy_class: EntropyClass;
   children?: TreeNode[];
 }
 
@@ -30,13 +39,8 @@ const RiskTreemap = ({ data }: RiskTreemapProps) => {
         // --- The "Risk" Coloring Logic ---
         colors={(node: any) => {
           // Access the raw data of the node
-          const entropy = node.data.entropy;
-
-          // Green (Safe) -> Yellow (Mixed) -> Red (Danger)
-          if (entropy < 3.0) return '#4caf50'; // Green
-          if (entropy < 6.0) return '#ffeb3b'; // Yellow
-          if (entropy < 7.5) return '#ff9800'; // Orange
-          return '#f44336'; // Red (High Entropy/Risk)
+          const entropyClass = node.data.entropy_class as EntropyClass;
+          return CLASS_COLORS[entropyClass] ?? CLASS_COLORS.text;
         }}
 
         parentLabelTextColor={{ from: 'color', modifiers: [['darker', 2]] }}
@@ -53,7 +57,7 @@ const RiskTreemap = ({ data }: RiskTreemapProps) => {
             <div style={{ background: '#333', padding: '10px', color: '#fff', borderRadius: '4px' }}>
                 <strong>{node.data.name}</strong><br/>
                 Size: {node.formattedValue}<br/>
-                Entropy: {(node.data as any).entropy.toFixed(2)} / 8.0
+                Entropy: {(node.data as any).entropy.toFixed(2)} / 8.0 ({(node.data as any).entropy_class})
             </div>
         )}
       />
@@ -6,10 +6,10 @@ const mockData = {
   name: "root",
   loc: 1000,
   entropy: 2.0,
-  risk_score: 20,
+  entropy_class: "text" as const,
   children: [
-    { name: "safe.txt", loc: 500, entropy: 1.0, risk_score: 10 },
-    { name: "danger.enc", loc: 500, entropy: 7.9, risk_score: 80 }
+    { name: "safe.txt", loc: 500, entropy: 1.0, entropy_class: "text" as const },
+    { name: "danger.enc", loc: 500, entropy: 7.99, entropy_class: "encrypted" as const }
   ]
 };
 
@@ -28,7 +28,7 @@ describe('RiskTreemap', () => {
       name: "empty",
       loc: 100,
       entropy: 0.5,
-      risk_score: 5,
+      entropy_class: "text" as const,
       children: []
     };
 
@@ -41,7 +41,7 @@ describe('RiskTreemap', () => {
       name: "single.txt",
       loc: 500,
       entropy: 2.5,
-      risk_score: 25
+      entropy_class: "text" as const
     };
 
     render(<RiskTreemap data={singleFile} />);
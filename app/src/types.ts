@@ -1,23 +1,63 @@
 export interface FileRecord {
   path: string;
   size_bytes: number;
+  /** On-disk allocated bytes, when the scan collected them */
+  allocated_bytes?: number;
+  /** A directory ranked by rolled-up size, or a rolled-up .git directory */
+  is_dir?: boolean;
+}
+
+export interface DirRecord {
+  path: string;
+  total_size_bytes: number;
+  file_count: number;
 }
 
 export interface ExtensionStat {
   count: number;
   size: number;
+  /** Share of the total size, as a percentage */
+  percent?: number;
 }
 
 export interface ScanStats {
   root_path: string;
+  /** Tag given with --label */
+  label?: string;
   total_files: number;
   total_folders: number;
   total_size_bytes: number;
+  /** On-disk allocated bytes, when the scan collected them */
+  total_allocated_bytes?: number;
   scan_duration_ms: number;
   extensions: Record<string, ExtensionStat>;
+  /** Files grouped by sniffed MIME type, when MIME detection was on */
+  mime_types?: Record<string, ExtensionStat>;
   top_files: FileRecord[];
+  /** Largest directories by recursive size, root excluded */
+  top_dirs?: DirRecord[];
   device_type?: string;
   threads_used?: number;
+  /** Scan start, seconds since the Unix epoch */
+  scanned_at?: number;
+  /** Stopped early by a time budget; totals are incomplete */
+  partial?: boolean;
+  /** Stopped by cancel_scan; totals are incomplete */
+  cancelled?: boolean;
+  /** Paths the scan could not read; their contents are missing from the totals */
+  errors?: { path: string; message: string }[];
+  /** Approximate file-size percentiles in bytes (max is exact) */
+  size_percentiles?: { p50: number; p90: number; p99: number; max: number };
+}
+
+/** What `scan_directory` returns: the stats plus values derived from them */
+export interface ScanReport extends ScanStats {
+  /** Per-category rollup of `extensions` */
+  categories: Record<string, ExtensionStat>;
+  /** Files plus folders */
+  total_inodes: number;
+  /** Allocated over logical size, when the scan collected allocated bytes */
+  allocation_ratio?: number;
 }
 
 // --- Scan Progress (#1 - Progressive scan) ---
@@ -26,6 +66,8 @@ export interface ScanProgress {
   files_scanned: number;
   folders_scanned: number;
   bytes_scanned: number;
+  /** Path of the entry processed just before this update */
+  current_path?: string;
 }
 
 // --- Time-Travel Analytics Types ---
@@ -37,6 +79,7 @@ export interface AgentSnapshot {
   total_size_bytes: number;
   file_count: number;
   top_extensions: [string, number, number][]; // [extension, size, count]
+  snapshot_id?: string; // Client-generated UUID; retries upsert the same record
 }
 
 export interface ExtensionDelta {
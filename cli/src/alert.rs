@@ -0,0 +1,183 @@
+//! Threshold alerts evaluated against the final scan stats (`--alert-if`).
+//!
+//! Grammar:
+//!
+//! ```text
+//! alert  := metric op value
+//! metric := total_bytes | total_files | total_folders
+//!         | ext:<name>          (bytes in files with that extension)
+//!         | ext_count:<name>    (number of files with that extension)
+//! op     := > | >= | < | <=
+//! value  := number [unit]       (unit: B, KB, MB, GB, TB or KiB, MiB, GiB, TiB;
+//!                                decimal units are powers of 1000)
+//! ```
+//!
+//! Extension names match case-insensitively, e.g. `ext:log>10GB`.
+
+use spectra_core::ScanStats;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Metric {
+    TotalBytes,
+    TotalFiles,
+    TotalFolders,
+    ExtBytes(String),
+    ExtCount(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+/// One parsed `--alert-if` expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertRule {
+    source: String,
+    metric: Metric,
+    comparison: Comparison,
+    threshold: u64,
+}
+
+impl AlertRule {
+    /// Returns a description of the breach if the rule fires for `stats`.
+    pub fn check(&self, stats: &ScanStats) -> Option<String> {
+        let actual = match &self.metric {
+            Metric::TotalBytes => stats.total_size_bytes,
+            Metric::TotalFiles => stats.total_files,
+            Metric::TotalFolders => stats.total_folders,
+            Metric::ExtBytes(ext) => extension_total(stats, ext, |s| s.size),
+            Metric::ExtCount(ext) => extension_total(stats, ext, |s| s.count),
+        };
+        let fired = match self.comparison {
+            Comparison::Greater => actual > self.threshold,
+            Comparison::GreaterOrEqual => actual >= self.threshold,
+            Comparison::Less => actual < self.threshold,
+            Comparison::LessOrEqual => actual <= self.threshold,
+        };
+        fired.then(|| format!("{} (actual: {})", self.source, actual))
+    }
+}
+
+impl fmt::Display for AlertRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn extension_total(
+    stats: &ScanStats,
+    ext: &str,
+    field: impl Fn(&spectra_core::ExtensionStat) -> u64,
+) -> u64 {
+    stats
+        .extensions
+        .iter()
+        .filter(|(key, _)| key.eq_ignore_ascii_case(ext))
+        .map(|(_, stat)| field(stat))
+        .sum()
+}
+
+impl FromStr for AlertRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let source: String = s.split_whitespace().collect();
+        let (op_at, op_len, comparison) = [
+            (">=", Comparison::GreaterOrEqual),
+            ("<=", Comparison::LessOrEqual),
+            (">", Comparison::Greater),
+            ("<", Comparison::Less),
+        ]
+        .into_iter()
+        .find_map(|(op, cmp)| source.find(op).map(|at| (at, op.len(), cmp)))
+        .ok_or_else(|| format!("'{}': expected one of >, >=, <, <=", s))?;
+
+        let metric = match &source[..op_at] {
+            "total_bytes" => Metric::TotalBytes,
+            "total_files" => Metric::TotalFiles,
+            "total_folders" => Metric::TotalFolders,
+            other => match other.split_once(':') {
+                Some(("ext", ext)) if !ext.is_empty() => Metric::ExtBytes(ext.to_string()),
+                Some(("ext_count", ext)) if !ext.is_empty() => Metric::ExtCount(ext.to_string()),
+                _ => return Err(format!("'{}': unknown metric '{}'", s, other)),
+            },
+        };
+        let threshold = parse_quantity(&source[op_at + op_len..])
+            .ok_or_else(|| format!("'{}': invalid threshold", s))?;
+
+        Ok(Self {
+            source,
+            metric,
+            comparison,
+            threshold,
+        })
+    }
+}
+
+/// `value_parser` for size flags such as `--hash-min-size 100MB`.
+pub(crate) fn parse_size_arg(s: &str) -> Result<u64, String> {
+    parse_quantity(s.trim()).ok_or_else(|| format!("'{}': expected a size like 100MB or 1GiB", s))
+}
+
+/// Parse `500GB`, `1.5TiB`, `10000` and the like into a plain count/bytes.
+pub(crate) fn parse_quantity(s: &str) -> Option<u64> {
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier: f64 = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1.0,
+        "kb" => 1e3,
+        "mb" => 1e6,
+        "gb" => 1e9,
+        "tb" => 1e12,
+        "kib" => 1024.0,
+        "mib" => 1024f64.powi(2),
+        "gib" => 1024f64.powi(3),
+        "tib" => 1024f64.powi(4),
+        _ => return None,
+    };
+    Some((number * multiplier).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectra_core::testing::stats_with_extensions;
+
+    fn stats() -> ScanStats {
+        ScanStats {
+            total_files: 1200,
+            total_size_bytes: 600_000_000_000,
+            ..stats_with_extensions(&[("log", 1000, 12_000_000_000)])
+        }
+    }
+
+    #[test]
+    fn test_exceeded_threshold_fires_and_unmet_does_not() {
+        let stats = stats();
+        let fired: AlertRule = "total_bytes > 500GB".parse().unwrap();
+        assert!(fired.check(&stats).is_some());
+
+        let quiet: AlertRule = "ext:log>20GB".parse().unwrap();
+        assert!(quiet.check(&stats).is_none());
+
+        let by_count: AlertRule = "ext_count:LOG>=1000".parse().unwrap();
+        assert!(by_count.check(&stats).is_some());
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_expressions() {
+        assert!("total_bytes 500GB".parse::<AlertRule>().is_err());
+        assert!("disk_free>1GB".parse::<AlertRule>().is_err());
+        assert!("total_bytes>lots".parse::<AlertRule>().is_err());
+        assert_eq!(parse_quantity("1.5KiB"), Some(1536));
+    }
+}
@@ -2,20 +2,23 @@
 ///
 /// This module provides tiered content analysis capabilities:
 /// - Tier 0: Metadata (size, path, extension) - handled in main.rs
-/// - Tier 1: Heuristics (entropy, filename patterns) - this module
+/// - Tier 1: Heuristics (entropy, filename patterns) - `spectra_core::analysis`
 /// - Tier 2: Semantic (AI-based content classification) - optional feature
 /// - Tier 3: Statistical outlier detection (IQR-based) - outliers module
 ///
-/// All analysis is performed on file headers only (max 8KB) to maintain
-/// the "zero-latency" performance characteristic of Spectra.
-pub mod entropy;
-pub mod heuristics;
+/// Entropy and classification read file headers only (max 8KB) to maintain
+/// the "zero-latency" performance characteristic of Spectra. The exceptions
+/// are the per-file block entropy profile and `--deep-entropy`, which stream
+/// the whole of each analyzed top file.
 pub mod outliers;
 pub mod semantic;
 
 // Re-export commonly used types
-pub use entropy::calculate_shannon_entropy;
-pub use heuristics::{analyze_filename_risk, RiskLevel};
 pub use outliers::detect_outliers;
 #[allow(unused_imports)] // Part of public API, used by external consumers
 pub use semantic::{ContentTags, SemanticEngine};
+pub use spectra_core::analysis::{
+    assess_blocks, assess_encryption, assess_with, average_entropy_by_extension,
+    calculate_shannon_entropy, entropy_profile, load_risk_rules, RiskRuleSet,
+    EXTENSION_ENTROPY_SAMPLES, MIN_ENTROPY_BLOCK_SIZE,
+};
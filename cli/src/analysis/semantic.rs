@@ -2,20 +2,32 @@
 use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
 use std::path::Path;
 
+#[cfg(feature = "semantic")]
+use spectra_core::analysis::is_likely_text;
 #[cfg(feature = "semantic")]
 use std::fs::File;
 #[cfg(feature = "semantic")]
 use std::io::Read;
 
+/// Default confidence floor for reporting a label.
+pub const DEFAULT_MIN_CONFIDENCE: f64 = 0.5;
+/// Default number of labels kept per file.
+pub const DEFAULT_TOP_K: usize = 3;
+
 #[derive(Debug, Default, Clone)]
 pub struct ContentTags {
     pub category: String, // e.g., "Contract", "Code", "Invoice"
     pub confidence: f64,
+    /// Up to top-K labels at or above the confidence floor, best first.
+    /// `category`/`confidence` mirror the first entry.
+    pub candidates: Vec<(String, f64)>,
 }
 
 pub struct SemanticEngine {
     #[cfg(feature = "semantic")]
     model: Option<ZeroShotClassificationModel>,
+    min_confidence: f64,
+    top_k: usize,
 }
 
 impl SemanticEngine {
@@ -28,17 +40,36 @@ impl SemanticEngine {
             if model.is_none() {
                 eprintln!("⚠️  Warning: Failed to load ML model. Semantic analysis disabled.");
             }
-            return Self { model };
+            return Self {
+                model,
+                min_confidence: DEFAULT_MIN_CONFIDENCE,
+                top_k: DEFAULT_TOP_K,
+            };
         }
 
         #[cfg(not(feature = "semantic"))]
-        Self {}
+        Self {
+            min_confidence: DEFAULT_MIN_CONFIDENCE,
+            top_k: DEFAULT_TOP_K,
+        }
+    }
+
+    /// Drop labels scoring below `min_confidence` (default 0.5).
+    pub fn with_min_confidence(mut self, min_confidence: f64) -> Self {
+        self.min_confidence = min_confidence;
+        self
+    }
+
+    /// Keep at most `top_k` labels per file (default 3).
+    pub fn with_top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k.max(1);
+        self
     }
 
     pub fn classify(&self, path: &Path) -> Option<ContentTags> {
         #[cfg(not(feature = "semantic"))]
         {
-            let _ = path; // Suppress unused variable warning
+            let _ = (path, self.min_confidence, self.top_k); // Suppress unused warnings
             None
         }
 
@@ -57,18 +88,11 @@ impl SemanticEngine {
                 return None;
             }
 
-            // 2. Decode (Lossy to handle binary/text mix)
-            let text_sample = String::from_utf8_lossy(&buffer[..n]);
-
-            // Skip if the sample is mostly non-text (binary)
-            if text_sample
-                .chars()
-                .filter(|c| c.is_control() && *c != '\n' && *c != '\r' && *c != '\t')
-                .count()
-                > text_sample.len() / 10
-            {
+            // 2. Skip binary samples, then decode (lossy for stray bytes)
+            if !is_likely_text(&buffer[..n]) {
                 return None;
             }
+            let text_sample = String::from_utf8_lossy(&buffer[..n]);
 
             // 3. Define Candidate Labels
             let candidate_labels = vec![
@@ -81,21 +105,26 @@ impl SemanticEngine {
                 "documentation",
             ];
 
-            // 4. Predict
-            match model.predict(&[text_sample.as_ref()], &candidate_labels, None, 128) {
-                Ok(predictions) => {
-                    if let Some(result) = predictions.first() {
-                        // The result structure from rust-bert contains labels with scores
-                        // We take the highest scoring label
-                        return Some(ContentTags {
-                            category: result.text.clone(),
-                            confidence: result.score as f64,
-                        });
-                    }
-                    None
-                }
-                Err(_) => None,
-            }
+            // 4. Predict. Multi-label mode scores every label independently,
+            // which lets runner-up classifications surface.
+            let mut predictions = model
+                .predict_multilabel(&[text_sample.as_ref()], &candidate_labels, None, 128)
+                .ok()?;
+            let mut labels = predictions.pop()?;
+            labels.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+            let candidates: Vec<(String, f64)> = labels
+                .into_iter()
+                .filter(|label| label.score >= self.min_confidence)
+                .take(self.top_k)
+                .map(|label| (label.text, label.score))
+                .collect();
+            let (category, confidence) = candidates.first().cloned()?;
+            Some(ContentTags {
+                category,
+                confidence,
+                candidates,
+            })
         }
     }
 }
@@ -125,4 +154,18 @@ mod tests {
             assert!(result.is_none());
         }
     }
+
+    #[test]
+    fn test_candidates_empty_without_semantic_feature() {
+        #[cfg(not(feature = "semantic"))]
+        {
+            let engine = SemanticEngine::new().with_min_confidence(0.2).with_top_k(5);
+            let candidates = engine
+                .classify(Path::new("test.txt"))
+                .map(|tags| tags.candidates)
+                .unwrap_or_default();
+            assert!(candidates.is_empty());
+            assert!(ContentTags::default().candidates.is_empty());
+        }
+    }
 }
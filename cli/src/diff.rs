@@ -0,0 +1,88 @@
+//! `spectra-cli diff`: compare two saved `--json` reports.
+
+use anyhow::{Context, Result};
+use humansize::{format_size, DECIMAL};
+use spectra_core::{ScanDiff, ScanStats};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Width of the bar for the largest extension change.
+const BAR_WIDTH: usize = 30;
+
+/// Load a report written by `spectra-cli --json`.
+pub fn load_report(path: &Path) -> Result<ScanStats> {
+    let data = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read report {}", path.display()))?;
+    serde_json::from_str(&data)
+        .with_context(|| format!("{} is not a Spectra JSON report", path.display()))
+}
+
+/// A report's root, followed by its label if it has one.
+pub fn describe(stats: &ScanStats) -> String {
+    match &stats.label {
+        Some(label) => format!("{} [{}]", stats.root_path, label),
+        None => stats.root_path.clone(),
+    }
+}
+
+/// Human-readable diff with ASCII bars sized relative to the largest
+/// extension change (`+` for growth, `-` for shrinkage).
+pub fn render(diff: &ScanDiff) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "------------------------------------------------");
+    let _ = writeln!(out, "💾 Size   : {}", signed_size(diff.total_size_delta));
+    let _ = writeln!(out, "📄 Files  : {:+}", diff.total_files_delta);
+    let _ = writeln!(out, "📁 Folders: {:+}", diff.total_folders_delta);
+    let _ = writeln!(out, "------------------------------------------------");
+
+    if diff.extensions.is_empty() {
+        let _ = writeln!(out, "No extension changes.");
+        return out;
+    }
+
+    let _ = writeln!(out, "📊 Extension Changes:");
+    let largest = diff.extensions[0].size_delta.unsigned_abs().max(1);
+    for ext in &diff.extensions {
+        let magnitude = ext.size_delta.unsigned_abs();
+        // Round up so any non-zero change gets at least one mark.
+        let len = ((magnitude as u128 * BAR_WIDTH as u128).div_ceil(largest as u128)) as usize;
+        let mark = if ext.size_delta < 0 { "-" } else { "+" };
+        let _ = writeln!(
+            out,
+            "   .{:<6} {:<width$} {:>12} ({:+} files)",
+            ext.extension,
+            mark.repeat(len),
+            signed_size(ext.size_delta),
+            ext.count_delta,
+            width = BAR_WIDTH
+        );
+    }
+    out
+}
+
+fn signed_size(bytes: i64) -> String {
+    let sign = if bytes < 0 { "-" } else { "+" };
+    format!("{}{}", sign, format_size(bytes.unsigned_abs(), DECIMAL))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectra_core::testing::stats_with_extensions as report;
+
+    #[test]
+    fn test_render_draws_full_bar_for_largest_change() {
+        let old = report(&[("jpg", 10, 1_000_000), ("log", 50, 500_000)]);
+        let new = report(&[("jpg", 30, 3_300_000), ("log", 10, 100_000)]);
+
+        let output = render(&old.diff(&new));
+        let jpg = output.lines().find(|l| l.contains(".jpg")).unwrap();
+        assert!(jpg.contains(&"+".repeat(BAR_WIDTH)));
+        assert!(jpg.contains("+2.30 MB"));
+
+        let log = output.lines().find(|l| l.contains(".log")).unwrap();
+        assert!(log.contains("-----"));
+        assert!(!log.contains("++"));
+        assert!(log.contains("-400 kB"));
+    }
+}
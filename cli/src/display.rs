@@ -0,0 +1,66 @@
+//! Terminal-safe rendering of file names.
+//!
+//! Filenames may contain newlines, tabs, ANSI escape sequences or Unicode
+//! bidi overrides. Printed verbatim they can break the report layout or
+//! spoof output, so the human report shows them escaped (similar to
+//! `ls -b`). JSON output is already escaped by the serializer.
+
+use std::borrow::Cow;
+use std::fmt::Write;
+
+/// Replace control and bidi-override characters with visible escapes:
+/// `\n`, `\t`, `\r`, `\e`, `\xNN` or `\u{NNNN}`. Backslashes are left alone
+/// so ordinary Windows paths stay readable.
+pub fn escape_control(s: &str) -> Cow<'_, str> {
+    if !s.chars().any(needs_escape) {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len() + 8);
+    for c in s.chars() {
+        match c {
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\x1b' => out.push_str("\\e"),
+            c if needs_escape(c) && (c as u32) < 0x100 => {
+                let _ = write!(out, "\\x{:02x}", c as u32);
+            }
+            c if needs_escape(c) => {
+                let _ = write!(out, "\\u{{{:04x}}}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// `path` as the human output shows it: escaped with [`escape_control`],
+/// or verbatim with `--raw-paths`.
+pub fn path(path: &str, raw: bool) -> Cow<'_, str> {
+    if raw {
+        Cow::Borrowed(path)
+    } else {
+        escape_control(path)
+    }
+}
+
+fn needs_escape(c: char) -> bool {
+    c.is_control() || matches!(c, '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ansi_escape_is_neutralized() {
+        let name = "/data/evil\x1b[2J\x1b[31mname\n.txt";
+        let shown = escape_control(name);
+
+        assert_eq!(shown, "/data/evil\\e[2J\\e[31mname\\n.txt");
+        assert!(!shown.chars().any(|c| c.is_control()));
+        assert_eq!(escape_control("/plain/path.txt"), "/plain/path.txt");
+        assert_eq!(escape_control("a\u{202e}b\x07"), "a\\u{202e}b\\x07");
+        assert_eq!(escape_control(r"C:\Users\a.txt"), r"C:\Users\a.txt");
+    }
+}
@@ -0,0 +1,83 @@
+//! Structured error output for `--json` runs.
+//!
+//! On failure a JSON run prints `{"error": {"kind": ..., "message": ...}}` to
+//! stdout instead of anyhow's free-form stderr text, so scripts can branch on
+//! `kind` without scraping messages.
+
+use serde::Serialize;
+use std::io;
+
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    pub error: ErrorBody,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorBody {
+    /// Stable category: `not_found`, `permission_denied`, `network`,
+    /// `invalid_data`, `io` or `other`.
+    pub kind: &'static str,
+    /// The outermost error message.
+    pub message: String,
+    /// Underlying causes, outermost first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub causes: Vec<String>,
+}
+
+impl ErrorReport {
+    pub fn from_error(err: &anyhow::Error) -> Self {
+        Self {
+            error: ErrorBody {
+                kind: classify(err),
+                message: err.to_string(),
+                causes: err.chain().skip(1).map(ToString::to_string).collect(),
+            },
+        }
+    }
+}
+
+/// Pick a category from the first recognisable error in the chain.
+fn classify(err: &anyhow::Error) -> &'static str {
+    for cause in err.chain() {
+        if let Some(io_err) = cause.downcast_ref::<io::Error>() {
+            return match io_err.kind() {
+                io::ErrorKind::NotFound => "not_found",
+                io::ErrorKind::PermissionDenied => "permission_denied",
+                io::ErrorKind::InvalidData => "invalid_data",
+                _ => "io",
+            };
+        }
+        if cause.is::<reqwest::Error>() {
+            return "network";
+        }
+        if cause.is::<serde_json::Error>() || cause.is::<rusqlite::Error>() {
+            return "invalid_data";
+        }
+    }
+    "other"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paths_file::ScanTargets;
+    use spectra_core::Scanner;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_nonexistent_path_renders_parseable_error() {
+        let targets = ScanTargets {
+            roots: vec![PathBuf::from("/definitely/not/a/spectra/root")],
+            excludes: Vec::new(),
+        };
+        let err = targets.scan(10, |root| Scanner::new(root, 10)).unwrap_err();
+
+        let json = serde_json::to_string(&ErrorReport::from_error(&err)).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["error"]["kind"], "not_found");
+        assert!(parsed["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("/definitely/not/a/spectra/root"));
+    }
+}
@@ -0,0 +1,105 @@
+//! Exclusions read from a file (`--exclude-paths-from`).
+//!
+//! Each non-empty, non-`#` line is a path or a glob to prune. Plain paths
+//! may be absolute or relative to the scan root; lines containing `*`, `?`
+//! or `[` are gitignore-style globs, handled like `--exclude`:
+//!
+//! ```text
+//! # kept in version control next to the scan job
+//! /srv/projects/scratch
+//! build/cache
+//! *.tmp
+//! node_modules/
+//! ```
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use spectra_core::Scanner;
+use std::path::{Path, PathBuf};
+
+/// Paths and globs declared in an exclude file (or a project manifest's
+/// `[ignore]` table).
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExcludeList {
+    /// Absolute, or relative to the scan root.
+    pub paths: Vec<PathBuf>,
+    pub globs: Vec<String>,
+}
+
+impl ExcludeList {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read exclude file {}", path.display()))?;
+        Ok(Self::parse(&contents))
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let mut list = Self::default();
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.contains(['*', '?', '[']) {
+                list.globs.push(line.to_string());
+            } else {
+                list.paths.push(PathBuf::from(line));
+            }
+        }
+        list
+    }
+
+    /// Add the exclusions to the scanner for `root`, resolving relative
+    /// paths against it.
+    pub fn apply(&self, scanner: Scanner, root: &Path) -> Scanner {
+        scanner
+            .exclude_paths(self.paths.iter().map(|path| root.join(path)))
+            .exclude(self.globs.iter().cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_splits_paths_and_globs() {
+        let list = ExcludeList::parse("# comment\n\n/abs/dir\n  rel/dir  \n*.tmp\nlogs/[0-9]*\n");
+        assert_eq!(
+            list.paths,
+            vec![PathBuf::from("/abs/dir"), PathBuf::from("rel/dir")]
+        );
+        assert_eq!(list.globs, vec!["*.tmp", "logs/[0-9]*"]);
+    }
+
+    #[test]
+    fn test_listed_subdirs_are_skipped() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("root");
+        std::fs::create_dir_all(root.join("keep")).unwrap();
+        std::fs::create_dir_all(root.join("skip/deeper")).unwrap();
+        std::fs::create_dir_all(root.join("vendor")).unwrap();
+        std::fs::write(root.join("keep/a.txt"), vec![0u8; 10]).unwrap();
+        std::fs::write(root.join("keep/scratch.tmp"), vec![0u8; 20]).unwrap();
+        std::fs::write(root.join("skip/deeper/b.bin"), vec![0u8; 1000]).unwrap();
+        std::fs::write(root.join("vendor/c.bin"), vec![0u8; 500]).unwrap();
+
+        let file = dir.path().join("excludes.txt");
+        std::fs::write(
+            &file,
+            format!(
+                "# big trees\nskip\n{}\n*.tmp\n",
+                root.join("vendor").display()
+            ),
+        )
+        .unwrap();
+
+        let list = ExcludeList::load(&file).unwrap();
+        let stats = list.apply(Scanner::new(&root, 10), &root).scan().unwrap();
+        assert_eq!(stats.total_files, 1);
+        assert_eq!(stats.total_size_bytes, 10);
+        // Only `keep` is left; pruned directories aren't counted either.
+        assert_eq!(stats.total_folders, 2);
+    }
+}
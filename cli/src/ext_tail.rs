@@ -0,0 +1,118 @@
+//! Roll the long tail of rare extensions into one `other` entry for display
+//! (`--ext-tail-threshold`). The scan data itself is left untouched.
+
+use crate::alert::parse_quantity;
+use spectra_core::ExtensionStat;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Key of the entry that collects the tail.
+pub const OTHER_KEY: &str = "other";
+
+/// Extensions smaller than this are grouped into [`OTHER_KEY`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TailThreshold {
+    /// Absolute size, e.g. `10MB`.
+    Bytes(u64),
+    /// Share of the total scanned size, e.g. `1%`.
+    Percent(f64),
+}
+
+impl FromStr for TailThreshold {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(pct) = s.strip_suffix('%') {
+            return match pct.trim().parse::<f64>() {
+                Ok(p) if (0.0..=100.0).contains(&p) => Ok(Self::Percent(p)),
+                _ => Err(format!(
+                    "'{}': expected a percentage between 0% and 100%",
+                    s
+                )),
+            };
+        }
+        parse_quantity(s)
+            .map(Self::Bytes)
+            .ok_or_else(|| format!("'{}': expected a size like 10MB or a percentage like 1%", s))
+    }
+}
+
+impl TailThreshold {
+    fn min_bytes(&self, total_size: u64) -> u64 {
+        match *self {
+            Self::Bytes(bytes) => bytes,
+            Self::Percent(pct) => (total_size as f64 * pct / 100.0).ceil() as u64,
+        }
+    }
+
+    /// Merge every extension below the threshold into `other`, summing counts
+    /// and sizes. Returns how many extensions were folded in.
+    pub fn collapse(
+        &self,
+        extensions: &mut HashMap<String, ExtensionStat>,
+        total_size: u64,
+    ) -> usize {
+        let min = self.min_bytes(total_size);
+        let tail: Vec<String> = extensions
+            .iter()
+            .filter(|(ext, stat)| stat.size < min && ext.as_str() != OTHER_KEY)
+            .map(|(ext, _)| ext.clone())
+            .collect();
+        if tail.is_empty() {
+            return 0;
+        }
+        let mut other = extensions.remove(OTHER_KEY).unwrap_or_default();
+        for ext in &tail {
+            if let Some(stat) = extensions.remove(ext) {
+                other.count += stat.count;
+                other.size += stat.size;
+            }
+        }
+        // Per-extension averages don't combine meaningfully.
+        other.avg_entropy = None;
+        extensions.insert(OTHER_KEY.to_string(), other);
+        tail.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stat(count: u64, size: u64) -> ExtensionStat {
+        ExtensionStat {
+            count,
+            size,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_tail_rolls_into_other() {
+        let mut exts = HashMap::from([
+            ("mp4".to_string(), stat(2, 9_000)),
+            ("log".to_string(), stat(5, 800)),
+            ("ini".to_string(), stat(3, 120)),
+            ("bak".to_string(), stat(1, 80)),
+        ]);
+
+        let folded = "5%"
+            .parse::<TailThreshold>()
+            .unwrap()
+            .collapse(&mut exts, 10_000);
+
+        assert_eq!(folded, 2);
+        assert_eq!(exts["mp4"].size, 9_000);
+        assert_eq!(exts["log"].size, 800);
+        assert_eq!(exts[OTHER_KEY].size, 200);
+        assert_eq!(exts[OTHER_KEY].count, 4);
+        assert!(!exts.contains_key("ini") && !exts.contains_key("bak"));
+
+        let mut by_size = HashMap::from([("a".to_string(), stat(1, 10))]);
+        TailThreshold::Bytes(1_000).collapse(&mut by_size, 10);
+        assert_eq!(by_size[OTHER_KEY].size, 10);
+        assert!("150%".parse::<TailThreshold>().is_err());
+        assert_eq!("1KB".parse(), Ok(TailThreshold::Bytes(1_000)));
+    }
+}
@@ -0,0 +1,69 @@
+//! Audit trail of governance actions (`--audit-log`).
+//!
+//! Every action a run takes, or in a dry run would take, is appended to a
+//! JSONL file as one [`AuditEntry`], so enforce runs leave a durable record
+//! after their output has scrolled away. Unlike the transaction log, the
+//! audit log covers every action and is never used to undo anything.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// One action on one file, as recorded in the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditEntry {
+    pub timestamp: i64,
+    pub policy: String,
+    /// Action name, e.g. `Delete` or `Archive`.
+    pub action: String,
+    pub path: String,
+    /// Size before the action ran; 0 if it couldn't be read.
+    pub size_bytes: u64,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub dry_run: bool,
+}
+
+impl AuditEntry {
+    pub fn new(policy: &str, action: &str, path: &Path, size_bytes: u64, dry_run: bool) -> Self {
+        Self {
+            timestamp: Utc::now().timestamp(),
+            policy: policy.to_string(),
+            action: action.to_string(),
+            path: path.display().to_string(),
+            size_bytes,
+            success: true,
+            error: None,
+            dry_run,
+        }
+    }
+
+    pub fn with_result(mut self, result: Result<(), String>) -> Self {
+        self.success = result.is_ok();
+        self.error = result.err();
+        self
+    }
+}
+
+/// An audit log kept open for the length of a run.
+pub struct AuditLog {
+    file: File,
+}
+
+impl AuditLog {
+    /// Open (or create) the log at `path` in append mode.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file })
+    }
+
+    /// Append `entry` as one line.
+    pub fn record(&mut self, entry: &AuditEntry) -> io::Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        self.file.write_all(&line)
+    }
+}
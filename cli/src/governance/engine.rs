@@ -1,33 +1,177 @@
-use serde::Deserialize;
-use std::path::Path;
+use super::transaction::{move_file, TransactionLog};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use humansize::{format_size, DECIMAL};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use spectra_core::analysis::calculate_shannon_entropy;
+use spectra_core::hash::hash_file;
+use spectra_core::{category, CaseSensitivity};
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Component, Path, PathBuf};
 
-#[derive(Debug, Deserialize, Clone)]
+/// Extensions outside the `archives` category whose formats are already
+/// compressed, so gzipping them again gains next to nothing.
+const COMPRESSED_EXTENSIONS: &[&str] = &[
+    "lz4", "lzma", "br", "z", "jar", "apk", "docx", "xlsx", "pptx", "jpg", "jpeg", "png", "webp",
+    "mp3", "mp4", "mkv", "mov",
+];
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub enum Action {
     Report,
+    /// Permanent removal. Enforce runs turn this into [`Action::Trash`]
+    /// unless `--hard-delete` (or `--txn-log`) is given.
     Delete,
-    Archive { target_path: String },
+    /// Move the file to the OS recycle bin, so a bad rule can be undone.
+    Trash,
+    Archive {
+        target_path: String,
+    },
+    /// Gzip the file to `<path>.gz`, removing the original unless
+    /// `keep_original` is set. Already-compressed files are skipped.
+    Compress {
+        #[serde(default)]
+        keep_original: bool,
+    },
+    /// Within each group of identical matched files, remove every copy but
+    /// the one `keep` selects, optionally leaving a hard link to it in each
+    /// removed copy's place. Applied per group by [`deduplicate`].
+    Deduplicate {
+        keep: KeepPolicy,
+        #[serde(default)]
+        hardlink: bool,
+    },
+}
+
+impl Action {
+    /// The variant name, without its fields, e.g. `Archive`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Report => "Report",
+            Action::Delete => "Delete",
+            Action::Trash => "Trash",
+            Action::Archive { .. } => "Archive",
+            Action::Compress { .. } => "Compress",
+            Action::Deduplicate { .. } => "Deduplicate",
+        }
+    }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// Which copy of a duplicate group [`Action::Deduplicate`] keeps.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum KeepPolicy {
+    /// Earliest modification time.
+    Oldest,
+    /// Latest modification time.
+    Newest,
+    /// Fewest characters in the path.
+    ShortestPath,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
 pub struct Rule {
     pub extension: Option<String>,
     pub min_size_bytes: Option<u64>,
     pub min_age_days: Option<u64>,
+    /// Only match files modified within this many days, e.g. 1 for "what
+    /// changed today". With `min_age_days`, matches a window.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u64>,
+    /// How `extension` is compared; follows the platform convention by default.
+    #[serde(default)]
+    pub case_sensitivity: CaseSensitivity,
+    /// Only match files whose full path (with the platform's separators)
+    /// matches this regex, e.g. `.*/cache/.*`. Combined with the other
+    /// conditions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_regex: Option<PathRegex>,
+}
+
+/// A [`Rule::path_regex`], compiled when the policy is loaded so that an
+/// invalid pattern fails the load instead of every evaluation.
+#[derive(Debug, Clone)]
+pub struct PathRegex(Regex);
+
+impl PathRegex {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Regex::new(pattern).map(Self)
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    pub fn is_match(&self, path: &Path) -> bool {
+        self.0.is_match(&path.to_string_lossy())
+    }
 }
 
-#[derive(Debug, Deserialize)]
+impl PartialEq for PathRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Serialize for PathRegex {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for PathRegex {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Self::new(&pattern).map_err(|e| {
+            serde::de::Error::custom(format!("invalid path_regex '{}': {}", pattern, e))
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
 pub struct Policy {
     pub name: String,
     pub rule: Rule,
     pub action: Action,
+    /// Disabled policies never match. Defaults to `true`.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Higher priorities are evaluated (and executed) first; ties keep their
+    /// load order. Defaults to 0.
+    #[serde(default)]
+    pub priority: i32,
+}
+
+fn default_enabled() -> bool {
+    true
 }
 
 impl Policy {
+    /// An enabled policy at the default priority (0).
+    pub fn new(name: impl Into<String>, rule: Rule, action: Action) -> Self {
+        Self {
+            name: name.into(),
+            rule,
+            action,
+            enabled: true,
+            priority: 0,
+        }
+    }
+
     pub fn evaluate(&self, path: &Path, metadata: &std::fs::Metadata) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
         // 1. Check Extension
         if let Some(target_ext) = &self.rule.extension {
             if let Some(ext) = path.extension() {
-                if ext.to_string_lossy().to_lowercase() != *target_ext {
+                if !self
+                    .rule
+                    .case_sensitivity
+                    .matches(&ext.to_string_lossy(), target_ext)
+                {
                     return false;
                 }
             } else {
@@ -35,20 +179,36 @@ impl Policy {
             }
         }
 
-        // 2. Check Size
+        // 2. Check Path
+        if let Some(regex) = &self.rule.path_regex {
+            if !regex.is_match(path) {
+                return false;
+            }
+        }
+
+        // 3. Check Size
         if let Some(min_size) = self.rule.min_size_bytes {
             if metadata.len() < min_size {
                 return false;
             }
         }
 
-        // 3. Check Age
-        if let Some(days) = self.rule.min_age_days {
-            if let Ok(modified) = metadata.modified() {
-                if let Ok(elapsed) = modified.elapsed() {
-                    if elapsed.as_secs() < days * 86400 {
-                        return false; // Too young
-                    }
+        // 4. Check Age
+        if self.rule.min_age_days.is_some() || self.rule.max_age_days.is_some() {
+            // A file without a readable mtime has no age to compare, and a
+            // future mtime counts as brand new.
+            let Ok(modified) = metadata.modified() else {
+                return false;
+            };
+            let age_secs = modified.elapsed().map_or(0, |elapsed| elapsed.as_secs());
+            if let Some(days) = self.rule.min_age_days {
+                if age_secs < days * 86400 {
+                    return false; // Too young
+                }
+            }
+            if let Some(days) = self.rule.max_age_days {
+                if age_secs >= days * 86400 {
+                    return false; // Too old
                 }
             }
         }
@@ -56,25 +216,285 @@ impl Policy {
         true // All conditions met
     }
 
-    pub fn execute(&self, path: &Path, dry_run: bool) {
+    /// Execute the action on `path`, routing `Delete` and `Trash` through a
+    /// transaction log when one is given so the file stays recoverable by
+    /// `govern-rollback` during the grace period, and archiving `path` under its path relative to the scan
+    /// `root`. Returns the error message of a failed action, which has
+    /// already been printed.
+    pub fn execute_with_txn(
+        &self,
+        path: &Path,
+        root: &Path,
+        dry_run: bool,
+        txn: Option<&mut TransactionLog>,
+    ) -> Result<(), String> {
         if dry_run {
-            println!("[DRY RUN] Would execute {:?} on {:?}", self.action, path);
-            return;
+            match &self.action {
+                Action::Compress { .. } if is_compressed(path) => {
+                    println!("[DRY RUN] Would skip {:?} (already compressed)", path)
+                }
+                Action::Compress { .. } => println!(
+                    "[DRY RUN] Would compress {:?} (~{} estimated savings)",
+                    path,
+                    format_size(estimated_gzip_savings(path), DECIMAL)
+                ),
+                _ => println!("[DRY RUN] Would execute {:?} on {:?}", self.action, path),
+            }
+            return Ok(());
         }
 
+        let failed = |verb: &str, e: &dyn std::fmt::Display| {
+            eprintln!("❌ Failed to {} {:?}: {}", verb, path, e);
+            Err(e.to_string())
+        };
         match &self.action {
             Action::Report => println!("🚩 Violation: {:?} matches '{}'", path, self.name),
-            Action::Delete => {
-                // SAFETY: Double check before deletion in production code!
-                match std::fs::remove_file(path) {
-                    Ok(_) => println!("🗑️ Deleted: {:?}", path),
-                    Err(e) => eprintln!("❌ Failed to delete {:?}: {}", path, e),
+            Action::Delete | Action::Trash => match (txn, &self.action) {
+                (Some(log), _) => match log.delete(path, &self.name) {
+                    Ok(_) => println!("🗑️ Deleted (recoverable): {:?}", path),
+                    Err(e) => return failed("delete", &e),
+                },
+                (None, Action::Trash) => match trash::delete(path) {
+                    Ok(()) => println!("🗑️ Moved to trash: {:?}", path),
+                    Err(e) => return failed("trash", &e),
+                },
+                (None, _) => {
+                    // SAFETY: Double check before deletion in production code!
+                    match std::fs::remove_file(path) {
+                        Ok(_) => println!("🗑️ Deleted: {:?}", path),
+                        Err(e) => return failed("delete", &e),
+                    }
                 }
-            }
+            },
             Action::Archive { target_path } => {
-                println!("📦 Archiving {:?} to {}", path, target_path);
-                // Implementation: Move file to target_path
+                match archive_file(path, root, Path::new(target_path)) {
+                    Ok(dest) => println!("📦 Archived {:?} to {:?}", path, dest),
+                    Err(e) => return failed("archive", &e),
+                }
             }
+            Action::Compress { keep_original } => {
+                if is_compressed(path) {
+                    println!("⏭️  Skipped {:?} (already compressed)", path);
+                    return Ok(());
+                }
+                match compress_file(path, *keep_original) {
+                    Ok(gz) => println!("🗜️ Compressed {:?} to {:?}", path, gz),
+                    Err(e) => return failed("compress", &e),
+                }
+            }
+            // Needs the whole duplicate group; see `deduplicate`.
+            Action::Deduplicate { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+/// Remove every copy in `group` (paths with identical contents) except the
+/// one `keep` selects, returning each other copy with its outcome. Nothing
+/// is removed unless each copy still hashes the same as the kept file, which
+/// must still exist, so the last copy can never be lost; a copy that differs
+/// or can't be read is reported and left alone. Removals go through `txn`
+/// when given, otherwise to the OS trash unless `hard_delete` is set, and
+/// with `hardlink` each removed copy is replaced by a hard link to the kept
+/// file. Links are skipped while `txn` is active, since a file at the
+/// vacated path would stop rollback restoring the copy. `dry_run` only lists
+/// what would be removed.
+pub fn deduplicate(
+    group: &[PathBuf],
+    keep: KeepPolicy,
+    hardlink: bool,
+    dry_run: bool,
+    hard_delete: bool,
+    mut txn: Option<&mut TransactionLog>,
+    policy: &str,
+) -> Vec<(PathBuf, Result<(), String>)> {
+    let Some(kept) = kept_copy(group, keep) else {
+        return Vec::new();
+    };
+    let copies = group.iter().filter(|copy| *copy != kept);
+    let kept_hash = match hash_file(kept) {
+        Ok(hash) => hash,
+        Err(e) => {
+            eprintln!(
+                "❌ Failed to hash {:?}, keeping its duplicates: {}",
+                kept, e
+            );
+            let error = format!("failed to hash kept copy {:?}: {}", kept, e);
+            return copies
+                .map(|copy| (copy.clone(), Err(error.clone())))
+                .collect();
+        }
+    };
+
+    copies
+        .map(|copy| {
+            let result = if dry_run {
+                println!(
+                    "[DRY RUN] Would remove duplicate {:?} (keeping {:?})",
+                    copy, kept
+                );
+                Ok(())
+            } else {
+                remove_duplicate(
+                    copy,
+                    kept,
+                    &kept_hash,
+                    hardlink,
+                    hard_delete,
+                    &mut txn,
+                    policy,
+                )
+            };
+            (copy.clone(), result)
+        })
+        .collect()
+}
+
+fn remove_duplicate(
+    copy: &Path,
+    kept: &Path,
+    kept_hash: &str,
+    hardlink: bool,
+    hard_delete: bool,
+    txn: &mut Option<&mut TransactionLog>,
+    policy: &str,
+) -> Result<(), String> {
+    match hash_file(copy) {
+        Ok(hash) if hash == kept_hash && kept.exists() => {}
+        Ok(_) => {
+            eprintln!("⚠️  {:?} no longer matches {:?}; kept", copy, kept);
+            return Err(format!("no longer matches {:?}", kept));
+        }
+        Err(e) => {
+            eprintln!("❌ Failed to hash {:?}: {}", copy, e);
+            return Err(e.to_string());
         }
     }
+    let deleted = match txn.as_deref_mut() {
+        Some(log) => log.delete(copy, policy).map(drop),
+        None if hard_delete => std::fs::remove_file(copy),
+        None => trash::delete(copy).map_err(std::io::Error::other),
+    };
+    if let Err(e) = deleted {
+        eprintln!("❌ Failed to remove duplicate {:?}: {}", copy, e);
+        return Err(e.to_string());
+    }
+    println!("🧹 Removed duplicate {:?} (kept {:?})", copy, kept);
+    if hardlink && txn.is_some() {
+        eprintln!(
+            "⚠️  Not linking {:?}: the transaction log restores it on rollback",
+            copy
+        );
+    } else if hardlink {
+        if let Err(e) = std::fs::hard_link(kept, copy) {
+            eprintln!("❌ Failed to link {:?} to {:?}: {}", copy, kept, e);
+            return Err(format!("removed, but failed to link to {:?}: {}", kept, e));
+        }
+    }
+    Ok(())
+}
+
+/// The copy `keep` selects; ties go to the first path in sorted order.
+fn kept_copy(group: &[PathBuf], keep: KeepPolicy) -> Option<&PathBuf> {
+    let mut sorted: Vec<&PathBuf> = group.iter().collect();
+    sorted.sort();
+    let modified = |path: &Path| {
+        std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .unwrap_or(std::time::UNIX_EPOCH)
+    };
+    match keep {
+        KeepPolicy::Oldest => sorted.into_iter().min_by_key(|path| modified(path)),
+        KeepPolicy::Newest => sorted.into_iter().rev().max_by_key(|path| modified(path)),
+        KeepPolicy::ShortestPath => sorted.into_iter().min_by_key(|path| path.as_os_str().len()),
+    }
+}
+
+/// Whether `path`'s extension marks an already-compressed format.
+pub fn is_compressed(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        let ext = ext.to_string_lossy().to_ascii_lowercase();
+        category::category_of(&ext) == "archives" || COMPRESSED_EXTENSIONS.contains(&ext.as_str())
+    })
+}
+
+/// Rough bytes saved by gzipping `path`: its size scaled by how far the
+/// header's entropy falls short of 8 bits/byte. 0 if it can't be read.
+fn estimated_gzip_savings(path: &Path) -> u64 {
+    let size = std::fs::metadata(path).map_or(0, |meta| meta.len());
+    let entropy = calculate_shannon_entropy(path).unwrap_or(8.0);
+    (size as f64 * (1.0 - f64::from(entropy) / 8.0)) as u64
+}
+
+/// Gzip `path` to `<path>.gz` and return the new path. An existing `.gz` is
+/// never overwritten, and the original is removed (unless `keep_original`)
+/// only once the compressed copy is complete.
+pub fn compress_file(path: &Path, keep_original: bool) -> io::Result<PathBuf> {
+    let mut gz_name = path.as_os_str().to_os_string();
+    gz_name.push(".gz");
+    let gz_path = PathBuf::from(gz_name);
+
+    let output = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&gz_path)?;
+    let written = (|| {
+        let mut encoder = GzEncoder::new(output, Compression::default());
+        io::copy(&mut File::open(path)?, &mut encoder)?;
+        encoder.finish()?.sync_all()
+    })();
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&gz_path);
+        return Err(e);
+    }
+    if !keep_original {
+        std::fs::remove_file(path)?;
+    }
+    Ok(gz_path)
+}
+
+/// Move `path` into `target`, keeping its path relative to `root` (or just
+/// its name when it isn't under `root`), and return where it landed. Root,
+/// drive and `..` components are dropped, so the destination always stays
+/// inside `target`, even for an absolute `path` with an empty `root`.
+/// Missing directories are created, moves across filesystems fall back to
+/// copy + delete, and an existing file is never overwritten: the archived
+/// copy gets a numeric suffix instead (`report-1.txt`, `report-2.txt`, ...).
+pub fn archive_file(path: &Path, root: &Path, target: &Path) -> io::Result<PathBuf> {
+    let relative =
+        match path.strip_prefix(root) {
+            Ok(relative) if !relative.as_os_str().is_empty() => relative,
+            _ => Path::new(path.file_name().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "path has no file name")
+            })?),
+        };
+    let relative: PathBuf = relative
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect();
+    let dest = unused_path(&target.join(relative));
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    move_file(path, &dest)?;
+    Ok(dest)
+}
+
+/// `path`, or the first `<stem>-<n>.<ext>` beside it that doesn't exist.
+fn unused_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| path.with_file_name(format!("{}-{}{}", stem, n, ext)))
+        .find(|candidate| !candidate.exists())
+        .expect("unbounded suffix search")
 }
@@ -0,0 +1,127 @@
+//! Parallel policy evaluation.
+//!
+//! Evaluation (stat + rule checks) is read-only and runs on a rayon pool.
+//! The resulting [`PolicyOutcome`]s are sorted by path and then by policy
+//! priority so that execution, which may delete or move files, always happens
+//! serially and in a stable order.
+
+use super::engine::Policy;
+use jwalk::WalkDir;
+use rayon::prelude::*;
+use spectra_core::{CaseSensitivity, PathExclusions};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// How conflicting policies that match the same file are resolved.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatchMode {
+    /// Every matching policy acts on the file, highest priority first.
+    #[default]
+    AllMatch,
+    /// Only the highest-priority matching policy acts, so an allow-style
+    /// `Report` policy can shield a file from a lower-priority `Delete`.
+    FirstMatch,
+}
+
+impl FromStr for MatchMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "all-match" | "all" => Ok(MatchMode::AllMatch),
+            "first-match" | "first" => Ok(MatchMode::FirstMatch),
+            other => Err(format!(
+                "unknown policy match mode '{}' (expected all-match or first-match)",
+                other
+            )),
+        }
+    }
+}
+
+/// A file matched by a policy during evaluation.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PolicyOutcome {
+    pub path: PathBuf,
+    /// Index into the evaluated policy slice.
+    pub policy_index: usize,
+}
+
+/// Collect every regular file under `roots`, skipping excluded subtrees.
+pub fn collect_files(
+    roots: &[PathBuf],
+    excludes: &[PathBuf],
+    case: CaseSensitivity,
+) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for root in roots {
+        let exclusions = PathExclusions::new(root, excludes).with_case_sensitivity(case);
+        for dir_entry in WalkDir::new(root).into_iter().flatten() {
+            let path = dir_entry.path();
+            if dir_entry.file_type().is_file() && !exclusions.contains(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files
+}
+
+/// Evaluate every enabled policy against every file.
+///
+/// Under [`MatchMode::FirstMatch`] each file keeps only its highest-priority
+/// match. `threads` caps the worker count; `Some(1)` evaluates sequentially on the
+/// calling thread and `None` uses rayon's default (one per CPU).
+pub fn evaluate(
+    files: &[PathBuf],
+    policies: &[Policy],
+    threads: Option<usize>,
+    mode: MatchMode,
+) -> Vec<PolicyOutcome> {
+    let mut outcomes = match threads {
+        Some(1) => files
+            .iter()
+            .flat_map(|path| matches_for(path, policies))
+            .collect(),
+        Some(n) => match rayon::ThreadPoolBuilder::new().num_threads(n).build() {
+            Ok(pool) => pool.install(|| evaluate_parallel(files, policies)),
+            Err(_) => evaluate_parallel(files, policies),
+        },
+        None => evaluate_parallel(files, policies),
+    };
+    // Per path, higher-priority policies first; ties keep their load order.
+    outcomes.sort_by(|a, b| {
+        a.path
+            .cmp(&b.path)
+            .then_with(|| {
+                policies[b.policy_index]
+                    .priority
+                    .cmp(&policies[a.policy_index].priority)
+            })
+            .then_with(|| a.policy_index.cmp(&b.policy_index))
+    });
+    if mode == MatchMode::FirstMatch {
+        outcomes.dedup_by(|later, first| later.path == first.path);
+    }
+    outcomes
+}
+
+fn evaluate_parallel(files: &[PathBuf], policies: &[Policy]) -> Vec<PolicyOutcome> {
+    files
+        .par_iter()
+        .flat_map_iter(|path| matches_for(path, policies))
+        .collect()
+}
+
+fn matches_for(path: &Path, policies: &[Policy]) -> Vec<PolicyOutcome> {
+    let Ok(meta) = std::fs::metadata(path) else {
+        return Vec::new();
+    };
+    policies
+        .iter()
+        .enumerate()
+        .filter(|(_, policy)| policy.evaluate(path, &meta))
+        .map(|(policy_index, _)| PolicyOutcome {
+            path: path.to_path_buf(),
+            policy_index,
+        })
+        .collect()
+}
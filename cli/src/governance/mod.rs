@@ -1,4 +1,12 @@
+pub mod audit;
 pub mod engine;
+pub mod evaluation;
+pub mod policy_diff;
+pub mod policy_file;
+pub mod remote;
+pub mod runner;
+pub mod structure;
+pub mod transaction;
 
 #[cfg(test)]
 mod tests;
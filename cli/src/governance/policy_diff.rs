@@ -0,0 +1,218 @@
+//! Governance diff: what changes between two policy sets.
+//!
+//! Both files use the `[[policies]]` format of project manifests (other
+//! top-level keys are ignored, so two manifests can be compared directly).
+//! Policies are matched by name, so a renamed policy shows up as one removal
+//! and one addition.
+
+use super::engine::Policy;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+struct PolicyFile {
+    #[serde(default)]
+    policies: Vec<Policy>,
+}
+
+/// Read the `[[policies]]` of a TOML file. Duplicate names are rejected,
+/// since they would make the diff ambiguous.
+pub fn load_policies(path: &Path) -> Result<Vec<Policy>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read policy file {}", path.display()))?;
+    let file: PolicyFile = toml::from_str(&contents)
+        .with_context(|| format!("Invalid policy file {}", path.display()))?;
+    let mut names = HashSet::new();
+    for policy in &file.policies {
+        if !names.insert(policy.name.as_str()) {
+            bail!(
+                "Duplicate policy name '{}' in {}",
+                policy.name,
+                path.display()
+            );
+        }
+    }
+    Ok(file.policies)
+}
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct PolicyDiff {
+    /// Names only in the new set.
+    pub added: Vec<String>,
+    /// Names only in the old set.
+    pub removed: Vec<String>,
+    /// Policies in both sets whose definition differs.
+    pub changed: Vec<PolicyChange>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct PolicyChange {
+    pub name: String,
+    pub fields: Vec<FieldChange>,
+}
+
+/// One differing field, e.g. `rule.min_age_days` or `action`.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+impl PolicyDiff {
+    /// Compare two policy sets; every list is sorted by name.
+    pub fn between(old: &[Policy], new: &[Policy]) -> Self {
+        let old: BTreeMap<&str, &Policy> = old.iter().map(|p| (p.name.as_str(), p)).collect();
+        let new: BTreeMap<&str, &Policy> = new.iter().map(|p| (p.name.as_str(), p)).collect();
+
+        let mut diff = PolicyDiff::default();
+        for (name, old_policy) in &old {
+            match new.get(name) {
+                None => diff.removed.push(name.to_string()),
+                Some(new_policy) => {
+                    let fields = field_changes(old_policy, new_policy);
+                    if !fields.is_empty() {
+                        diff.changed.push(PolicyChange {
+                            name: name.to_string(),
+                            fields,
+                        });
+                    }
+                }
+            }
+        }
+        diff.added = new
+            .keys()
+            .filter(|name| !old.contains_key(*name))
+            .map(|name| name.to_string())
+            .collect();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// The fields a policy is compared on, as JSON values so that actions with
+/// payloads (`Archive { target_path }`) compare and print whole.
+fn fields(policy: &Policy) -> [(&'static str, Value); 9] {
+    let rule = &policy.rule;
+    [
+        ("rule.extension", serde_json::json!(rule.extension)),
+        (
+            "rule.min_size_bytes",
+            serde_json::json!(rule.min_size_bytes),
+        ),
+        ("rule.min_age_days", serde_json::json!(rule.min_age_days)),
+        ("rule.max_age_days", serde_json::json!(rule.max_age_days)),
+        (
+            "rule.case_sensitivity",
+            serde_json::json!(rule.case_sensitivity),
+        ),
+        ("rule.path_regex", serde_json::json!(rule.path_regex)),
+        ("action", serde_json::json!(policy.action)),
+        ("enabled", serde_json::json!(policy.enabled)),
+        ("priority", serde_json::json!(policy.priority)),
+    ]
+}
+
+fn field_changes(old: &Policy, new: &Policy) -> Vec<FieldChange> {
+    fields(old)
+        .into_iter()
+        .zip(fields(new))
+        .filter(|((_, old), (_, new))| old != new)
+        .map(|((field, old), (_, new))| FieldChange {
+            field: field.to_string(),
+            old,
+            new,
+        })
+        .collect()
+}
+
+impl fmt::Display for PolicyDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No policy changes");
+        }
+        for name in &self.removed {
+            writeln!(f, "- {}", name)?;
+        }
+        for name in &self.added {
+            writeln!(f, "+ {}", name)?;
+        }
+        for change in &self.changed {
+            writeln!(f, "~ {}", change.name)?;
+            for field in &change.fields {
+                writeln!(f, "    {}: {} → {}", field.field, field.old, field.new)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const OLD: &str = r#"
+[[policies]]
+name = "Stale logs"
+rule = { extension = "log", min_age_days = 90 }
+action = "Report"
+
+[[policies]]
+name = "Temp files"
+rule = { extension = "tmp" }
+action = "Delete"
+"#;
+
+    const NEW: &str = r#"
+[[policies]]
+name = "Stale logs"
+rule = { extension = "log", min_age_days = 90 }
+action = "Delete"
+priority = 10
+
+[[policies]]
+name = "Scratch files"
+rule = { extension = "tmp" }
+action = "Delete"
+"#;
+
+    #[test]
+    fn test_action_change_and_rename_are_reported() {
+        let dir = tempdir().unwrap();
+        let (old_path, new_path) = (dir.path().join("old.toml"), dir.path().join("new.toml"));
+        std::fs::write(&old_path, OLD).unwrap();
+        std::fs::write(&new_path, NEW).unwrap();
+
+        let old = load_policies(&old_path).unwrap();
+        let new = load_policies(&new_path).unwrap();
+        let diff = PolicyDiff::between(&old, &new);
+
+        assert_eq!(diff.removed, ["Temp files"]);
+        assert_eq!(diff.added, ["Scratch files"]);
+        assert_eq!(diff.changed.len(), 1);
+        let change = &diff.changed[0];
+        assert_eq!(change.name, "Stale logs");
+        assert_eq!(
+            change.fields[0],
+            FieldChange {
+                field: "action".to_string(),
+                old: Value::from("Report"),
+                new: Value::from("Delete"),
+            }
+        );
+        assert_eq!(change.fields[1].field, "priority");
+        assert_eq!(change.fields.len(), 2);
+
+        assert!(PolicyDiff::between(&old, &old).is_empty());
+        let text = diff.to_string();
+        assert!(text.contains("action: \"Report\" → \"Delete\""), "{}", text);
+    }
+}
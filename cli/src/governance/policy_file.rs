@@ -0,0 +1,121 @@
+//! Local policy files (`--policy-file`), for running governance without a
+//! server.
+//!
+//! The file is a YAML list of policies in the same shape as the TOML
+//! `[[policies]]` tables of a project manifest:
+//!
+//! ```yaml
+//! - name: Stale logs
+//!   rule: { extension: log, min_age_days: 90 }
+//!   action: Delete
+//! - name: Archive reports
+//!   rule: { extension: pdf }
+//!   action: { Archive: { target_path: /mnt/archive } }
+//! ```
+
+use super::engine::{Action, Policy};
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+/// Read and validate the policies of a YAML file.
+pub fn load(path: &Path) -> Result<Vec<Policy>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read policy file {}", path.display()))?;
+    // Actions with fields are written as maps (`{ Archive: { ... } }`), as
+    // in TOML and JSON, rather than as YAML `!Archive` tags.
+    let policies: Vec<Policy> = serde_yaml::with::singleton_map_recursive::deserialize(
+        serde_yaml::Deserializer::from_str(&contents),
+    )
+    .with_context(|| format!("Invalid policy file {}", path.display()))?;
+    for policy in &policies {
+        if let Action::Archive { target_path } = &policy.action {
+            check_archive_target(Path::new(target_path))
+                .with_context(|| format!("Policy '{}' in {}", policy.name, path.display()))?;
+        }
+    }
+    Ok(policies)
+}
+
+/// An archive target must be a directory, or a path whose nearest existing
+/// ancestor is one, so archiving can create it.
+fn check_archive_target(target: &Path) -> Result<()> {
+    if target.as_os_str().is_empty() {
+        bail!("archive target_path is empty");
+    }
+    match target.ancestors().find(|dir| dir.exists()) {
+        Some(dir) if dir.is_dir() => Ok(()),
+        Some(dir) => bail!(
+            "archive target {} is unreachable: {} is not a directory",
+            target.display(),
+            dir.display()
+        ),
+        // A relative path with no existing ancestor lands in the working
+        // directory.
+        None if target.is_relative() => Ok(()),
+        None => bail!("archive target {} is unreachable", target.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_yaml_policies_parse_into_actions() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("archive");
+        let file = dir.path().join("policies.yaml");
+        std::fs::write(
+            &file,
+            format!(
+                r#"
+- name: Temp files
+  rule: {{ extension: tmp, min_size_bytes: 1024 }}
+  action: Delete
+- name: Old reports
+  rule: {{ extension: pdf, min_age_days: 365 }}
+  action:
+    Archive:
+      target_path: {}
+  priority: 5
+"#,
+                archive.display()
+            ),
+        )
+        .unwrap();
+
+        let policies = load(&file).unwrap();
+        assert_eq!(policies.len(), 2);
+        assert_eq!(policies[0].name, "Temp files");
+        assert_eq!(policies[0].action, Action::Delete);
+        assert_eq!(policies[0].rule.min_size_bytes, Some(1024));
+        assert!(policies[0].enabled);
+        assert_eq!(
+            policies[1].action,
+            Action::Archive {
+                target_path: archive.display().to_string()
+            }
+        );
+        assert_eq!(policies[1].rule.extension.as_deref(), Some("pdf"));
+        assert_eq!(policies[1].priority, 5);
+
+        // A target below a regular file can never be created.
+        let blocker = dir.path().join("blocker");
+        std::fs::write(&blocker, b"").unwrap();
+        std::fs::write(
+            &file,
+            format!(
+                "- name: Bad archive\n  rule: {{ extension: pdf }}\n  action: {{ Archive: {{ target_path: {} }} }}\n",
+                blocker.join("archive").display()
+            ),
+        )
+        .unwrap();
+        let err = format!("{:#}", load(&file).unwrap_err());
+        assert!(
+            err.contains("Bad archive") && err.contains("not a directory"),
+            "{}",
+            err
+        );
+    }
+}
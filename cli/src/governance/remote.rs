@@ -0,0 +1,219 @@
+//! Policies served by the Spectra Server (`GET /api/v1/policies`).
+//!
+//! The server sends each rule as a list of condition strings and the action
+//! as an uppercase name:
+//!
+//! ```json
+//! {
+//!   "name": "Cleanup Old Logs",
+//!   "rules": ["extension == 'log'", "days_since_modified > 90"],
+//!   "action": "DELETE"
+//! }
+//! ```
+//!
+//! Supported conditions are `extension == 'x'`, `size > N` (bytes; also
+//! `>=`), `days_since_modified > N` (also `>=`, and `<`/`<=` for recent
+//! files) and `path matches 'regex'`. Comparisons keep their strictness: a
+//! file exactly N days old matches `>= N` and `<= N` but not `> N` or `< N`. `ARCHIVE` reads its destination from
+//! a `target_path` field beside `action`.
+
+use super::engine::{Action, KeepPolicy, PathRegex, Policy, Rule};
+use serde_json::Value;
+
+/// Turn one server policy into a [`Policy`]. A condition that can't be
+/// parsed rejects the whole policy, since dropping it would widen what the
+/// policy matches. An unknown action (or one missing its fields) falls back
+/// to [`Action::Report`] with a warning.
+pub fn parse_policy(value: &Value) -> Result<Policy, String> {
+    let name = value
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or("policy has no name")?
+        .to_string();
+
+    let mut rule = Rule::default();
+    let conditions = value.get("rules").and_then(Value::as_array);
+    for condition in conditions.into_iter().flatten() {
+        let condition = condition
+            .as_str()
+            .ok_or_else(|| format!("policy '{}': rule {} is not a string", name, condition))?;
+        apply_condition(&mut rule, condition).map_err(|e| format!("policy '{}': {}", name, e))?;
+    }
+
+    let action = value.get("action").and_then(Value::as_str).unwrap_or("");
+    let action = parse_action(action, value).unwrap_or_else(|| {
+        eprintln!(
+            "⚠️  Policy '{}': unknown action '{}' (or ARCHIVE without target_path), reporting only",
+            name, action
+        );
+        Action::Report
+    });
+
+    Ok(Policy {
+        enabled: value
+            .get("enabled")
+            .and_then(Value::as_bool)
+            .unwrap_or(true),
+        priority: value
+            .get("priority")
+            .and_then(Value::as_i64)
+            .and_then(|v| i32::try_from(v).ok())
+            .unwrap_or(0),
+        ..Policy::new(name, rule, action)
+    })
+}
+
+fn parse_action(action: &str, value: &Value) -> Option<Action> {
+    let flag = |key| value.get(key).and_then(Value::as_bool).unwrap_or(false);
+    Some(match action.to_ascii_uppercase().as_str() {
+        "REPORT" => Action::Report,
+        "DELETE" => Action::Delete,
+        "TRASH" => Action::Trash,
+        "ARCHIVE" => Action::Archive {
+            target_path: value.get("target_path")?.as_str()?.to_string(),
+        },
+        "COMPRESS" => Action::Compress {
+            keep_original: flag("keep_original"),
+        },
+        "DEDUPLICATE" => Action::Deduplicate {
+            keep: match value.get("keep").and_then(Value::as_str) {
+                None => KeepPolicy::Oldest,
+                Some(keep) => serde_json::from_value(Value::from(keep)).ok()?,
+            },
+            hardlink: flag("hardlink"),
+        },
+        _ => return None,
+    })
+}
+
+/// Fold one `field op value` condition into `rule`.
+fn apply_condition(rule: &mut Rule, condition: &str) -> Result<(), String> {
+    let unsupported = || format!("unsupported rule '{}'", condition);
+    let mut parts = condition.trim().splitn(3, char::is_whitespace);
+    let (Some(field), Some(op), Some(operand)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(unsupported());
+    };
+    let operand = operand.trim();
+    let quoted = || {
+        operand
+            .strip_prefix('\'')
+            .and_then(|s| s.strip_suffix('\''))
+            .or_else(|| operand.strip_prefix('"').and_then(|s| s.strip_suffix('"')))
+            .ok_or_else(unsupported)
+    };
+    let number = || operand.parse::<u64>().map_err(|_| unsupported());
+
+    match (field, op) {
+        ("extension", "==") => {
+            rule.extension = Some(quoted()?.trim_start_matches('.').to_string());
+        }
+        ("size" | "size_bytes", ">") => rule.min_size_bytes = Some(number()?.saturating_add(1)),
+        ("size" | "size_bytes", ">=") => rule.min_size_bytes = Some(number()?),
+        // The engine's minimum age is inclusive and its maximum exclusive.
+        ("days_since_modified", ">") => rule.min_age_days = Some(number()?.saturating_add(1)),
+        ("days_since_modified", ">=") => rule.min_age_days = Some(number()?),
+        ("days_since_modified", "<") => rule.max_age_days = Some(number()?),
+        ("days_since_modified", "<=") => rule.max_age_days = Some(number()?.saturating_add(1)),
+        ("path", "matches") => {
+            let pattern = quoted()?;
+            let regex = PathRegex::new(pattern)
+                .map_err(|e| format!("invalid path regex '{}': {}", pattern, e))?;
+            rule.path_regex = Some(regex);
+        }
+        _ => return Err(unsupported()),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_server_payload_maps_to_policy_fields() {
+        let payload = json!({
+            "id": "pol_archive_reports",
+            "name": "Archive old reports",
+            "rules": [
+                "extension == 'pdf'",
+                "size > 1048576",
+                "days_since_modified > 90",
+                "days_since_modified < 365",
+                "path matches '.*/reports/.*'"
+            ],
+            "action": "ARCHIVE",
+            "target_path": "/mnt/archive",
+            "priority": 3
+        });
+
+        let policy = parse_policy(&payload).unwrap();
+        assert_eq!(
+            policy,
+            Policy {
+                name: "Archive old reports".to_string(),
+                rule: Rule {
+                    extension: Some("pdf".to_string()),
+                    min_size_bytes: Some(1_048_577),
+                    min_age_days: Some(91),
+                    max_age_days: Some(365),
+                    path_regex: Some(PathRegex::new(".*/reports/.*").unwrap()),
+                    ..Default::default()
+                },
+                action: Action::Archive {
+                    target_path: "/mnt/archive".to_string()
+                },
+                enabled: true,
+                priority: 3,
+            }
+        );
+
+        let delete = json!({"name": "Temp", "rules": ["extension == 'tmp'"], "action": "DELETE"});
+        assert_eq!(parse_policy(&delete).unwrap().action, Action::Delete);
+
+        let unknown = json!({"name": "Odd", "rules": ["extension == 'tmp'"], "action": "SHRED"});
+        assert_eq!(parse_policy(&unknown).unwrap().action, Action::Report);
+
+        // An archive without a destination can't run either.
+        let no_target = json!({"name": "Nowhere", "rules": [], "action": "ARCHIVE"});
+        assert_eq!(parse_policy(&no_target).unwrap().action, Action::Report);
+
+        // `size > u64::MAX` can't match, but mustn't overflow either.
+        let huge = json!({"name": "Huge", "rules": [format!("size > {}", u64::MAX)]});
+        assert_eq!(
+            parse_policy(&huge).unwrap().rule.min_size_bytes,
+            Some(u64::MAX)
+        );
+
+        let unparsed = json!({"name": "Vague", "rules": ["owner == 'bob'"], "action": "DELETE"});
+        let err = parse_policy(&unparsed).unwrap_err();
+        assert!(err.contains("unsupported rule 'owner == 'bob''"), "{}", err);
+    }
+
+    #[test]
+    fn test_age_conditions_at_exactly_n_days() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("old.log");
+        std::fs::write(&path, b"x").unwrap();
+        let ninety_days = std::time::Duration::from_secs(90 * 86400);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - ninety_days)
+            .unwrap();
+        let metadata = std::fs::metadata(&path).unwrap();
+
+        let matches = |condition: &str| {
+            parse_policy(&json!({"name": "Age", "rules": [condition]}))
+                .unwrap()
+                .evaluate(&path, &metadata)
+        };
+        assert!(!matches("days_since_modified > 90"));
+        assert!(matches("days_since_modified >= 90"));
+        assert!(!matches("days_since_modified < 90"));
+        assert!(matches("days_since_modified <= 90"));
+        assert!(matches("days_since_modified > 89"));
+        assert!(matches("days_since_modified < 91"));
+    }
+}
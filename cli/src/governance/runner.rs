@@ -0,0 +1,178 @@
+//! Serial execution of evaluated policy matches.
+
+use super::audit::{AuditEntry, AuditLog};
+use super::engine::{self, KeepPolicy, Policy};
+use super::transaction::TransactionLog;
+use humansize::{format_size, DECIMAL};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Totals over a run's matches, so a dry run can say what enforcing would
+/// do before anything is touched.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GovernancePlan {
+    /// Matched files; a file matched by two policies counts twice.
+    pub matched: u64,
+    /// Size of the matched files.
+    pub bytes_affected: u64,
+    /// Matched files per action name.
+    pub by_action: HashMap<String, u64>,
+    /// Matched bytes per action name.
+    pub bytes_by_action: HashMap<String, u64>,
+}
+
+/// Actions that free the space of the files they act on.
+const RECLAIMING_ACTIONS: &[&str] = &["Delete", "Trash", "Deduplicate"];
+
+impl GovernancePlan {
+    fn add(&mut self, action: &str, size: u64) {
+        self.matched += 1;
+        self.bytes_affected += size;
+        *self.by_action.entry(action.to_string()).or_default() += 1;
+        *self.bytes_by_action.entry(action.to_string()).or_default() += size;
+    }
+
+    /// Bytes freed by deleting, trashing and deduplicating; archives and
+    /// compression are not counted.
+    pub fn reclaimable_bytes(&self) -> u64 {
+        RECLAIMING_ACTIONS
+            .iter()
+            .filter_map(|action| self.bytes_by_action.get(*action))
+            .sum()
+    }
+}
+
+impl fmt::Display for GovernancePlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut actions: Vec<(&String, &u64)> = self.by_action.iter().collect();
+        actions.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        for (action, files) in actions {
+            let bytes = self.bytes_by_action.get(action).copied().unwrap_or(0);
+            writeln!(
+                f,
+                "   {:<12} {:>8} files  {:>10}",
+                action,
+                files,
+                format_size(bytes, DECIMAL)
+            )?;
+        }
+        writeln!(
+            f,
+            "   {:<12} {:>8} files  {:>10}",
+            "Total",
+            self.matched,
+            format_size(self.bytes_affected, DECIMAL)
+        )?;
+        writeln!(
+            f,
+            "   Reclaimable: {}",
+            format_size(self.reclaimable_bytes(), DECIMAL)
+        )
+    }
+}
+
+/// Executes matches one at a time, routing deletions through the optional
+/// transaction log, recording every action in the optional audit log and
+/// totalling them in a [`GovernancePlan`].
+pub struct GovernanceRunner<'a> {
+    dry_run: bool,
+    hard_delete: bool,
+    txn: Option<&'a mut TransactionLog>,
+    audit: Option<AuditLog>,
+    plan: GovernancePlan,
+}
+
+impl<'a> GovernanceRunner<'a> {
+    pub fn new(dry_run: bool) -> Self {
+        Self {
+            dry_run,
+            hard_delete: false,
+            txn: None,
+            audit: None,
+            plan: GovernancePlan::default(),
+        }
+    }
+
+    /// Totals of every match run so far, whether or not its action succeeded.
+    pub fn plan(&self) -> &GovernancePlan {
+        &self.plan
+    }
+
+    /// Let deduplication delete copies outright when there is no
+    /// transaction log, instead of moving them to the OS trash.
+    pub fn with_hard_delete(mut self, hard_delete: bool) -> Self {
+        self.hard_delete = hard_delete;
+        self
+    }
+
+    pub fn with_txn(mut self, txn: Option<&'a mut TransactionLog>) -> Self {
+        self.txn = txn;
+        self
+    }
+
+    pub fn with_audit(mut self, audit: Option<AuditLog>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Apply `policy` to `path`, found under the scan `root`.
+    pub fn run(&mut self, policy: &Policy, path: &Path, root: &Path) {
+        // Read before the action can move or remove the file.
+        let size = file_size(path);
+        self.plan.add(policy.action.name(), size);
+        let result = policy.execute_with_txn(path, root, self.dry_run, self.txn.as_deref_mut());
+        self.audit(
+            AuditEntry::new(&policy.name, policy.action.name(), path, size, self.dry_run)
+                .with_result(result),
+        );
+    }
+
+    /// Apply a `Deduplicate` policy to one group of identical files; see
+    /// [`engine::deduplicate`]. Each copy other than the kept one is audited
+    /// with its outcome.
+    pub fn deduplicate(
+        &mut self,
+        policy: &Policy,
+        group: &[PathBuf],
+        keep: KeepPolicy,
+        hardlink: bool,
+    ) {
+        // The copies are identical, so any of them gives the size.
+        let size = group.first().map_or(0, |path| file_size(path));
+        let outcomes = engine::deduplicate(
+            group,
+            keep,
+            hardlink,
+            self.dry_run,
+            self.hard_delete,
+            self.txn.as_deref_mut(),
+            &policy.name,
+        );
+        for (path, result) in outcomes {
+            self.plan.add(policy.action.name(), size);
+            self.audit(
+                AuditEntry::new(
+                    &policy.name,
+                    policy.action.name(),
+                    &path,
+                    size,
+                    self.dry_run,
+                )
+                .with_result(result),
+            );
+        }
+    }
+
+    fn audit(&mut self, entry: AuditEntry) {
+        if let Some(log) = &mut self.audit {
+            if let Err(e) = log.record(&entry) {
+                eprintln!("⚠️  Failed to write audit log: {}", e);
+            }
+        }
+    }
+}
+
+fn file_size(path: &Path) -> u64 {
+    std::fs::metadata(path).map_or(0, |meta| meta.len())
+}
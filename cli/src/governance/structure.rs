@@ -0,0 +1,127 @@
+//! Structural lint: check a tree against a declared expected layout.
+//!
+//! The spec is a TOML, YAML or JSON file (format picked by extension):
+//!
+//! ```toml
+//! required = ["logs", "src"]
+//!
+//! [[forbidden]]
+//! under = "public"
+//! names = [".env"]
+//! extensions = ["pem", "key"]
+//! ```
+//!
+//! `required` directories must exist below the root. A `forbidden` rule
+//! flags every file under `under` (recursively, hidden files included) whose
+//! name is in `names` or whose extension is in `extensions`.
+
+use anyhow::{Context, Result};
+use jwalk::WalkDir;
+use serde::{Deserialize, Serialize};
+use spectra_core::CaseSensitivity;
+use std::path::Path;
+
+#[derive(Debug, Deserialize, Default)]
+pub struct StructureSpec {
+    /// Directories (relative to the root) that must exist.
+    #[serde(default)]
+    pub required: Vec<String>,
+    #[serde(default)]
+    pub forbidden: Vec<ForbiddenRule>,
+    /// How names and extensions are compared; platform convention by default.
+    #[serde(default)]
+    pub case_sensitivity: CaseSensitivity,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct ForbiddenRule {
+    /// Directory (relative to the root) the rule applies to; empty for the
+    /// whole tree.
+    #[serde(default)]
+    pub under: String,
+    /// Exact file names, e.g. `.env`.
+    #[serde(default)]
+    pub names: Vec<String>,
+    /// Extensions without the dot, e.g. `pem`.
+    #[serde(default)]
+    pub extensions: Vec<String>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Violation {
+    MissingDirectory { path: String },
+    ForbiddenFile { path: String, under: String },
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::MissingDirectory { path } => {
+                write!(f, "required directory missing: {}", path)
+            }
+            Violation::ForbiddenFile { path, under } if under.is_empty() => {
+                write!(f, "forbidden file: {}", path)
+            }
+            Violation::ForbiddenFile { path, under } => {
+                write!(f, "forbidden file under {}/: {}", under, path)
+            }
+        }
+    }
+}
+
+impl StructureSpec {
+    pub fn load(path: &Path) -> Result<Self> {
+        config::Config::builder()
+            .add_source(config::File::from(path))
+            .build()
+            .and_then(|c| c.try_deserialize())
+            .with_context(|| format!("Invalid structure spec {}", path.display()))
+    }
+
+    /// Every violation under `root`, missing directories first.
+    pub fn check(&self, root: &Path) -> Vec<Violation> {
+        let mut violations: Vec<Violation> = self
+            .required
+            .iter()
+            .filter(|dir| !root.join(dir).is_dir())
+            .map(|dir| Violation::MissingDirectory { path: dir.clone() })
+            .collect();
+
+        for rule in &self.forbidden {
+            let base = root.join(&rule.under);
+            if !base.is_dir() {
+                continue;
+            }
+            let mut found: Vec<String> = WalkDir::new(&base)
+                .skip_hidden(false)
+                .into_iter()
+                .flatten()
+                .filter(|entry| entry.file_type().is_file())
+                .map(|entry| entry.path())
+                .filter(|path| rule.forbids(path, self.case_sensitivity))
+                .map(|path| path.display().to_string())
+                .collect();
+            found.sort();
+            violations.extend(found.into_iter().map(|path| Violation::ForbiddenFile {
+                path,
+                under: rule.under.clone(),
+            }));
+        }
+        violations
+    }
+}
+
+impl ForbiddenRule {
+    fn forbids(&self, path: &Path, case: CaseSensitivity) -> bool {
+        let name_hit = path.file_name().is_some_and(|name| {
+            let name = name.to_string_lossy();
+            self.names.iter().any(|n| case.matches(&name, n))
+        });
+        let ext_hit = path.extension().is_some_and(|ext| {
+            let ext = ext.to_string_lossy();
+            self.extensions.iter().any(|e| case.matches(&ext, e))
+        });
+        name_hit || ext_hit
+    }
+}
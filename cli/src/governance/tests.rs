@@ -1,4 +1,7 @@
 use super::engine::*;
+use super::evaluation::{collect_files, evaluate, MatchMode};
+use super::structure::{StructureSpec, Violation};
+use spectra_core::CaseSensitivity;
 use std::fs::File;
 use tempfile::TempDir;
 
@@ -17,6 +20,7 @@ fn test_policy_evaluation_age() {
         extension: Some("log".to_string()),
         min_size_bytes: None,
         min_age_days: Some(30),
+        ..Default::default()
     };
 
     // This test validates the rule structure is correct
@@ -37,13 +41,10 @@ fn test_policy_extension_match() {
         extension: Some("tmp".to_string()),
         min_size_bytes: None,
         min_age_days: None,
+        ..Default::default()
     };
 
-    let policy = Policy {
-        name: "Test TMP Files".to_string(),
-        rule: rule.clone(),
-        action: Action::Report,
-    };
+    let policy = Policy::new("Test TMP Files", rule.clone(), Action::Report);
 
     // Test that .tmp file matches
     let tmp_metadata = std::fs::metadata(&tmp_file_path).unwrap();
@@ -71,13 +72,10 @@ fn test_policy_size_threshold() {
         extension: None,
         min_size_bytes: Some(1024), // 1KB threshold
         min_age_days: None,
+        ..Default::default()
     };
 
-    let policy = Policy {
-        name: "Large Files Only".to_string(),
-        rule,
-        action: Action::Report,
-    };
+    let policy = Policy::new("Large Files Only", rule, Action::Report);
 
     // Test small file does not match
     let small_metadata = std::fs::metadata(&small_file_path).unwrap();
@@ -94,19 +92,791 @@ fn test_dry_run_mode() {
     let test_file_path = temp_dir.path().join("test.txt");
     std::fs::write(&test_file_path, b"test content").unwrap();
 
-    let policy = Policy {
-        name: "Delete Test".to_string(),
-        rule: Rule {
+    let policy = Policy::new(
+        "Delete Test",
+        Rule {
             extension: Some("txt".to_string()),
             min_size_bytes: None,
             min_age_days: None,
+            ..Default::default()
         },
-        action: Action::Delete,
-    };
+        Action::Delete,
+    );
 
     // Execute in dry-run mode
-    policy.execute(&test_file_path, true);
+    policy
+        .execute_with_txn(
+            &test_file_path,
+            test_file_path.parent().unwrap(),
+            true,
+            None,
+        )
+        .unwrap();
 
     // File should still exist after dry-run
     assert!(test_file_path.exists());
 }
+
+#[test]
+fn test_extension_match_honors_case_sensitivity() {
+    let temp_dir = TempDir::new().unwrap();
+    let upper_path = temp_dir.path().join("REPORT.LOG");
+    std::fs::write(&upper_path, b"log line").unwrap();
+    let metadata = std::fs::metadata(&upper_path).unwrap();
+
+    let policy = |case_sensitivity| {
+        Policy::new(
+            "Logs",
+            Rule {
+                extension: Some("log".to_string()),
+                case_sensitivity,
+                ..Default::default()
+            },
+            Action::Report,
+        )
+    };
+
+    assert!(policy(CaseSensitivity::Insensitive).evaluate(&upper_path, &metadata));
+    assert!(!policy(CaseSensitivity::Sensitive).evaluate(&upper_path, &metadata));
+    assert_eq!(
+        policy(CaseSensitivity::Platform).evaluate(&upper_path, &metadata),
+        cfg!(any(windows, target_os = "macos"))
+    );
+}
+
+#[test]
+fn test_parallel_evaluation_matches_sequential() {
+    let temp_dir = TempDir::new().unwrap();
+    for i in 0..40 {
+        let sub = temp_dir.path().join(format!("dir_{}", i % 4));
+        std::fs::create_dir_all(&sub).unwrap();
+        let ext = ["log", "tmp", "txt"][i % 3];
+        std::fs::write(sub.join(format!("file_{}.{}", i, ext)), vec![0u8; i * 100]).unwrap();
+    }
+
+    let policies = vec![
+        Policy::new(
+            "Logs",
+            Rule {
+                extension: Some("log".to_string()),
+                ..Default::default()
+            },
+            Action::Report,
+        ),
+        Policy::new(
+            "Big Files",
+            Rule {
+                min_size_bytes: Some(2000),
+                ..Default::default()
+            },
+            Action::Report,
+        ),
+    ];
+
+    let files = collect_files(
+        &[temp_dir.path().to_path_buf()],
+        &[],
+        CaseSensitivity::default(),
+    );
+    assert_eq!(files.len(), 40);
+
+    let sequential = evaluate(&files, &policies, Some(1), MatchMode::AllMatch);
+    let parallel = evaluate(&files, &policies, Some(4), MatchMode::AllMatch);
+
+    assert!(!sequential.is_empty());
+    assert_eq!(sequential, parallel);
+}
+
+#[test]
+fn test_disabled_policy_skipped_and_priority_orders_evaluation() {
+    let temp_dir = TempDir::new().unwrap();
+    let log_path = temp_dir.path().join("app.log");
+    std::fs::write(&log_path, vec![0u8; 4096]).unwrap();
+
+    let policy = |name: &str, enabled, priority| Policy {
+        name: name.to_string(),
+        rule: Rule {
+            extension: Some("log".to_string()),
+            ..Default::default()
+        },
+        action: Action::Report,
+        enabled,
+        priority,
+    };
+    let policies = vec![
+        policy("Low", true, -5),
+        policy("Disabled", false, 100),
+        policy("High", true, 10),
+        policy("Default", true, 0),
+    ];
+
+    let metadata = std::fs::metadata(&log_path).unwrap();
+    assert!(!policies[1].evaluate(&log_path, &metadata));
+
+    let outcomes = evaluate(&[log_path], &policies, Some(1), MatchMode::AllMatch);
+    let order: Vec<&str> = outcomes
+        .iter()
+        .map(|o| policies[o.policy_index].name.as_str())
+        .collect();
+    assert_eq!(order, ["High", "Default", "Low"]);
+}
+
+#[test]
+fn test_first_match_lets_high_priority_report_preempt_delete() {
+    let temp_dir = TempDir::new().unwrap();
+    let keep = temp_dir.path().join("keep.bak");
+    let other = temp_dir.path().join("other.tmp");
+    std::fs::write(&keep, b"keep").unwrap();
+    std::fs::write(&other, b"other").unwrap();
+
+    let policies = vec![
+        Policy::new("Delete everything", Rule::default(), Action::Delete),
+        Policy {
+            priority: 10,
+            ..Policy::new(
+                "Keep backups",
+                Rule {
+                    extension: Some("bak".to_string()),
+                    ..Default::default()
+                },
+                Action::Report,
+            )
+        },
+    ];
+    let files = vec![keep.clone(), other.clone()];
+
+    let first = evaluate(&files, &policies, Some(1), MatchMode::FirstMatch);
+    let actions: Vec<(&std::path::Path, &str)> = first
+        .iter()
+        .map(|o| (o.path.as_path(), policies[o.policy_index].name.as_str()))
+        .collect();
+    assert_eq!(
+        actions,
+        [
+            (keep.as_path(), "Keep backups"),
+            (other.as_path(), "Delete everything"),
+        ]
+    );
+
+    let all = evaluate(&files, &policies, Some(1), MatchMode::AllMatch);
+    assert_eq!(all.len(), 3);
+    assert_eq!("first-match".parse(), Ok(MatchMode::FirstMatch));
+}
+
+#[test]
+fn test_structure_lint_flags_forbidden_env_under_public() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    std::fs::create_dir_all(root.join("public/assets")).unwrap();
+    std::fs::create_dir(root.join("src")).unwrap();
+    File::create(root.join("public/assets/.env")).unwrap();
+    File::create(root.join("public/index.html")).unwrap();
+    // Allowed outside public/.
+    File::create(root.join("src/.env")).unwrap();
+
+    let spec_path = root.join("structure.toml");
+    std::fs::write(
+        &spec_path,
+        "required = [\"src\", \"logs\"]\n\n[[forbidden]]\nunder = \"public\"\nnames = [\".env\"]\n",
+    )
+    .unwrap();
+    let spec = StructureSpec::load(&spec_path).unwrap();
+
+    let violations = spec.check(root);
+    assert_eq!(
+        violations,
+        [
+            Violation::MissingDirectory {
+                path: "logs".to_string()
+            },
+            Violation::ForbiddenFile {
+                path: root.join("public/assets/.env").display().to_string(),
+                under: "public".to_string(),
+            },
+        ]
+    );
+}
+
+fn archive_policy(target: &std::path::Path) -> Policy {
+    Policy::new(
+        "Archive logs",
+        Rule {
+            extension: Some("log".to_string()),
+            ..Default::default()
+        },
+        Action::Archive {
+            target_path: target.display().to_string(),
+        },
+    )
+}
+
+#[test]
+fn test_archive_moves_file_under_its_relative_path() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().join("data");
+    let archive = temp_dir.path().join("archive");
+    std::fs::create_dir_all(root.join("app/2023")).unwrap();
+    let file = root.join("app/2023/server.log");
+    std::fs::write(&file, b"old log").unwrap();
+
+    archive_policy(&archive)
+        .execute_with_txn(&file, &root, false, None)
+        .unwrap();
+
+    assert!(!file.exists());
+    let archived = archive.join("app/2023/server.log");
+    assert_eq!(std::fs::read(archived).unwrap(), b"old log");
+}
+
+#[test]
+fn test_archive_without_root_keeps_absolute_path_inside_target() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("data/server.log");
+    std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+    std::fs::write(&file, b"old log").unwrap();
+    let archive = temp_dir.path().join("archive");
+
+    let dest = archive_file(&file, std::path::Path::new(""), &archive).unwrap();
+
+    assert!(dest.starts_with(&archive), "{:?}", dest);
+    assert!(dest.ends_with("data/server.log"));
+    assert!(!file.exists());
+    assert_eq!(std::fs::read(&dest).unwrap(), b"old log");
+}
+
+#[test]
+fn test_archive_dry_run_leaves_file_untouched() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("server.log");
+    std::fs::write(&file, b"old log").unwrap();
+    let archive = temp_dir.path().join("archive");
+
+    archive_policy(&archive)
+        .execute_with_txn(&file, temp_dir.path(), true, None)
+        .unwrap();
+
+    assert_eq!(std::fs::read(&file).unwrap(), b"old log");
+    assert!(!archive.exists());
+}
+
+#[test]
+fn test_archive_collision_gets_numeric_suffix() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().join("data");
+    let archive = temp_dir.path().join("archive");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::create_dir_all(&archive).unwrap();
+    std::fs::write(archive.join("server.log"), b"archived earlier").unwrap();
+    std::fs::write(archive.join("server-1.log"), b"archived earlier too").unwrap();
+    let file = root.join("server.log");
+    std::fs::write(&file, b"new").unwrap();
+
+    let dest = archive_file(&file, &root, &archive).unwrap();
+
+    assert_eq!(dest, archive.join("server-2.log"));
+    assert_eq!(std::fs::read(&dest).unwrap(), b"new");
+    assert_eq!(
+        std::fs::read(archive.join("server.log")).unwrap(),
+        b"archived earlier"
+    );
+    assert!(!file.exists());
+}
+
+#[test]
+fn test_compress_gzips_compressible_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("server.log");
+    let text = "GET /index.html 200 OK\n".repeat(2_000);
+    std::fs::write(&file, &text).unwrap();
+
+    let policy = Policy::new(
+        "Compress logs",
+        Rule {
+            extension: Some("log".to_string()),
+            ..Default::default()
+        },
+        Action::Compress {
+            keep_original: false,
+        },
+    );
+    policy
+        .execute_with_txn(&file, file.parent().unwrap(), true, None)
+        .unwrap();
+    assert!(file.exists());
+
+    policy
+        .execute_with_txn(&file, file.parent().unwrap(), false, None)
+        .unwrap();
+    let gz = temp_dir.path().join("server.log.gz");
+    assert!(!file.exists());
+    assert!(std::fs::metadata(&gz).unwrap().len() < text.len() as u64 / 10);
+    let mut restored = String::new();
+    std::io::Read::read_to_string(
+        &mut flate2::read::GzDecoder::new(File::open(&gz).unwrap()),
+        &mut restored,
+    )
+    .unwrap();
+    assert_eq!(restored, text);
+
+    // Already compressed: left alone, no `.gz.gz`.
+    policy
+        .execute_with_txn(&gz, gz.parent().unwrap(), false, None)
+        .unwrap();
+    assert!(gz.exists());
+    assert!(!temp_dir.path().join("server.log.gz.gz").exists());
+
+    let kept = temp_dir.path().join("kept.log");
+    std::fs::write(&kept, &text).unwrap();
+    compress_file(&kept, true).unwrap();
+    assert!(kept.exists());
+    assert!(temp_dir.path().join("kept.log.gz").exists());
+    // An existing archive is never overwritten.
+    assert!(compress_file(&kept, true).is_err());
+}
+
+#[test]
+fn test_deduplicate_removes_all_but_kept_copy() {
+    use std::time::{Duration, SystemTime};
+
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let copies: Vec<_> = ["a/report.pdf", "b/report.pdf", "report-copy.pdf"]
+        .iter()
+        .map(|name| dir.join(name))
+        .collect();
+    let now = SystemTime::now();
+    for (age_days, path) in [(30, &copies[0]), (1, &copies[1]), (10, &copies[2])] {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, b"quarterly numbers").unwrap();
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(now - Duration::from_secs(age_days * 86_400))
+            .unwrap();
+    }
+
+    // Dry run lists the copies but touches nothing.
+    let planned = deduplicate(
+        &copies,
+        KeepPolicy::Oldest,
+        false,
+        true,
+        true,
+        None,
+        "dedup",
+    );
+    assert_eq!(
+        planned,
+        [(copies[1].clone(), Ok(())), (copies[2].clone(), Ok(()))]
+    );
+    assert!(copies.iter().all(|path| path.exists()));
+
+    let removed = deduplicate(
+        &copies,
+        KeepPolicy::Oldest,
+        false,
+        false,
+        true,
+        None,
+        "dedup",
+    );
+    assert_eq!(
+        removed,
+        [(copies[1].clone(), Ok(())), (copies[2].clone(), Ok(()))]
+    );
+    assert_eq!(std::fs::read(&copies[0]).unwrap(), b"quarterly numbers");
+    assert!(!copies[1].exists());
+    assert!(!copies[2].exists());
+}
+
+#[test]
+fn test_deduplicate_skips_copies_that_no_longer_match() {
+    let temp_dir = TempDir::new().unwrap();
+    let short = temp_dir.path().join("a.bin");
+    let long = temp_dir.path().join("longer-name.bin");
+    std::fs::write(&short, b"same").unwrap();
+    std::fs::write(&long, b"changed since the scan").unwrap();
+
+    let group = [long.clone(), short.clone()];
+    let removed = deduplicate(
+        &group,
+        KeepPolicy::ShortestPath,
+        true,
+        false,
+        true,
+        None,
+        "dedup",
+    );
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].0, long);
+    assert!(removed[0]
+        .1
+        .as_ref()
+        .unwrap_err()
+        .contains("no longer matches"));
+    assert!(short.exists() && long.exists());
+
+    // Once they match again, the copy is replaced by a hard link.
+    std::fs::write(&long, b"same").unwrap();
+    let removed = deduplicate(
+        &group,
+        KeepPolicy::ShortestPath,
+        true,
+        false,
+        true,
+        None,
+        "dedup",
+    );
+    assert_eq!(removed, [(long.clone(), Ok(()))]);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let ino = |path: &std::path::Path| std::fs::metadata(path).unwrap().ino();
+        assert_eq!(ino(&long), ino(&short));
+    }
+}
+
+#[test]
+fn test_deduplicate_under_txn_log_leaves_copies_restorable() {
+    use super::transaction::{rollback, TransactionLog};
+
+    let temp_dir = TempDir::new().unwrap();
+    let kept = temp_dir.path().join("a.bin");
+    let copy = temp_dir.path().join("copy-of-a.bin");
+    std::fs::write(&kept, b"same").unwrap();
+    std::fs::write(&copy, b"same").unwrap();
+    let log_path = temp_dir.path().join("txn.log");
+    let mut log = TransactionLog::open(&log_path).unwrap();
+
+    // No link is made, so nothing blocks the restore.
+    let group = [copy.clone(), kept.clone()];
+    let removed = deduplicate(
+        &group,
+        KeepPolicy::ShortestPath,
+        true,
+        false,
+        false,
+        Some(&mut log),
+        "dedup",
+    );
+    assert_eq!(removed, [(copy.clone(), Ok(()))]);
+    assert!(!copy.exists());
+
+    let report = rollback(&log_path).unwrap();
+    assert_eq!(report.restored.len(), 1);
+    assert_eq!(std::fs::read(&copy).unwrap(), b"same");
+}
+
+#[test]
+fn test_trash_action_under_txn_log_can_be_rolled_back() {
+    use super::transaction::{rollback, TransactionLog};
+
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("scratch.tmp");
+    std::fs::write(&file, b"keep me").unwrap();
+    let log_path = temp_dir.path().join("txn.log");
+    let mut log = TransactionLog::open(&log_path).unwrap();
+
+    let policy = Policy::new("Trash temp files", Rule::default(), Action::Trash);
+    policy
+        .execute_with_txn(&file, temp_dir.path(), false, Some(&mut log))
+        .unwrap();
+    assert!(!file.exists());
+
+    let report = rollback(&log_path).unwrap();
+    assert_eq!(report.restored, [file.display().to_string()]);
+    assert_eq!(std::fs::read(&file).unwrap(), b"keep me");
+}
+
+#[test]
+fn test_runner_audits_each_duplicate_with_its_outcome() {
+    use super::audit::{AuditEntry, AuditLog};
+    use super::runner::GovernanceRunner;
+
+    let temp_dir = TempDir::new().unwrap();
+    let kept = temp_dir.path().join("a.bin");
+    let same = temp_dir.path().join("b.bin");
+    let changed = temp_dir.path().join("c.bin");
+    std::fs::write(&kept, b"same").unwrap();
+    std::fs::write(&same, b"same").unwrap();
+    std::fs::write(&changed, b"edited").unwrap();
+    let policy = Policy::new(
+        "Dedup",
+        Rule::default(),
+        Action::Deduplicate {
+            keep: KeepPolicy::ShortestPath,
+            hardlink: false,
+        },
+    );
+
+    let audit_path = temp_dir.path().join("audit.jsonl");
+    let mut runner = GovernanceRunner::new(false)
+        .with_hard_delete(true)
+        .with_audit(Some(AuditLog::open(&audit_path).unwrap()));
+    runner.deduplicate(
+        &policy,
+        &[kept.clone(), same.clone(), changed.clone()],
+        KeepPolicy::ShortestPath,
+        false,
+    );
+    drop(runner);
+
+    let entries: Vec<AuditEntry> = std::fs::read_to_string(&audit_path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].path, same.display().to_string());
+    assert!(entries[0].success);
+    assert_eq!(entries[1].path, changed.display().to_string());
+    assert!(!entries[1].success);
+    assert!(entries[1].error.is_some());
+    assert!(!same.exists() && changed.exists());
+}
+
+#[test]
+fn test_deduplicate_trashes_copies_without_hard_delete() {
+    let temp_dir = TempDir::new().unwrap();
+    let probe = temp_dir.path().join("probe.tmp");
+    std::fs::write(&probe, b"").unwrap();
+    if trash::delete(&probe).is_err() {
+        eprintln!("skipping: no OS trash available");
+        return;
+    }
+
+    let kept = temp_dir.path().join("a.bin");
+    let copy = temp_dir.path().join("copy-of-a.bin");
+    std::fs::write(&kept, b"same").unwrap();
+    std::fs::write(&copy, b"same").unwrap();
+    let group = [copy.clone(), kept.clone()];
+    let removed = deduplicate(
+        &group,
+        KeepPolicy::ShortestPath,
+        false,
+        false,
+        false,
+        None,
+        "dedup",
+    );
+    assert_eq!(removed, [(copy.clone(), Ok(()))]);
+    assert!(!copy.exists());
+    assert!(kept.exists());
+}
+
+#[test]
+fn test_trash_removes_file_from_original_location() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("mistake.tmp");
+    std::fs::write(&file, b"recoverable").unwrap();
+
+    // Headless CI machines may have no usable trash (no home directory,
+    // or a temp dir on a mount without one); there is nothing to test then.
+    let probe = temp_dir.path().join("probe.tmp");
+    std::fs::write(&probe, b"").unwrap();
+    if trash::delete(&probe).is_err() {
+        eprintln!("skipping: no OS trash available");
+        return;
+    }
+
+    let policy = Policy::new(
+        "Trash temp files",
+        Rule {
+            extension: Some("tmp".to_string()),
+            ..Default::default()
+        },
+        Action::Trash,
+    );
+    policy
+        .execute_with_txn(&file, file.parent().unwrap(), true, None)
+        .unwrap();
+    assert!(file.exists());
+
+    policy
+        .execute_with_txn(&file, file.parent().unwrap(), false, None)
+        .unwrap();
+    assert!(!file.exists());
+}
+
+// The pattern uses `/`; Windows paths would need `\\`.
+#[cfg(unix)]
+#[test]
+fn test_path_regex_matches_only_files_under_cache() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = temp_dir.path().join("app").join("cache");
+    std::fs::create_dir_all(&cache).unwrap();
+    let cached = cache.join("blob.tmp");
+    let outside = temp_dir.path().join("app").join("blob.tmp");
+    let lookalike = temp_dir.path().join("app").join("cache.tmp");
+    for path in [&cached, &outside, &lookalike] {
+        std::fs::write(path, b"data").unwrap();
+    }
+
+    let policy: Policy = toml::from_str(
+        r#"
+name = "Cache files"
+rule = { extension = "tmp", path_regex = ".*/cache/.*" }
+action = "Report"
+"#,
+    )
+    .unwrap();
+    let matches = |path: &std::path::Path| policy.evaluate(path, &std::fs::metadata(path).unwrap());
+    assert!(matches(&cached));
+    assert!(!matches(&outside));
+    assert!(!matches(&lookalike));
+
+    let err = toml::from_str::<Policy>(
+        r#"
+name = "Broken"
+rule = { path_regex = "cache/(" }
+action = "Report"
+"#,
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string().contains("invalid path_regex 'cache/('"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_max_age_days_matches_only_recent_files() {
+    use std::time::{Duration, SystemTime};
+
+    let temp_dir = TempDir::new().unwrap();
+    let fresh = temp_dir.path().join("fresh.log");
+    let old = temp_dir.path().join("old.log");
+    std::fs::write(&fresh, b"just now").unwrap();
+    std::fs::write(&old, b"last week").unwrap();
+    File::options()
+        .write(true)
+        .open(&old)
+        .unwrap()
+        .set_modified(SystemTime::now() - Duration::from_secs(7 * 86_400))
+        .unwrap();
+
+    let policy = |min_age_days, max_age_days| {
+        Policy::new(
+            "Recent changes",
+            Rule {
+                min_age_days,
+                max_age_days,
+                ..Default::default()
+            },
+            Action::Report,
+        )
+    };
+    let matches = |policy: &Policy, path: &std::path::Path| {
+        policy.evaluate(path, &std::fs::metadata(path).unwrap())
+    };
+
+    let last_day = policy(None, Some(1));
+    assert!(matches(&last_day, &fresh));
+    assert!(!matches(&last_day, &old));
+
+    // A window: older than 3 days but younger than 30.
+    let window = policy(Some(3), Some(30));
+    assert!(!matches(&window, &fresh));
+    assert!(matches(&window, &old));
+}
+
+#[test]
+fn test_audit_log_records_one_line_per_matched_file() {
+    use super::audit::{AuditEntry, AuditLog};
+    use super::runner::GovernanceRunner;
+
+    let temp_dir = TempDir::new().unwrap();
+    let tree = temp_dir.path().join("tree");
+    std::fs::create_dir(&tree).unwrap();
+    for (name, size) in [("a.log", 10), ("b.log", 20), ("c.txt", 30)] {
+        std::fs::write(tree.join(name), vec![0u8; size]).unwrap();
+    }
+    let policies = vec![Policy::new(
+        "Report logs",
+        Rule {
+            extension: Some("log".to_string()),
+            ..Default::default()
+        },
+        Action::Report,
+    )];
+
+    let audit_path = temp_dir.path().join("audit.jsonl");
+    let files = collect_files(std::slice::from_ref(&tree), &[], CaseSensitivity::default());
+    let outcomes = evaluate(&files, &policies, Some(1), MatchMode::AllMatch);
+    let mut runner =
+        GovernanceRunner::new(false).with_audit(Some(AuditLog::open(&audit_path).unwrap()));
+    for outcome in &outcomes {
+        runner.run(&policies[outcome.policy_index], &outcome.path, &tree);
+    }
+    drop(runner);
+
+    let entries: Vec<AuditEntry> = std::fs::read_to_string(&audit_path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(entries.len(), 2);
+    for (entry, (name, size)) in entries.iter().zip([("a.log", 10), ("b.log", 20)]) {
+        assert_eq!(entry.policy, "Report logs");
+        assert_eq!(entry.action, "Report");
+        assert_eq!(entry.path, tree.join(name).display().to_string());
+        assert_eq!(entry.size_bytes, size);
+        assert!(entry.success);
+        assert_eq!(entry.error, None);
+        assert!(!entry.dry_run);
+        assert!(entry.timestamp > 0);
+    }
+}
+
+#[test]
+fn test_dry_run_plan_totals_reclaimable_bytes() {
+    use super::runner::GovernanceRunner;
+
+    let temp_dir = TempDir::new().unwrap();
+    let tree = temp_dir.path();
+    for (name, size) in [
+        ("a.tmp", 1_000),
+        ("b.tmp", 2_500),
+        ("c.log", 400),
+        ("keep.txt", 9_999),
+    ] {
+        std::fs::write(tree.join(name), vec![0u8; size]).unwrap();
+    }
+    let policy = |name: &str, extension: &str, action| {
+        Policy::new(
+            name,
+            Rule {
+                extension: Some(extension.to_string()),
+                ..Default::default()
+            },
+            action,
+        )
+    };
+    let policies = vec![
+        policy("Temp files", "tmp", Action::Delete),
+        policy("Logs", "log", Action::Report),
+    ];
+
+    let files = collect_files(&[tree.to_path_buf()], &[], CaseSensitivity::default());
+    let outcomes = evaluate(&files, &policies, Some(1), MatchMode::AllMatch);
+    let mut runner = GovernanceRunner::new(true);
+    for outcome in &outcomes {
+        runner.run(&policies[outcome.policy_index], &outcome.path, tree);
+    }
+
+    let plan = runner.plan();
+    assert_eq!(plan.matched, 3);
+    assert_eq!(plan.bytes_affected, 3_900);
+    assert_eq!(plan.by_action["Delete"], 2);
+    assert_eq!(plan.by_action["Report"], 1);
+    assert_eq!(plan.reclaimable_bytes(), 3_500);
+    // A dry run leaves everything in place.
+    assert!(tree.join("a.tmp").exists() && tree.join("b.tmp").exists());
+    assert!(
+        plan.to_string().contains("Reclaimable: 3.50 kB"),
+        "{}",
+        plan
+    );
+}
@@ -0,0 +1,369 @@
+//! Transactional deletion with a rollback window.
+//!
+//! Instead of unlinking files immediately, enforced deletions are:
+//! 1. Recorded in an append-only JSONL transaction log (path, size, content
+//!    hash) and flushed to disk *before* anything is touched.
+//! 2. Moved into a trash directory that lives next to the log
+//!    (`<log>.trash/`).
+//! 3. Permanently removed only once they are older than the grace period.
+//!
+//! `spectra-cli govern-rollback --txn <log>` restores every logged file that
+//! is still in the trash.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use spectra_core::hash::hash_file;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// One deletion, as recorded in the transaction log.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TxnEntry {
+    pub timestamp: i64,
+    pub policy: String,
+    pub original_path: String,
+    pub trash_path: String,
+    pub size_bytes: u64,
+    pub content_hash: String,
+}
+
+/// Outcome of a rollback run.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RollbackReport {
+    pub restored: Vec<String>,
+    /// Entries whose trash copy was already purged (grace period elapsed).
+    pub expired: Vec<String>,
+    /// Entries whose file is still at its original path, unchanged: the
+    /// move into the trash failed after the entry was logged.
+    pub never_moved: Vec<String>,
+    /// Entries skipped because something now occupies the original path or
+    /// the trash copy no longer matches the logged hash.
+    pub skipped: Vec<String>,
+    /// Unreadable log lines and entries that failed to restore, with the
+    /// error; the rest of the log is still processed.
+    pub errors: Vec<String>,
+}
+
+/// An open transaction log for an enforce run.
+pub struct TransactionLog {
+    file: File,
+    trash_dir: PathBuf,
+    sequence: u64,
+}
+
+impl TransactionLog {
+    /// Open (or create) the log at `log_path` in append mode.
+    pub fn open(log_path: &Path) -> io::Result<Self> {
+        let trash_dir = trash_dir_for(log_path);
+        fs::create_dir_all(&trash_dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path)?;
+        Ok(Self {
+            file,
+            trash_dir,
+            sequence: 0,
+        })
+    }
+
+    /// Log and then move `path` into the trash directory.
+    pub fn delete(&mut self, path: &Path, policy: &str) -> io::Result<TxnEntry> {
+        let size_bytes = fs::metadata(path)?.len();
+        let content_hash = hash_file(path)?;
+        let timestamp = Utc::now().timestamp();
+
+        self.sequence += 1;
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "file".to_string());
+        let trash_path = self.trash_dir.join(format!(
+            "{}-{}-{}-{}",
+            timestamp,
+            std::process::id(),
+            self.sequence,
+            file_name
+        ));
+
+        let entry = TxnEntry {
+            timestamp,
+            policy: policy.to_string(),
+            original_path: path.display().to_string(),
+            trash_path: trash_path.display().to_string(),
+            size_bytes,
+            content_hash,
+        };
+
+        // Write-ahead: the record must be durable before the file moves.
+        serde_json::to_writer(&mut self.file, &entry)?;
+        self.file.write_all(b"\n")?;
+        self.file.sync_data()?;
+
+        move_file(path, &trash_path)?;
+        Ok(entry)
+    }
+}
+
+/// The trash directory paired with a transaction log.
+pub fn trash_dir_for(log_path: &Path) -> PathBuf {
+    let mut name = log_path
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_default();
+    name.push(".trash");
+    log_path.with_file_name(name)
+}
+
+/// Permanently remove trashed files older than `grace`.
+/// Returns the number of files purged.
+///
+/// Only trash copies recorded in the log are touched, so anything else that
+/// ends up in the trash directory is left alone. Unreadable log lines are
+/// skipped.
+pub fn purge_expired(log_path: &Path, grace: Duration) -> io::Result<usize> {
+    let file = match File::open(log_path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let trash_dir = trash_dir_for(log_path);
+
+    // A rename keeps the file's original mtime, so the trash time is the
+    // one logged by `TransactionLog::delete`.
+    let now = Utc::now().timestamp();
+    let grace_secs = i64::try_from(grace.as_secs()).unwrap_or(i64::MAX);
+    let mut purged = 0;
+    for (_, entry) in read_entries(file) {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let Some(trash_path) = trashed_copy(&trash_dir, &entry) else {
+            continue;
+        };
+        if now.saturating_sub(entry.timestamp) >= grace_secs && fs::remove_file(trash_path).is_ok()
+        {
+            purged += 1;
+        }
+    }
+    Ok(purged)
+}
+
+/// Restore every logged deletion whose trash copy still exists. A bad log
+/// line or a failed restore is recorded in [`RollbackReport::errors`] and
+/// the rest of the log is still processed.
+pub fn rollback(log_path: &Path) -> io::Result<RollbackReport> {
+    let mut report = RollbackReport::default();
+    let trash_dir = trash_dir_for(log_path);
+
+    for (line, entry) in read_entries(File::open(log_path)?) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                report.errors.push(format!("line {}: {}", line, e));
+                continue;
+            }
+        };
+        match restore(&trash_dir, &entry) {
+            Ok(Restore::Restored) => report.restored.push(entry.original_path),
+            Ok(Restore::Expired) => report.expired.push(entry.original_path),
+            Ok(Restore::NeverMoved) => report.never_moved.push(entry.original_path),
+            Ok(Restore::Skipped) => report.skipped.push(entry.original_path),
+            Err(e) => report
+                .errors
+                .push(format!("{}: {}", entry.original_path, e)),
+        }
+    }
+
+    Ok(report)
+}
+
+enum Restore {
+    Restored,
+    Expired,
+    NeverMoved,
+    Skipped,
+}
+
+fn restore(trash_dir: &Path, entry: &TxnEntry) -> io::Result<Restore> {
+    let original = Path::new(&entry.original_path);
+    let Some(trash_path) = trashed_copy(trash_dir, entry).filter(|path| path.exists()) else {
+        // Logged but still in place: the move never happened.
+        if original.is_file() && hash_file(original)? == entry.content_hash {
+            return Ok(Restore::NeverMoved);
+        }
+        return Ok(Restore::Expired);
+    };
+    let trash_path = trash_path.as_path();
+    if original.exists() || hash_file(trash_path)? != entry.content_hash {
+        return Ok(Restore::Skipped);
+    }
+    if let Some(parent) = original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    move_file(trash_path, original)?;
+    Ok(Restore::Restored)
+}
+
+/// Where `entry`'s trash copy lives: its logged file name inside
+/// `trash_dir`, so a log read from another working directory or moved along
+/// with its trash still resolves, and nothing outside the trash is touched.
+fn trashed_copy(trash_dir: &Path, entry: &TxnEntry) -> Option<PathBuf> {
+    Path::new(&entry.trash_path)
+        .file_name()
+        .map(|name| trash_dir.join(name))
+}
+
+/// The log's entries in order with their 1-based line numbers, skipping
+/// blank lines.
+fn read_entries(file: File) -> impl Iterator<Item = (usize, io::Result<TxnEntry>)> {
+    BufReader::new(file)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|(i, line)| {
+            let entry = line.and_then(|line| {
+                serde_json::from_str(&line)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            });
+            (i + 1, entry)
+        })
+}
+
+/// Rename, falling back to copy + remove when crossing filesystems.
+pub(crate) fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_delete_then_rollback_restores_file() {
+        let dir = tempdir().unwrap();
+        let victim = dir.path().join("data/report.tmp");
+        fs::create_dir_all(victim.parent().unwrap()).unwrap();
+        fs::write(&victim, b"precious bytes").unwrap();
+        let log_path = dir.path().join("txn.jsonl");
+
+        let mut log = TransactionLog::open(&log_path).unwrap();
+        let entry = log.delete(&victim, "Cleanup TMP").unwrap();
+        assert!(!victim.exists());
+        assert!(Path::new(&entry.trash_path).exists());
+        assert_eq!(entry.size_bytes, 14);
+
+        // Still inside the grace window: nothing is purged.
+        assert_eq!(
+            purge_expired(&log_path, Duration::from_secs(3600)).unwrap(),
+            0
+        );
+
+        let report = rollback(&log_path).unwrap();
+        assert_eq!(report.restored, vec![victim.display().to_string()]);
+        assert_eq!(fs::read(&victim).unwrap(), b"precious bytes");
+    }
+
+    #[test]
+    fn test_rollback_after_grace_period_reports_expired() {
+        let dir = tempdir().unwrap();
+        let victim = dir.path().join("old.log");
+        fs::write(&victim, b"gone").unwrap();
+        let log_path = dir.path().join("txn.jsonl");
+
+        TransactionLog::open(&log_path)
+            .unwrap()
+            .delete(&victim, "Cleanup Logs")
+            .unwrap();
+        // Files the log doesn't list survive the purge, whatever their name.
+        let stray = trash_dir_for(&log_path).join("1700000000-notes.txt");
+        fs::write(&stray, b"not ours").unwrap();
+        assert_eq!(purge_expired(&log_path, Duration::ZERO).unwrap(), 1);
+        assert!(stray.exists());
+
+        let report = rollback(&log_path).unwrap();
+        assert!(report.restored.is_empty());
+        assert_eq!(report.expired, vec![victim.display().to_string()]);
+        assert!(!victim.exists());
+    }
+
+    #[test]
+    fn test_rollback_follows_a_moved_log_and_spots_failed_moves() {
+        let dir = tempdir().unwrap();
+        let victim = dir.path().join("data/report.tmp");
+        let stuck = dir.path().join("data/stuck.tmp");
+        fs::create_dir_all(victim.parent().unwrap()).unwrap();
+        fs::write(&victim, b"precious bytes").unwrap();
+        fs::write(&stuck, b"still here").unwrap();
+        let log_path = dir.path().join("txn.jsonl");
+
+        let mut log = TransactionLog::open(&log_path).unwrap();
+        log.delete(&victim, "Cleanup TMP").unwrap();
+        // A write-ahead entry whose move failed: the file never left.
+        let never_moved = TxnEntry {
+            timestamp: Utc::now().timestamp(),
+            policy: "Cleanup TMP".to_string(),
+            original_path: stuck.display().to_string(),
+            trash_path: "elsewhere/0-0-0-stuck.tmp".to_string(),
+            size_bytes: 10,
+            content_hash: hash_file(&stuck).unwrap(),
+        };
+        serde_json::to_writer(&mut log.file, &never_moved).unwrap();
+        log.file.write_all(b"\n").unwrap();
+        drop(log);
+
+        // The log and its trash move together; logged paths go stale.
+        let moved = dir.path().join("archive");
+        fs::create_dir(&moved).unwrap();
+        fs::rename(&log_path, moved.join("txn.jsonl")).unwrap();
+        fs::rename(
+            trash_dir_for(&log_path),
+            trash_dir_for(&moved.join("txn.jsonl")),
+        )
+        .unwrap();
+
+        let report = rollback(&moved.join("txn.jsonl")).unwrap();
+        assert_eq!(report.restored, vec![victim.display().to_string()]);
+        assert_eq!(report.never_moved, vec![stuck.display().to_string()]);
+        assert!(report.expired.is_empty());
+        assert_eq!(fs::read(&victim).unwrap(), b"precious bytes");
+    }
+
+    #[test]
+    fn test_rollback_continues_past_bad_entries() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("first.tmp");
+        let second = dir.path().join("second.tmp");
+        fs::write(&first, b"one").unwrap();
+        fs::write(&second, b"two").unwrap();
+        let log_path = dir.path().join("txn.jsonl");
+
+        let mut log = TransactionLog::open(&log_path).unwrap();
+        log.delete(&first, "Cleanup TMP").unwrap();
+        drop(log);
+        let mut file = OpenOptions::new().append(true).open(&log_path).unwrap();
+        file.write_all(b"{not json\n").unwrap();
+        drop(file);
+        TransactionLog::open(&log_path)
+            .unwrap()
+            .delete(&second, "Cleanup TMP")
+            .unwrap();
+
+        let report = rollback(&log_path).unwrap();
+        assert_eq!(report.restored.len(), 2);
+        assert_eq!(report.errors.len(), 1);
+        assert!(
+            report.errors[0].starts_with("line 2:"),
+            "{:?}",
+            report.errors
+        );
+        assert!(first.exists() && second.exists());
+    }
+}
@@ -0,0 +1,461 @@
+//! Local scan history for single-machine trend tracking.
+//!
+//! Every scan appends a summary row to a SQLite database (by default
+//! `~/.local/share/spectra/history.db`), and `spectra-cli trend` reports how a
+//! root has grown over its most recent runs. This gives time-travel style
+//! analytics without running the server. Each run's top-file list is stored
+//! too, so `spectra-cli changes` can name the large files that appeared
+//! between the last two scans. With a [`Retention`] policy, old runs are
+//! downsampled so the database stays small under nightly scans.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Utc};
+use rusqlite::{params, Connection};
+use spectra_core::{FileRecord, ScanStats};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// One recorded scan summary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub timestamp: i64,
+    pub total_files: u64,
+    pub total_folders: u64,
+    pub total_size_bytes: u64,
+    pub scan_duration_ms: u64,
+}
+
+/// Growth between the oldest and newest of the runs considered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trend {
+    pub runs: Vec<HistoryEntry>,
+    pub size_delta: i64,
+    pub files_delta: i64,
+}
+
+/// A top file that is new or larger in the newer of two runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileChange {
+    pub path: String,
+    pub size_bytes: u64,
+    /// Size in the older run's top list; `None` if it wasn't listed there
+    /// (new, or previously too small to make the list).
+    pub previous_size_bytes: Option<u64>,
+}
+
+/// What changed between the two most recent runs of a root.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanChanges {
+    pub older: HistoryEntry,
+    pub newer: HistoryEntry,
+    /// Largest first.
+    pub files: Vec<FileChange>,
+}
+
+/// How far back history is kept at full daily resolution, and then at weekly
+/// resolution. Older runs are kept one per month.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retention {
+    pub daily_days: u32,
+    pub weekly_days: u32,
+}
+
+impl Default for Retention {
+    /// Daily for a week, weekly for a month, monthly beyond.
+    fn default() -> Self {
+        Self {
+            daily_days: 7,
+            weekly_days: 30,
+        }
+    }
+}
+
+impl std::str::FromStr for Retention {
+    type Err = String;
+
+    /// `DAILY,WEEKLY` in days (e.g. `7,30`), or `default`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("default") {
+            return Ok(Self::default());
+        }
+        let parsed = s
+            .split_once(',')
+            .and_then(|(d, w)| Some((d.trim().parse().ok()?, w.trim().parse().ok()?)));
+        match parsed {
+            Some((daily_days, weekly_days)) if daily_days <= weekly_days => Ok(Self {
+                daily_days,
+                weekly_days,
+            }),
+            _ => Err(format!(
+                "'{}': expected DAILY,WEEKLY in days with DAILY <= WEEKLY, e.g. 7,30",
+                s
+            )),
+        }
+    }
+}
+
+impl Retention {
+    /// The downsampling bucket a run at `timestamp` falls in, seen from `now`.
+    fn bucket(&self, timestamp: i64, now: i64) -> (u8, i32, u32) {
+        let at = DateTime::from_timestamp(timestamp, 0).unwrap_or_default();
+        let age_days = (now - timestamp).max(0) / 86_400;
+        if age_days < i64::from(self.daily_days) {
+            (0, at.year(), at.ordinal())
+        } else if age_days < i64::from(self.weekly_days) {
+            let week = at.iso_week();
+            (1, week.year(), week.week())
+        } else {
+            (2, at.year(), at.month())
+        }
+    }
+}
+
+/// Default database location, following the XDG data directory convention
+/// (`%LOCALAPPDATA%` on Windows).
+pub fn default_history_path() -> PathBuf {
+    let data_dir = std::env::var("XDG_DATA_HOME")
+        .or_else(|_| std::env::var("LOCALAPPDATA"))
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| {
+            std::env::var("HOME")
+                .map(|home| PathBuf::from(home).join(".local").join("share"))
+                .unwrap_or_else(|_| PathBuf::from("."))
+        });
+    data_dir.join("spectra").join("history.db")
+}
+
+/// The key a root is stored under, so `.` and its absolute spelling agree.
+pub fn root_key(root: &Path) -> String {
+    std::fs::canonicalize(root)
+        .unwrap_or_else(|_| root.to_path_buf())
+        .display()
+        .to_string()
+}
+
+pub struct HistoryStore {
+    conn: Connection,
+}
+
+impl HistoryStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open history database {}", path.display()))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS scans (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 root_path TEXT NOT NULL,
+                 timestamp INTEGER NOT NULL,
+                 total_files INTEGER NOT NULL,
+                 total_folders INTEGER NOT NULL,
+                 total_size_bytes INTEGER NOT NULL,
+                 scan_duration_ms INTEGER NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS idx_scans_root ON scans (root_path, timestamp);
+             CREATE TABLE IF NOT EXISTS scan_top_files (
+                 scan_id INTEGER NOT NULL REFERENCES scans (id),
+                 path TEXT NOT NULL,
+                 size_bytes INTEGER NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS idx_top_files_scan ON scan_top_files (scan_id);",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// Append a summary row and the top-file list for `stats`, stored under
+    /// `root`.
+    pub fn record(&mut self, root: &str, stats: &ScanStats) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO scans (root_path, timestamp, total_files, total_folders,
+                                total_size_bytes, scan_duration_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                root,
+                if stats.scanned_at > 0 {
+                    stats.scanned_at
+                } else {
+                    Utc::now().timestamp()
+                },
+                stats.total_files as i64,
+                stats.total_folders as i64,
+                stats.total_size_bytes as i64,
+                stats.scan_duration_ms as i64,
+            ],
+        )?;
+        let scan_id = tx.last_insert_rowid();
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO scan_top_files (scan_id, path, size_bytes) VALUES (?1, ?2, ?3)",
+            )?;
+            for file in &stats.top_files {
+                insert.execute(params![scan_id, file.path, file.size_bytes as i64])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// The last `limit` runs for `root`, oldest first.
+    pub fn recent(&self, root: &str, limit: usize) -> Result<Vec<HistoryEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT timestamp, total_files, total_folders, total_size_bytes, scan_duration_ms
+             FROM scans WHERE root_path = ?1
+             ORDER BY id DESC LIMIT ?2",
+        )?;
+        let mut runs = stmt
+            .query_map(params![root, limit as i64], |row| {
+                Ok(HistoryEntry {
+                    timestamp: row.get(0)?,
+                    total_files: row.get::<_, i64>(1)? as u64,
+                    total_folders: row.get::<_, i64>(2)? as u64,
+                    total_size_bytes: row.get::<_, i64>(3)? as u64,
+                    scan_duration_ms: row.get::<_, i64>(4)? as u64,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        runs.reverse();
+        Ok(runs)
+    }
+
+    /// Top files that appeared or grew by at least `min_size_bytes` between
+    /// the two most recent runs of `root`. `None` with fewer than two runs.
+    pub fn changes(&self, root: &str, min_size_bytes: u64) -> Result<Option<ScanChanges>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, timestamp, total_files, total_folders, total_size_bytes, scan_duration_ms
+             FROM scans WHERE root_path = ?1
+             ORDER BY id DESC LIMIT 2",
+        )?;
+        let mut runs = stmt
+            .query_map(params![root], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    HistoryEntry {
+                        timestamp: row.get(1)?,
+                        total_files: row.get::<_, i64>(2)? as u64,
+                        total_folders: row.get::<_, i64>(3)? as u64,
+                        total_size_bytes: row.get::<_, i64>(4)? as u64,
+                        scan_duration_ms: row.get::<_, i64>(5)? as u64,
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        // Newest first, so the older run pops off the end.
+        let (Some((older_id, older)), Some((newer_id, newer))) = (runs.pop(), runs.pop()) else {
+            return Ok(None);
+        };
+
+        let previous: HashMap<String, u64> = self
+            .top_files(older_id)?
+            .into_iter()
+            .map(|f| (f.path, f.size_bytes))
+            .collect();
+        let mut files: Vec<FileChange> = self
+            .top_files(newer_id)?
+            .into_iter()
+            .filter_map(|file| {
+                let previous_size_bytes = previous.get(&file.path).copied();
+                let growth = file
+                    .size_bytes
+                    .saturating_sub(previous_size_bytes.unwrap_or(0));
+                (growth > 0 && growth >= min_size_bytes).then_some(FileChange {
+                    path: file.path,
+                    size_bytes: file.size_bytes,
+                    previous_size_bytes,
+                })
+            })
+            .collect();
+        files.sort_by_key(|f| std::cmp::Reverse(f.size_bytes));
+        Ok(Some(ScanChanges {
+            older,
+            newer,
+            files,
+        }))
+    }
+
+    fn top_files(&self, scan_id: i64) -> Result<Vec<FileRecord>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, size_bytes FROM scan_top_files WHERE scan_id = ?1")?;
+        let files = stmt
+            .query_map(params![scan_id], |row| {
+                Ok(FileRecord {
+                    path: row.get(0)?,
+                    size_bytes: row.get::<_, i64>(1)? as u64,
+                    allocated_bytes: None,
+                    is_dir: false,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(files)
+    }
+
+    /// Downsample `root`'s history per `retention`, keeping the newest run
+    /// in each day/week/month bucket. The two most recent runs are always
+    /// kept so `changes` still has a pair to compare. Returns the number of
+    /// runs removed.
+    pub fn compact(&mut self, root: &str, retention: &Retention, now: i64) -> Result<usize> {
+        let runs: Vec<(i64, i64)> = {
+            let mut stmt = self
+                .conn
+                .prepare("SELECT id, timestamp FROM scans WHERE root_path = ?1 ORDER BY id DESC")?;
+            let rows = stmt
+                .query_map(params![root], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            rows
+        };
+
+        // Newest first: the first run seen in a bucket is the one kept. The
+        // two newest are kept unconditionally but still claim their buckets.
+        let mut seen: HashSet<_> = runs
+            .iter()
+            .take(2)
+            .map(|(_, timestamp)| retention.bucket(*timestamp, now))
+            .collect();
+        let doomed: Vec<i64> = runs
+            .iter()
+            .skip(2)
+            .filter(|(_, timestamp)| !seen.insert(retention.bucket(*timestamp, now)))
+            .map(|(id, _)| *id)
+            .collect();
+
+        let tx = self.conn.transaction()?;
+        for id in &doomed {
+            tx.execute("DELETE FROM scan_top_files WHERE scan_id = ?1", params![id])?;
+            tx.execute("DELETE FROM scans WHERE id = ?1", params![id])?;
+        }
+        tx.commit()?;
+        Ok(doomed.len())
+    }
+
+    /// Growth over the last `limit` runs, or `None` if `root` was never scanned.
+    pub fn trend(&self, root: &str, limit: usize) -> Result<Option<Trend>> {
+        let runs = self.recent(root, limit)?;
+        let (Some(first), Some(last)) = (runs.first(), runs.last()) else {
+            return Ok(None);
+        };
+        let size_delta = last.total_size_bytes as i64 - first.total_size_bytes as i64;
+        let files_delta = last.total_files as i64 - first.total_files as i64;
+        Ok(Some(Trend {
+            runs,
+            size_delta,
+            files_delta,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectra_core::Scanner;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_two_scans_append_rows_and_trend_reports_delta() {
+        let data = tempdir().unwrap();
+        let db_dir = tempdir().unwrap();
+        let mut store = HistoryStore::open(&db_dir.path().join("history.db")).unwrap();
+        let root = root_key(data.path());
+
+        std::fs::write(data.path().join("a.bin"), vec![0u8; 1000]).unwrap();
+        store
+            .record(&root, &Scanner::new(data.path(), 5).scan().unwrap())
+            .unwrap();
+
+        std::fs::write(data.path().join("b.bin"), vec![0u8; 500]).unwrap();
+        store
+            .record(&root, &Scanner::new(data.path(), 5).scan().unwrap())
+            .unwrap();
+
+        let trend = store.trend(&root, 10).unwrap().unwrap();
+        assert_eq!(trend.runs.len(), 2);
+        assert_eq!(trend.size_delta, 500);
+        assert_eq!(trend.files_delta, 1);
+        assert!(store.trend("/never/scanned", 10).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_changes_lists_new_large_file() {
+        let data = tempdir().unwrap();
+        let db_dir = tempdir().unwrap();
+        let mut store = HistoryStore::open(&db_dir.path().join("history.db")).unwrap();
+        let root = root_key(data.path());
+
+        std::fs::write(data.path().join("old.bin"), vec![0u8; 1000]).unwrap();
+        store
+            .record(&root, &Scanner::new(data.path(), 5).scan().unwrap())
+            .unwrap();
+        assert!(store.changes(&root, 0).unwrap().is_none());
+
+        std::fs::write(data.path().join("huge.iso"), vec![0u8; 50_000]).unwrap();
+        std::fs::write(data.path().join("tiny.txt"), vec![0u8; 10]).unwrap();
+        store
+            .record(&root, &Scanner::new(data.path(), 5).scan().unwrap())
+            .unwrap();
+
+        let changes = store.changes(&root, 100).unwrap().unwrap();
+        assert_eq!(changes.newer.total_files, 3);
+        assert_eq!(changes.files.len(), 1);
+        assert!(changes.files[0].path.ends_with("huge.iso"));
+        assert_eq!(changes.files[0].size_bytes, 50_000);
+        assert_eq!(changes.files[0].previous_size_bytes, None);
+    }
+
+    #[test]
+    fn test_compact_downsamples_by_age() {
+        let db_dir = tempdir().unwrap();
+        let mut store = HistoryStore::open(&db_dir.path().join("history.db")).unwrap();
+        // 2025-06-30T12:00:00Z; one run every 6 hours for 90 days.
+        let now = 1_751_284_800;
+        for k in (0..360).rev() {
+            let stats = ScanStats {
+                scanned_at: now - k * 6 * 3600,
+                top_files: vec![FileRecord {
+                    path: "/data/big.bin".to_string(),
+                    size_bytes: 1,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+            store.record("/data", &stats).unwrap();
+        }
+        store.record("/other", &ScanStats::default()).unwrap();
+
+        let removed = store.compact("/data", &Retention::default(), now).unwrap();
+        let left = store.recent("/data", 1000).unwrap();
+        assert_eq!(left.len() + removed, 360);
+
+        let retention = Retention::default();
+        let buckets: HashSet<_> = left
+            .iter()
+            .map(|run| retention.bucket(run.timestamp, now))
+            .collect();
+        // One run per bucket, except the newest two which share today.
+        assert_eq!(buckets.len(), left.len() - 1);
+        let daily = left
+            .iter()
+            .filter(|r| now - r.timestamp < 7 * 86_400)
+            .count();
+        let monthly = left
+            .iter()
+            .filter(|r| now - r.timestamp >= 30 * 86_400)
+            .count();
+        // Today twice, then seven earlier calendar days (the oldest only
+        // partly inside the window).
+        assert_eq!(daily, 9);
+        assert_eq!(monthly, 2); // April and May 2025
+        assert!(left.len() < 20);
+
+        // Orphaned top files are removed with their runs, other roots untouched.
+        let top_rows: i64 = store
+            .conn
+            .query_row("SELECT COUNT(*) FROM scan_top_files", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(top_rows as usize, left.len());
+        assert_eq!(store.recent("/other", 10).unwrap().len(), 1);
+        assert_eq!("7,30".parse(), Ok(Retention::default()));
+        assert!("30,7".parse::<Retention>().is_err());
+    }
+}
@@ -0,0 +1,92 @@
+//! Shared blocking HTTP client for server and webhook calls.
+
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use std::time::Duration;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// A client with a request timeout so a hung endpoint can't stall the CLI.
+pub fn client() -> Client {
+    Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_else(|_| Client::new())
+}
+
+/// The server reads its key from the same variable.
+const API_KEY_ENV: &str = "SPECTRA_API_KEY";
+
+/// Attach `Authorization: Bearer <key>` to a Spectra server request when an
+/// API key is configured. Not for third-party endpoints such as webhooks.
+pub fn with_api_key(request: RequestBuilder) -> RequestBuilder {
+    match std::env::var(API_KEY_ENV) {
+        Ok(key) if !key.is_empty() => request.bearer_auth(key),
+        _ => request,
+    }
+}
+
+/// Send a request, retrying connection errors and 5xx responses with linear
+/// backoff. `make_request` is called once per attempt.
+pub fn send_with_retry<F>(make_request: F) -> reqwest::Result<Response>
+where
+    F: Fn() -> RequestBuilder,
+{
+    let mut attempt = 1;
+    loop {
+        let result = make_request().send();
+        let retryable = match &result {
+            Ok(response) => response.status().is_server_error(),
+            Err(e) => e.is_connect() || e.is_timeout(),
+        };
+        if !retryable || attempt >= MAX_ATTEMPTS {
+            return result;
+        }
+        std::thread::sleep(RETRY_BACKOFF * attempt);
+        attempt += 1;
+    }
+}
+
+/// A one-shot local endpoint for testing the calls above.
+#[cfg(test)]
+pub mod mock {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    /// Accept one request, reply 200, and hand back the request line and
+    /// body. Returns the server's base URL (`http://127.0.0.1:<port>`).
+    pub fn server() -> (String, JoinHandle<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0u8; content_length];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                .unwrap();
+            (request_line, String::from_utf8(body).unwrap())
+        });
+        (url, handle)
+    }
+}
@@ -6,237 +6,963 @@
 // See LICENSE-MIT and LICENSE-APACHE in the repository root for full license texts.
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use humansize::{format_size, DECIMAL};
 use indicatif::{ProgressBar, ProgressStyle};
-use jwalk::WalkDir;
-use serde::Serialize;
-use std::collections::HashMap;
-use std::path::PathBuf;
-use std::time::Duration;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 // Import core scanner
+use spectra_core::category;
+use spectra_core::dedup::{self, DedupConfig, DedupSavings, DuplicateGroup};
+use spectra_core::hash::{analyze_file, HashFilter};
 use spectra_core::{
-    ExtensionStat, FileRecord as CoreFileRecord, ScanCache, ScanStats as CoreScanStats, Scanner,
+    AnalyzedFile, CaseSensitivity, ExtensionStat, ScanCache, ScanError, ScanReport, Scanner,
+    SymlinkMode,
 };
 
+mod alert;
+use alert::AlertRule;
+
 mod analysis;
 use analysis::{
-    analyze_filename_risk, calculate_shannon_entropy, detect_outliers, RiskLevel, SemanticEngine,
+    assess_blocks, assess_encryption, assess_with, average_entropy_by_extension,
+    calculate_shannon_entropy, detect_outliers, entropy_profile, load_risk_rules, RiskRuleSet,
+    SemanticEngine, EXTENSION_ENTROPY_SAMPLES, MIN_ENTROPY_BLOCK_SIZE,
 };
 
+mod diff;
+mod display;
+mod error_output;
+use error_output::ErrorReport;
+
+mod exclude_file;
+use exclude_file::ExcludeList;
+
+mod ext_tail;
+
 mod governance;
-use governance::engine::{Action, Policy, Rule};
+use governance::audit::AuditLog;
+use governance::engine::{Action, Policy};
+use governance::evaluation;
+use governance::policy_diff::{self, PolicyDiff};
+use governance::policy_file;
+use governance::remote;
+use governance::runner::GovernanceRunner;
+use governance::structure::StructureSpec;
+use governance::transaction::{self, TransactionLog};
+
+mod history;
+mod http;
+use history::HistoryStore;
+
+mod output;
+use output::{NdjsonWriter, OutputFormat};
+
+mod manifest;
+use manifest::ProjectManifest;
+
+mod paths_file;
+mod profile;
+mod since;
+mod snapshot;
+use paths_file::ScanTargets;
+use snapshot::UploadGrouping;
+
+#[cfg(feature = "tui")]
+mod tui;
 
 mod watch;
+mod webhook;
+
+/// Process exit status when an `--alert-if` threshold is breached or `lint`
+/// finds violations.
+const ALERT_EXIT_CODE: i32 = 2;
+
+/// Roughly how many blocks each `--deep-entropy` profile has (fewer for
+/// files smaller than this many `MIN_ENTROPY_BLOCK_SIZE` blocks).
+const ENTROPY_PROFILE_BLOCKS: u64 = 64;
 
 /// S.P.E.C.T.R.A.
 /// Scalable Platform for Enterprise Content Topology & Resource Analytics
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 struct Args {
     /// The root directory to scan
     #[arg(short, long, default_value = ".")]
     path: String,
 
-    /// Output detailed JSON logs instead of human summary
-    #[arg(short, long)]
+    /// Scan every root listed in a file (one per line; `!path` excludes a subtree)
+    #[arg(long, conflicts_with = "path")]
+    paths_file: Option<String>,
+
+    /// Skip entries matching a gitignore-style glob (repeatable), e.g.
+    /// `--exclude node_modules --exclude target/ --exclude '*.tmp'`
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Skip every path or glob listed in a file, one per line; paths may be
+    /// absolute or relative to the scan root
+    #[arg(long, value_name = "FILE")]
+    exclude_paths_from: Option<String>,
+
+    /// Only count files with these extensions (comma-separated or repeated),
+    /// e.g. `--ext mp4,mov,mkv`. Other files are walked past but not counted
+    #[arg(long, value_name = "EXT", value_delimiter = ',')]
+    ext: Vec<String>,
+
+    /// Report format: human (default), json, csv or markdown
+    #[arg(long, value_name = "FORMAT")]
+    format: Option<OutputFormat>,
+
+    /// Deprecated alias for `--format json`
+    #[arg(short, long, conflicts_with = "format")]
     json: bool,
 
+    /// With `--format csv`, append a per-extension table after the top files
+    #[arg(long)]
+    csv_extensions: bool,
+
     /// Number of top largest files to track
     #[arg(short, long, default_value_t = 10)]
     limit: usize,
 
+    /// Tag the report (e.g. prod-nfs-weekly); stored as `label` in JSON
+    /// output and shown by `diff`
+    #[arg(long, value_name = "LABEL")]
+    label: Option<String>,
+
+    /// Only compute totals (files, folders, size): no extension stats, top
+    /// files or top directories. Fastest on huge trees
+    #[arg(long, conflicts_with_all = ["analyze", "explain", "semantic", "hash", "duplicates", "detect_mime", "deep_entropy", "risk_rules", "dedup_report"])]
+    summary_only: bool,
+
+    /// Also group files by MIME type sniffed from their first bytes, catching
+    /// mislabeled and extensionless files
+    #[arg(long)]
+    detect_mime: bool,
+
+    /// Rank directories, by the total size below them, alongside files in
+    /// the top list
+    #[arg(long)]
+    include_dirs: bool,
+
+    /// Show on-disk allocated size and its ratio to logical size, flagging
+    /// sparse files and block slack (Unix)
+    #[arg(long)]
+    allocated: bool,
+
     /// Enable Phase 2 semantic analysis (entropy, risk scoring)
     #[arg(long)]
     analyze: bool,
 
+    /// Explain why each analyzed file received its risk level (implies --analyze)
+    #[arg(long)]
+    explain: bool,
+
+    /// Compute entropy over each analyzed file's full contents instead of
+    /// its first 8KB, plus a block entropy profile that flags partially
+    /// encrypted files (reads every byte; implies --analyze)
+    #[arg(long)]
+    deep_entropy: bool,
+
+    /// Extra filename risk rules (TOML or JSON list of `{ pattern, level }`),
+    /// checked alongside the built-in patterns (implies --analyze)
+    #[arg(long, value_name = "FILE")]
+    risk_rules: Option<String>,
+
     /// Enable AI-based content classification (requires 'semantic' feature)
     #[arg(long)]
     semantic: bool,
 
+    /// Browse the scan in an interactive terminal UI instead of printing a
+    /// report (requires 'tui' feature)
+    #[arg(long, conflicts_with_all = ["json", "format", "watch"])]
+    tui: bool,
+
+    /// Minimum score for a semantic label to be reported
+    #[arg(long, default_value_t = analysis::semantic::DEFAULT_MIN_CONFIDENCE)]
+    semantic_min_confidence: f64,
+
+    /// Number of semantic labels kept per file (the top one is displayed)
+    #[arg(long, default_value_t = analysis::semantic::DEFAULT_TOP_K)]
+    semantic_top_k: usize,
+
     /// URL of the Spectra Server for federation
     #[arg(long)]
     server: Option<String>,
 
+    /// Load governance policies from a local YAML file (a list of policies);
+    /// combined with the server's when --server is also given
+    #[arg(long, value_name = "FILE")]
+    policy_file: Option<PathBuf>,
+
+    /// Key uploaded extension stats by raw extension, by category (images, video,
+    /// code, ...) or both [extensions|categories|both]
+    #[arg(long, default_value = "extensions", value_name = "GROUPING")]
+    upload_grouping: UploadGrouping,
+
     /// Enable Active Governance (Execute policies - defaults to dry-run)
     #[arg(long)]
     enforce: bool,
 
+    /// Let enforced Delete policies and Deduplicate removals delete files
+    /// permanently instead of moving them to the OS trash
+    #[arg(long)]
+    hard_delete: bool,
+
     /// Watch directory for real-time changes after scanning
     #[arg(long)]
     watch: bool,
-}
 
-// CLI-specific FileRecord WITH analysis fields
-#[derive(Debug, Serialize)]
-struct AnalyzedFileRecord {
-    path: String,
-    size_bytes: u64,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    entropy: Option<f32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    risk_level: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    semantic_tag: Option<String>,
-    /// Whether this file is a statistical entropy outlier (IQR method)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    entropy_outlier: Option<bool>,
+    /// Case handling for extensions, exclusions and policy rules:
+    /// platform (default), sensitive or insensitive
+    #[arg(long)]
+    case_sensitivity: Option<CaseSensitivity>,
+
+    /// Exit with status 2 (and notify --webhook) when the expression holds,
+    /// e.g. 'total_bytes>500GB' or 'ext:log>10GB'. Repeatable
+    #[arg(long = "alert-if", value_name = "EXPR")]
+    alert_if: Vec<AlertRule>,
+
+    /// POST a JSON summary of the scan to this webhook URL
+    #[arg(long)]
+    webhook: Option<String>,
+
+    /// Send a Slack-style `{"text": ...}` message instead of the JSON summary.
+    /// Placeholders: {root}, {files}, {folders}, {size}, {duration_ms}
+    #[arg(long, requires = "webhook")]
+    webhook_template: Option<String>,
+
+    /// Print paths verbatim in the human report instead of escaping control
+    /// characters (newlines, ANSI escapes) in file names
+    #[arg(long)]
+    raw_paths: bool,
+
+    /// Show file paths relative to the scan root in the report
+    #[arg(long)]
+    relative_paths: bool,
+
+    /// Estimate very large directories by stat'ing only this fraction (0.0-1.0]
+    /// of their files
+    #[arg(long, value_name = "FRACTION")]
+    sample: Option<f64>,
+
+    /// Quick scan of recent activity: count only files modified since TIME
+    /// (24h, 7d, 2025-01-31 or RFC 3339) and skip directories untouched since then
+    #[arg(long, value_name = "TIME", value_parser = since::parse_since)]
+    changed_since: Option<std::time::SystemTime>,
+
+    /// Stop scanning after DURATION (e.g. 90s, 5m) and report what was
+    /// gathered so far, marked partial
+    #[arg(long, value_name = "DURATION", value_parser = since::parse_duration)]
+    time_budget: Option<Duration>,
+
+    /// Leave files smaller than SIZE (e.g. 1KB) out of the extension stats;
+    /// they still count in the totals
+    #[arg(long, value_name = "SIZE", value_parser = alert::parse_size_arg)]
+    ext_min_size: Option<u64>,
+
+    /// Group extensions smaller than SIZE (e.g. 10MB) or PERCENT (e.g. 1%) of
+    /// the total into a single "other" entry in the report
+    #[arg(long, value_name = "SIZE|PERCENT")]
+    ext_tail_threshold: Option<ext_tail::TailThreshold>,
+
+    /// Follow symbolic links while scanning (same as --symlinks follow)
+    #[arg(long, conflicts_with = "symlinks")]
+    follow_links: bool,
+
+    /// How to treat symbolic links: skip (default), follow, or count-as-file
+    /// (count each link once by its own size)
+    #[arg(long, value_name = "MODE")]
+    symlinks: Option<SymlinkMode>,
+
+    /// BLAKE3-hash the top files (content_hash in the report)
+    #[arg(long)]
+    hash: bool,
+
+    /// Only hash files with these extensions (comma-separated or repeated;
+    /// implies --hash)
+    #[arg(long, value_name = "EXT", value_delimiter = ',')]
+    hash_ext: Vec<String>,
+
+    /// Only hash files at least this large, e.g. 100MB (implies --hash)
+    #[arg(long, value_name = "SIZE", value_parser = alert::parse_size_arg)]
+    hash_min_size: Option<u64>,
+
+    /// Count .git directories (normally skipped as hidden), each shown as one
+    /// rolled-up entry in the top files instead of listing its objects
+    #[arg(long)]
+    git_rollup: bool,
+
+    /// List the paths of broken (dangling) symlinks in the report
+    #[arg(long)]
+    list_broken_symlinks: bool,
+
+    /// Collapse top files reached through several paths (symlinks, hard links)
+    /// into one entry listing its aliases
+    #[arg(long)]
+    dedup: bool,
+
+    /// Find byte-for-byte duplicate files (size, then BLAKE3 content hash)
+    /// and report the space reclaimable by keeping one copy of each
+    #[arg(long)]
+    duplicates: bool,
+
+    /// Summarise what removing duplicates would free (keeping one copy per
+    /// group), broken down by extension and directory (implies --duplicates)
+    #[arg(long)]
+    dedup_report: bool,
+
+    /// Also total the size of every directory down to DEPTH levels below
+    /// the root, like `du --max-depth` (0 = the root only)
+    #[arg(long, value_name = "DEPTH")]
+    breakdown: Option<usize>,
+
+    /// Don't count the scan root itself in the folder total
+    #[arg(long)]
+    no_root_folder: bool,
+
+    /// Report per-walker-thread file counts and where scan time went
+    /// (walk vs metadata vs hashing/analysis) to diagnose slow scans
+    #[arg(long)]
+    profile: bool,
+
+    /// Pre-count files before scanning to show an exact percentage (walks
+    /// every directory twice)
+    #[arg(long)]
+    accurate_progress: bool,
+
+    /// Walker threads for the scan (default: tuned to the detected device;
+    /// lower it for NAS mounts and spinning disks)
+    #[arg(long)]
+    threads: Option<usize>,
+
+    /// Cap walked entries buffered ahead of aggregation (default: unbounded)
+    #[arg(long)]
+    queue_bound: Option<usize>,
+
+    /// Only track directory totals this many levels below the root; deeper
+    /// files count toward their ancestor at that depth (bounds memory on
+    /// huge trees)
+    #[arg(long)]
+    rollup_depth: Option<usize>,
+
+    /// SQLite scan history location (default: ~/.local/share/spectra/history.db)
+    #[arg(long, global = true)]
+    history_db: Option<String>,
+
+    /// Don't append this scan to the local history
+    #[arg(long)]
+    no_history: bool,
+
+    /// After recording, downsample this root's history: keep one run per day
+    /// for DAILY days, per week up to WEEKLY days, per month beyond
+    /// (DAILY,WEEKLY in days, e.g. 7,30, or "default")
+    #[arg(long, value_name = "DAILY,WEEKLY")]
+    history_retention: Option<history::Retention>,
+
+    /// Worker threads for governance policy evaluation (default: one per CPU)
+    #[arg(long)]
+    governance_threads: Option<usize>,
+
+    /// How policies matching the same file combine: all-match (default, every
+    /// match acts) or first-match (only the highest-priority match acts)
+    #[arg(long)]
+    policy_match: Option<evaluation::MatchMode>,
+
+    /// Record enforced deletions in a JSONL transaction log, keeping the files
+    /// recoverable in a trash directory next to the log
+    #[arg(long)]
+    txn_log: Option<String>,
+
+    /// Append one JSON line per governance action (policy, action, path, size,
+    /// outcome, dry-run flag) to this file
+    #[arg(long, value_name = "PATH")]
+    audit_log: Option<PathBuf>,
+
+    /// Hours a transactionally deleted file stays recoverable before it is purged
+    #[arg(long, default_value_t = 72)]
+    grace_hours: u64,
+
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-// Conversion from core FileRecord to analyzed FileRecord
-impl From<CoreFileRecord> for AnalyzedFileRecord {
-    fn from(core: CoreFileRecord) -> Self {
-        Self {
-            path: core.path,
-            size_bytes: core.size_bytes,
-            entropy: None,
-            risk_level: None,
-            semantic_tag: None,
-            entropy_outlier: None,
+impl Args {
+    /// `--format`, with the deprecated `--json` mapping to JSON.
+    fn output_format(&self) -> OutputFormat {
+        if self.json {
+            OutputFormat::Json
+        } else {
+            self.format.unwrap_or_default()
         }
     }
 }
 
-// CLI-specific stats structure WITH analyzed files
-#[derive(Serialize, Debug, Default)]
-struct CliScanStats {
-    root_path: String,
-    total_files: u64,
-    total_folders: u64,
-    total_size_bytes: u64,
-    scan_duration_ms: u128,
-    extensions: HashMap<String, ExtensionStat>,
-    top_files: Vec<AnalyzedFileRecord>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    device_type: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    threads_used: Option<usize>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    cache_hits: Option<usize>,
-}
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Restore files deleted by an enforce run that are still in the trash
+    GovernRollback {
+        /// Transaction log written by `--txn-log`
+        #[arg(long)]
+        txn: String,
+    },
+    /// Compare two reports saved with `--json`
+    Diff {
+        /// The earlier report
+        old: String,
+        /// The later report
+        new: String,
+    },
+    /// Show how a root has grown over its most recent scans
+    Trend {
+        /// Scan root to report on
+        #[arg(short, long, default_value = ".")]
+        path: String,
 
-// Conversion from core ScanStats to CLI ScanStats
-impl From<CoreScanStats> for CliScanStats {
-    fn from(core: CoreScanStats) -> Self {
-        Self {
-            root_path: core.root_path,
-            total_files: core.total_files,
-            total_folders: core.total_folders,
-            total_size_bytes: core.total_size_bytes,
-            scan_duration_ms: core.scan_duration_ms,
-            extensions: core.extensions,
-            top_files: core.top_files.into_iter().map(Into::into).collect(),
-            device_type: core.device_type.map(|d| format!("{:?}", d)),
-            threads_used: core.threads_used,
-            cache_hits: None,
-        }
-    }
+        /// Number of recent runs to include
+        #[arg(short = 'n', long, default_value_t = 10)]
+        runs: usize,
+    },
+    /// List large files that appeared or grew between a root's last two scans
+    Changes {
+        /// Scan root to report on
+        #[arg(short, long, default_value = ".")]
+        path: String,
+
+        /// Only list files that appeared or grew by at least this much, e.g. 100MB
+        #[arg(long, default_value = "0", value_parser = alert::parse_size_arg)]
+        min_size: u64,
+    },
+    /// Check a tree against an expected structure (required directories,
+    /// forbidden files); exits with status 2 on violations
+    Lint {
+        /// Structure spec (TOML, YAML or JSON)
+        #[arg(long)]
+        spec: String,
+
+        /// Root to check
+        #[arg(short, long, default_value = ".")]
+        path: String,
+    },
+    /// Scan and govern the roots declared in a project manifest, with its
+    /// ignore rules, categories, risk patterns, policies and output settings
+    Run {
+        /// Project manifest (TOML)
+        #[arg(long, default_value = "spectra.project.toml")]
+        manifest: String,
+    },
+    /// Compare two policy sets and report added, removed and changed
+    /// policies by name
+    PolicyDiff {
+        /// Old policy file (TOML with [[policies]])
+        old: String,
+        /// New policy file
+        new: String,
+    },
 }
 
 // Helper: Fetch policies from server
 fn fetch_policies(server_url: &str) -> Vec<Policy> {
     let url = format!("{}/api/v1/policies", server_url);
-    match reqwest::blocking::get(&url) {
+    let client = http::client();
+    match http::send_with_retry(|| http::with_api_key(client.get(&url))) {
+        Ok(response) if !response.status().is_success() => {
+            eprintln!(
+                "⚠️  Failed to fetch policies: server responded with status {}",
+                response.status()
+            );
+            Vec::new()
+        }
         Ok(response) => {
             if let Ok(policies) = response.json::<Vec<serde_json::Value>>() {
-                // Parse server policies into our Policy format
                 policies
-                    .into_iter()
-                    .filter_map(|p| {
-                        Some(Policy {
-                            name: p.get("name")?.as_str()?.to_string(),
-                            rule: Rule {
-                                extension: Some("log".to_string()), // Simplified parsing
-                                min_size_bytes: None,
-                                min_age_days: Some(90),
-                            },
-                            action: Action::Report, // Default to Report for safety
-                        })
+                    .iter()
+                    .filter_map(|p| match remote::parse_policy(p) {
+                        Ok(policy) => Some(policy),
+                        Err(e) => {
+                            eprintln!("⚠️  Skipping server policy: {}", e);
+                            None
+                        }
                     })
                     .collect()
             } else {
-                println!("⚠️  Failed to parse policies from server");
+                eprintln!("⚠️  Failed to parse policies from server");
                 Vec::new()
             }
         }
         Err(e) => {
-            println!("⚠️  Failed to fetch policies: {}", e);
+            eprintln!("⚠️  Failed to fetch policies: {}", e);
             Vec::new()
         }
     }
 }
 
-// Helper: Upload snapshot to server
-fn upload_snapshot(server_url: &str, stats: &CliScanStats) {
-    let url = format!("{}/api/v1/ingest", server_url);
-    let client = reqwest::blocking::Client::new();
-
-    // Extract top extensions for the snapshot
-    let mut sorted_exts: Vec<(&String, &ExtensionStat)> = stats.extensions.iter().collect();
-    sorted_exts.sort_by(|a, b| b.1.size.cmp(&a.1.size));
-    let top_extensions: Vec<(String, u64)> = sorted_exts
-        .iter()
-        .take(10)
-        .map(|(ext, stat)| (ext.to_string(), stat.size))
-        .collect();
+fn run_command(command: &Command, args: &Args) -> Result<()> {
+    if matches!(
+        args.output_format(),
+        OutputFormat::Csv | OutputFormat::Markdown
+    ) && !matches!(command, Command::Run { .. })
+    {
+        anyhow::bail!("--format csv and markdown are only supported for scans");
+    }
+    match command {
+        Command::Run { manifest } => run_project(args, Path::new(manifest)),
+        Command::PolicyDiff { old, new } => {
+            let old = policy_diff::load_policies(Path::new(old))?;
+            let new = policy_diff::load_policies(Path::new(new))?;
+            let diff = PolicyDiff::between(&old, &new);
+            if args.output_format() == OutputFormat::Json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print!("{}", diff);
+            }
+            Ok(())
+        }
+        Command::GovernRollback { txn } => {
+            let report = transaction::rollback(Path::new(txn))?;
+            for path in &report.restored {
+                println!("♻️  Restored: {}", path);
+            }
+            for path in &report.skipped {
+                println!(
+                    "⚠️  Skipped (original path occupied or trash copy changed): {}",
+                    path
+                );
+            }
+            for path in &report.expired {
+                println!("⌛ Expired (already purged): {}", path);
+            }
+            for path in &report.never_moved {
+                println!("⏸️  Never moved (still at its original path): {}", path);
+            }
+            for error in &report.errors {
+                eprintln!("❌ Failed: {}", error);
+            }
+            println!(
+                "✅ Rollback complete: {} restored, {} skipped, {} expired, {} never moved, {} failed",
+                report.restored.len(),
+                report.skipped.len(),
+                report.expired.len(),
+                report.never_moved.len(),
+                report.errors.len()
+            );
+            Ok(())
+        }
+        Command::Diff { old, new } => {
+            let old = diff::load_report(Path::new(old))?;
+            let new = diff::load_report(Path::new(new))?;
+            // Extensions compare case-insensitively unless asked otherwise,
+            // so reports from scans with different settings line up.
+            let case = args
+                .case_sensitivity
+                .unwrap_or(CaseSensitivity::Insensitive);
+            let changes = old.diff_with_case(&new, case);
+            if args.output_format() == OutputFormat::Json {
+                println!("{}", serde_json::to_string_pretty(&changes)?);
+            } else {
+                println!(
+                    "🔍 Comparing {} → {}",
+                    diff::describe(&old),
+                    diff::describe(&new)
+                );
+                print!("{}", diff::render(&changes));
+            }
+            Ok(())
+        }
+        Command::Trend { path, runs } => {
+            let store = HistoryStore::open(&history_path(args))?;
+            let root = history::root_key(Path::new(path));
+            let Some(trend) = store.trend(&root, *runs)? else {
+                println!(
+                    "No scan history for {}",
+                    display::path(&root, args.raw_paths)
+                );
+                return Ok(());
+            };
 
-    let snapshot = serde_json::json!({
-        "agent_id": format!("agent_{}", chrono::Utc::now().timestamp()),
-        "timestamp": chrono::Utc::now().timestamp(),
-        "hostname": std::env::var("COMPUTERNAME").or_else(|_| std::env::var("HOSTNAME")).unwrap_or_else(|_| "unknown".to_string()),
-        "total_size_bytes": stats.total_size_bytes,
-        "file_count": stats.total_files,
-        "top_extensions": top_extensions,
-    });
+            println!(
+                "📈 Trend for {} (last {} runs)",
+                display::path(&root, args.raw_paths),
+                trend.runs.len()
+            );
+            println!("------------------------------------------------");
+            for run in &trend.runs {
+                let when = chrono::DateTime::from_timestamp(run.timestamp, 0)
+                    .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| run.timestamp.to_string());
+                println!(
+                    "   {}  {:>10}  {:>8} files",
+                    when,
+                    format_size(run.total_size_bytes, DECIMAL),
+                    run.total_files
+                );
+            }
+            println!("------------------------------------------------");
+            let sign = if trend.size_delta < 0 { "-" } else { "+" };
+            println!(
+                "Δ Size : {}{}",
+                sign,
+                format_size(trend.size_delta.unsigned_abs(), DECIMAL)
+            );
+            println!("Δ Files: {:+}", trend.files_delta);
+            Ok(())
+        }
+        Command::Changes { path, min_size } => {
+            let store = HistoryStore::open(&history_path(args))?;
+            let root = history::root_key(Path::new(path));
+            let Some(changes) = store.changes(&root, *min_size)? else {
+                println!(
+                    "Need at least two recorded scans of {}",
+                    display::path(&root, args.raw_paths)
+                );
+                return Ok(());
+            };
 
-    match client.post(&url).json(&snapshot).send() {
-        Ok(response) => {
-            if response.status().is_success() {
-                println!("📤 Snapshot uploaded successfully to {}", server_url);
-            } else {
-                println!("⚠️  Server responded with status: {}", response.status());
+            let when = |ts: i64| {
+                chrono::DateTime::from_timestamp(ts, 0)
+                    .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| ts.to_string())
+            };
+            println!(
+                "🆕 Changes in {} between {} and {}",
+                display::path(&root, args.raw_paths),
+                when(changes.older.timestamp),
+                when(changes.newer.timestamp)
+            );
+            println!("------------------------------------------------");
+            if changes.files.is_empty() {
+                println!("   No new or grown files in the top list");
             }
+            for file in &changes.files {
+                let note = match file.previous_size_bytes {
+                    Some(prev) => format!("grew from {}", format_size(prev, DECIMAL)),
+                    None => "new".to_string(),
+                };
+                println!(
+                    "   {:>10}  ({})  {}",
+                    format_size(file.size_bytes, DECIMAL),
+                    note,
+                    display::path(&file.path, args.raw_paths)
+                );
+            }
+            Ok(())
         }
-        Err(e) => {
-            println!("⚠️  Failed to upload snapshot: {}", e);
+        Command::Lint { spec, path } => {
+            let spec = StructureSpec::load(Path::new(spec))?;
+            let violations = spec.check(Path::new(path));
+            if args.output_format() == OutputFormat::Json {
+                println!("{}", serde_json::to_string_pretty(&violations)?);
+            } else if violations.is_empty() {
+                println!("✅ {} matches the expected structure", path);
+            } else {
+                for violation in &violations {
+                    println!(
+                        "🚩 {}",
+                        display::path(&violation.to_string(), args.raw_paths)
+                    );
+                }
+                println!("❌ {} structure violation(s)", violations.len());
+            }
+            if !violations.is_empty() {
+                std::process::exit(ALERT_EXIT_CODE);
+            }
+            Ok(())
         }
     }
 }
 
-fn main() -> Result<()> {
+fn history_path(args: &Args) -> PathBuf {
+    args.history_db
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(history::default_history_path)
+}
+
+fn main() {
     let args = Args::parse();
-    let root_path = PathBuf::from(&args.path);
+    if let Err(e) = run(&args) {
+        if args.output_format() == OutputFormat::Json {
+            match serde_json::to_string_pretty(&ErrorReport::from_error(&e)) {
+                Ok(json) => println!("{}", json),
+                Err(_) => eprintln!("Error: {:?}", e),
+            }
+        } else {
+            eprintln!("Error: {:?}", e);
+        }
+        std::process::exit(1);
+    }
+}
 
-    if !args.json {
-        println!(
-            "🚀 SPECTRA: Profiling topology of '{}'...",
-            root_path.display()
-        );
+fn run(args: &Args) -> Result<()> {
+    if let Some(command) = &args.command {
+        return run_command(command, args);
+    }
+    let targets = match &args.paths_file {
+        Some(file) => ScanTargets::load(Path::new(file))?,
+        None => ScanTargets {
+            roots: vec![PathBuf::from(&args.path)],
+            excludes: Vec::new(),
+        },
+    };
+
+    if args.output_format().is_human() {
+        match &args.paths_file {
+            Some(file) => println!(
+                "🚀 SPECTRA: Profiling topology of {} root(s) from '{}'...",
+                targets.roots.len(),
+                file
+            ),
+            None => println!(
+                "🚀 SPECTRA: Profiling topology of '{}'...",
+                targets.roots[0].display()
+            ),
+        }
     }
 
-    // PHASE 3: Fetch Policies from Server (if connected)
-    let mut policies = Vec::new();
+    // PHASE 3: Policies from a local file and/or the server (if connected)
+    let mut policies = match &args.policy_file {
+        Some(path) => policy_file::load(path)?,
+        None => Vec::new(),
+    };
     if let Some(server_url) = &args.server {
-        if !args.json {
+        if args.output_format().is_human() {
             println!("🌐 Fetching governance policies from {}...", server_url);
         }
-        policies = fetch_policies(server_url);
-        if !args.json && !policies.is_empty() {
-            println!("📋 Loaded {} policies", policies.len());
-            if !args.enforce {
-                println!("⚠️  Running in DRY-RUN mode. Use --enforce to execute actions.");
+        policies.extend(fetch_policies(server_url));
+    }
+    if let Some(case) = args.case_sensitivity {
+        for policy in &mut policies {
+            policy.rule.case_sensitivity = case;
+        }
+    }
+    if args.output_format().is_human() && !policies.is_empty() {
+        println!("📋 Loaded {} policies", policies.len());
+        if !args.enforce {
+            println!("⚠️  Running in DRY-RUN mode. Use --enforce to execute actions.");
+        }
+    }
+
+    scan_and_govern(args, &targets, policies, None)
+}
+
+/// Run each `Deduplicate` policy over the duplicate groups under `roots`,
+/// limited to the files the policy matched. `known` reuses groups the scan
+/// already found (with `--duplicates`); otherwise the roots are hashed here.
+fn apply_deduplicate_policies(
+    policies: &[Policy],
+    outcomes: &[evaluation::PolicyOutcome],
+    roots: &[PathBuf],
+    known: Option<&[DuplicateGroup]>,
+    runner: &mut GovernanceRunner,
+) {
+    let found: Vec<DuplicateGroup>;
+    let groups = match known {
+        Some(groups) => groups,
+        None => {
+            found = roots
+                .iter()
+                .flat_map(|root| dedup::find_duplicates(root, &DedupConfig::default()))
+                .collect();
+            &found
+        }
+    };
+    for (index, policy) in policies.iter().enumerate() {
+        let Action::Deduplicate { keep, hardlink } = policy.action else {
+            continue;
+        };
+        let matched: HashSet<&Path> = outcomes
+            .iter()
+            .filter(|outcome| outcome.policy_index == index)
+            .map(|outcome| outcome.path.as_path())
+            .collect();
+        for group in groups {
+            let copies: Vec<PathBuf> = group
+                .paths
+                .iter()
+                .map(PathBuf::from)
+                .filter(|path| matched.contains(path.as_path()))
+                .collect();
+            if copies.len() > 1 {
+                runner.deduplicate(policy, &copies, keep, hardlink);
             }
         }
     }
+}
+
+/// `spectra run`: the manifest's sections override the matching flags, and
+/// any other flags on the command line still apply.
+fn run_project(args: &Args, path: &Path) -> Result<()> {
+    let mut manifest = ProjectManifest::load(path)?;
+    let mut args = args.clone();
+    args.command = None;
+    if let Some(format) = &manifest.output.format {
+        args.format = Some(format.parse().map_err(anyhow::Error::msg)?);
+        args.json = false;
+    }
+    if let Some(limit) = manifest.output.limit {
+        args.limit = limit;
+    }
+    args.analyze |= manifest.output.analyze || !manifest.risk_patterns.is_empty();
+    args.exclude.extend(manifest.ignore.globs.iter().cloned());
 
+    let targets = manifest.targets();
+    let mut policies = std::mem::take(&mut manifest.policies);
+    if let Some(policy_path) = &args.policy_file {
+        policies.extend(policy_file::load(policy_path)?);
+    }
+    if let Some(case) = args.case_sensitivity {
+        for policy in &mut policies {
+            policy.rule.case_sensitivity = case;
+        }
+    }
+    if args.output_format().is_human() {
+        println!(
+            "🚀 SPECTRA: Running project '{}' over {} root(s) with {} policies...",
+            path.display(),
+            targets.roots.len(),
+            policies.len()
+        );
+        if !policies.is_empty() && !args.enforce {
+            println!("⚠️  Running in DRY-RUN mode. Use --enforce to execute actions.");
+        }
+    }
+    scan_and_govern(&args, &targets, policies, Some(&manifest))
+}
+
+/// Scan `targets`, apply `policies` and the analysis and output `args` ask
+/// for. `project` adds a manifest's risk patterns and categories.
+fn scan_and_govern(
+    args: &Args,
+    targets: &ScanTargets,
+    mut policies: Vec<Policy>,
+    project: Option<&ProjectManifest>,
+) -> Result<()> {
     // USE CORE SCANNER for basic scanning (Phase 1)
     // Device-aware I/O: thread count is auto-tuned based on SSD vs HDD
-    let mut scanner = Scanner::new(root_path.clone(), args.limit);
+    // One deadline shared by every root, so --time-budget bounds the run.
+    let deadline = args.time_budget.map(|budget| Instant::now() + budget);
+    // The manifest's risk patterns, then the --risk-rules file's.
+    let mut risk_rules = match project {
+        Some(project) => project.risk_rules()?,
+        None => RiskRuleSet::default(),
+    };
+    if let Some(file) = &args.risk_rules {
+        risk_rules = risk_rules.merge(load_risk_rules(Path::new(file))?)?;
+    }
+    let exclude_list = args
+        .exclude_paths_from
+        .as_deref()
+        .map(|file| ExcludeList::load(Path::new(file)))
+        .transpose()?;
+    let analyze = args.analyze
+        || args.semantic
+        || args.explain
+        || args.deep_entropy
+        || args.risk_rules.is_some();
+    let configure = |root: &Path| {
+        let mut scanner = Scanner::new(root, args.limit)
+            .with_case_sensitivity(args.case_sensitivity.unwrap_or_default())
+            .with_root_counted(!args.no_root_folder)
+            .with_symlink_mode(if args.follow_links {
+                SymlinkMode::Follow
+            } else {
+                args.symlinks.unwrap_or_default()
+            })
+            .with_broken_symlink_list(args.list_broken_symlinks)
+            .with_git_rollup(args.git_rollup)
+            .with_profile(args.profile)
+            .with_summary_only(args.summary_only)
+            .with_allocated_size(args.allocated)
+            .with_directories_in_top(args.include_dirs)
+            .with_mime_detection(args.detect_mime)
+            .with_extension_samples(if analyze {
+                EXTENSION_ENTROPY_SAMPLES
+            } else {
+                0
+            })
+            .exclude(args.exclude.iter().cloned());
+        if let Some(threads) = args.threads {
+            scanner = scanner.with_threads(threads);
+        }
+        if !args.ext.is_empty() {
+            scanner = scanner.with_include_extensions(&args.ext);
+        }
+        if let Some(bytes) = args.ext_min_size {
+            scanner = scanner.with_extension_min_size(bytes);
+        }
+        if let Some(bound) = args.queue_bound {
+            scanner = scanner.with_queue_bound(bound);
+        }
+        if let Some(depth) = args.rollup_depth {
+            scanner = scanner.with_rollup_depth(depth);
+        }
+        if let Some(fraction) = args.sample {
+            scanner = scanner.with_directory_sampling(fraction);
+        }
+        if let Some(cutoff) = args.changed_since {
+            scanner = scanner.with_changed_since(cutoff);
+        }
+        if let Some(deadline) = deadline {
+            scanner = scanner.with_time_budget(deadline.saturating_duration_since(Instant::now()));
+        }
+        if let Some(list) = &exclude_list {
+            scanner = list.apply(scanner, root);
+        }
+        scanner
+    };
+
+    // Watch events are human lines or NDJSON; a table format has no row
+    // shape for them, so refuse before scanning rather than switch formats.
+    if args.watch
+        && matches!(
+            args.output_format(),
+            OutputFormat::Csv | OutputFormat::Markdown
+        )
+    {
+        anyhow::bail!("--watch can't be combined with --format csv or markdown; use json or human");
+    }
+
+    if args.tui {
+        #[cfg(feature = "tui")]
+        return tui::run(|progress| {
+            targets.scan(args.limit, |root| {
+                let progress = progress.clone();
+                configure(root).with_progress(move |p| {
+                    let _ = progress.send(p);
+                })
+            })
+        });
+        #[cfg(not(feature = "tui"))]
+        anyhow::bail!("--tui requires a build with `--features tui`");
+    }
+
+    // --accurate-progress: pre-count files per root for a real percentage.
+    let root_totals = if args.accurate_progress && args.output_format().is_human() {
+        println!("🔢 Counting files...");
+        Some(targets.count_files(configure)?)
+    } else {
+        None
+    };
 
-    // Attach an indicatif spinner unless we're emitting JSON.
-    let progress_bar = if args.json {
+    // Attach an indicatif spinner (or bar, with a pre-count) unless we're
+    // emitting JSON or CSV.
+    let progress_bar = if !args.output_format().is_human() {
         None
+    } else if let Some(totals) = &root_totals {
+        let pb = ProgressBar::new(totals.iter().sum());
+        pb.set_style(
+            ProgressStyle::with_template(
+                "{spinner:.cyan} Scanning [{bar:30.cyan/blue}] {percent}% ({pos}/{len} files) {msg}",
+            )
+            .unwrap()
+            .progress_chars("=> ")
+            .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏ "),
+        );
+        pb.enable_steady_tick(Duration::from_millis(100));
+        Some(pb)
     } else {
         let pb = ProgressBar::new_spinner();
         pb.set_style(
@@ -248,111 +974,321 @@ fn main() -> Result<()> {
         Some(pb)
     };
 
-    if let Some(pb) = progress_bar.clone() {
-        scanner = scanner.with_progress(move |p| {
-            pb.set_message(format!(
-                "{} files, {} folders, {}",
-                p.files_scanned,
-                p.folders_scanned,
-                format_size(p.bytes_scanned, DECIMAL),
-            ));
-        });
-    }
-
-    let core_stats = scanner.scan()?;
+    let mut roots_started = 0;
+    let mut core_stats = targets.scan(args.limit, |root| {
+        let scanner = configure(root);
+        // Files counted in the roots already scanned.
+        let offset: u64 = root_totals
+            .as_ref()
+            .map_or(0, |totals| totals[..roots_started].iter().sum());
+        roots_started += 1;
+        let counted = root_totals.is_some();
+        match progress_bar.clone() {
+            Some(pb) => scanner.with_progress(move |p| {
+                if counted {
+                    pb.set_position(offset + p.files_scanned);
+                    pb.set_message(format_size(p.bytes_scanned, DECIMAL));
+                } else {
+                    pb.set_message(format!(
+                        "{} files, {} folders, {}",
+                        p.files_scanned,
+                        p.folders_scanned,
+                        format_size(p.bytes_scanned, DECIMAL),
+                    ));
+                }
+            }),
+            None => scanner,
+        }
+    })?;
 
     if let Some(pb) = &progress_bar {
         pb.finish_and_clear();
     }
+    core_stats.label = args.label.clone();
+
+    if !args.no_history {
+        let root = targets
+            .roots
+            .iter()
+            .map(|r| history::root_key(r))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let recorded = HistoryStore::open(&history_path(args)).and_then(|mut h| {
+            h.record(&root, &core_stats)?;
+            match &args.history_retention {
+                Some(retention) => h.compact(&root, retention, chrono::Utc::now().timestamp()),
+                None => Ok(0),
+            }
+        });
+        match recorded {
+            Ok(compacted) if compacted > 0 && args.output_format().is_human() => {
+                println!("🧹 Compacted {} old history entries", compacted);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                if args.output_format().is_human() {
+                    eprintln!("⚠️  Failed to record scan history: {}", e);
+                }
+            }
+        }
+    }
 
-    // Convert to CLI stats structure with analysis fields
-    let mut stats = CliScanStats::from(core_stats);
+    // Wrap in a report so the analysis below can annotate the top files
+    let mut report = ScanReport::new(core_stats);
+    if args.dedup {
+        report.dedup_top_files();
+    }
+    if args.duplicates || args.dedup_report {
+        let config = DedupConfig::default();
+        let hashing_from = Instant::now();
+        let mut groups: Vec<DuplicateGroup> = targets
+            .roots
+            .iter()
+            .flat_map(|root| dedup::find_duplicates(root, &config))
+            .collect();
+        groups.sort_by_key(|g| std::cmp::Reverse(g.reclaimable_bytes()));
+        if let Some(profile) = &mut report.stats.profile {
+            profile.hashing_ms += hashing_from.elapsed().as_millis() as u64;
+        }
+        report.duplicates = Some(groups);
+    }
+    if let Some(depth) = args.breakdown {
+        report.breakdown = Some(targets.breakdown(depth, configure)?);
+    }
+    let hash_filter = (args.hash || !args.hash_ext.is_empty() || args.hash_min_size.is_some())
+        .then(|| {
+            HashFilter::new()
+                .with_extensions(args.hash_ext.iter().cloned())
+                .with_min_size(args.hash_min_size.unwrap_or(0))
+                .with_case_sensitivity(args.case_sensitivity.unwrap_or_default())
+        });
+    // Under --deep-entropy the analysis below already reads each file in
+    // full, so it hashes in the same pass instead.
+    if let Some(filter) = hash_filter.as_ref().filter(|_| !args.deep_entropy) {
+        let hashing_from = Instant::now();
+        let hashed = report.hash_top_files(filter);
+        if let Some(profile) = &mut report.stats.profile {
+            profile.hashing_ms += hashing_from.elapsed().as_millis() as u64;
+        }
+        if args.output_format().is_human() {
+            println!(
+                "🔑 Hashed {} of {} top files",
+                hashed,
+                report.top_files.len()
+            );
+        }
+    }
 
     // PHASE 3: Apply governance policies (if configured)
     if !policies.is_empty() {
-        if !args.json {
+        if args.output_format().is_human() {
             println!("⚙️  Evaluating {} governance policies...", policies.len());
         }
 
-        for dir_entry in WalkDir::new(&root_path).into_iter().flatten() {
-            if let Ok(meta) = dir_entry.metadata() {
-                if meta.is_file() {
-                    for policy in &policies {
-                        if policy.evaluate(&dir_entry.path(), &meta) {
-                            policy.execute(&dir_entry.path(), !args.enforce);
-                        }
-                    }
+        let mut txn_log = match (&args.txn_log, args.enforce) {
+            (Some(log_path), true) => {
+                let log_path = Path::new(log_path);
+                let grace = Duration::from_secs(args.grace_hours * 3600);
+                let purged = transaction::purge_expired(log_path, grace)?;
+                if args.output_format().is_human() && purged > 0 {
+                    println!("🧹 Purged {} trashed file(s) past the grace period", purged);
+                }
+                Some(TransactionLog::open(log_path)?)
+            }
+            _ => None,
+        };
+
+        // Deletes stay reversible unless asked otherwise: the transaction
+        // log keeps its own trash, and without one the OS trash is used.
+        // The runner does the same for duplicate copies.
+        if !args.hard_delete && txn_log.is_none() {
+            for policy in &mut policies {
+                if matches!(policy.action, Action::Delete) {
+                    policy.action = Action::Trash;
                 }
             }
         }
+
+        // Evaluate in parallel, then execute serially so destructive actions
+        // never race each other.
+        let files = evaluation::collect_files(
+            &targets.roots,
+            &targets.excludes,
+            args.case_sensitivity.unwrap_or_default(),
+        );
+        let outcomes = evaluation::evaluate(
+            &files,
+            &policies,
+            args.governance_threads,
+            args.policy_match.unwrap_or_default(),
+        );
+        let (dedup_outcomes, outcomes): (Vec<_>, Vec<_>) =
+            outcomes.into_iter().partition(|outcome| {
+                matches!(
+                    policies[outcome.policy_index].action,
+                    Action::Deduplicate { .. }
+                )
+            });
+        let audit = match &args.audit_log {
+            Some(path) => Some(AuditLog::open(path).map_err(|e| {
+                anyhow::anyhow!("Failed to open audit log {}: {}", path.display(), e)
+            })?),
+            None => None,
+        };
+        let mut runner = GovernanceRunner::new(!args.enforce)
+            .with_hard_delete(args.hard_delete)
+            .with_txn(txn_log.as_mut())
+            .with_audit(audit);
+        for outcome in &outcomes {
+            // Archives mirror the layout below the root the file was found in.
+            let root = targets
+                .roots
+                .iter()
+                .filter(|root| outcome.path.starts_with(root))
+                .max_by_key(|root| root.as_os_str().len())
+                .map_or(Path::new(""), PathBuf::as_path);
+            runner.run(&policies[outcome.policy_index], &outcome.path, root);
+        }
+        if !dedup_outcomes.is_empty() {
+            apply_deduplicate_policies(
+                &policies,
+                &dedup_outcomes,
+                &targets.roots,
+                report.duplicates.as_deref(),
+                &mut runner,
+            );
+        }
+        if !args.enforce && args.output_format().is_human() {
+            println!("------------------------------------------------");
+            println!("📋 Governance Plan (dry run):");
+            print!("{}", runner.plan());
+            println!("   Re-run with --enforce to apply.");
+            println!("------------------------------------------------");
+        }
     }
 
     // POST-SCAN ANALYSIS: The Semantic Bridge (Phase 2)
-    if args.analyze || args.semantic {
-        if !args.json {
+    if analyze {
+        let analysis_from = Instant::now();
+        if args.output_format().is_human() {
             println!(
                 "🧠 Running Semantic Analysis on Top {} Files...",
-                stats.top_files.len()
+                report.top_files.len()
             );
         }
 
         // Load entropy cache (#5 - Hash/entropy caching)
-        let mut cache = ScanCache::load(&root_path);
+        let mut cache = ScanCache::load(Path::new(&report.stats.root_path));
         let mut cache_hits = 0usize;
 
         // Initialize Semantic Engine (only if --semantic flag is used)
         let semantic_engine = if args.semantic {
-            Some(SemanticEngine::new())
+            Some(
+                SemanticEngine::new()
+                    .with_min_confidence(args.semantic_min_confidence)
+                    .with_top_k(args.semantic_top_k),
+            )
         } else {
             None
         };
 
-        for file_record in &mut stats.top_files {
-            let p = PathBuf::from(&file_record.path);
+        let mut hashed = 0usize;
+        for file in report.top_files.iter_mut().filter(|f| !f.record.is_dir) {
+            let p = PathBuf::from(&file.record.path);
+            let size = file.record.size_bytes;
+            let file_record = &mut file.analysis;
+            let mut content_hash = file_record.content_hash.take();
 
-            // 1. Calculate Entropy (with cache)
-            if let Some(cached) = cache.get_entropy(&p, file_record.size_bytes) {
-                file_record.entropy = Some(cached);
+            // 1. Calculate Entropy (with cache). The cache holds header
+            // entropy, so full-file entropy bypasses it. The full read also
+            // yields the block profile and, when requested, the hash.
+            let mut blocks = None;
+            let entropy = if args.deep_entropy {
+                let block_size =
+                    (size / ENTROPY_PROFILE_BLOCKS).max(MIN_ENTROPY_BLOCK_SIZE as u64) as usize;
+                if hash_filter.as_ref().is_some_and(|f| f.matches(&p, size)) {
+                    analyze_file(&p, block_size)
+                        .ok()
+                        .map(|(hash, entropy, profile)| {
+                            content_hash = Some(hash);
+                            hashed += 1;
+                            blocks = Some(profile);
+                            entropy
+                        })
+                } else {
+                    entropy_profile(&p, block_size)
+                        .ok()
+                        .map(|(entropy, profile)| {
+                            blocks = Some(profile);
+                            entropy
+                        })
+                }
+            } else if let Some(cached) = cache.get_entropy(&p, size) {
                 cache_hits += 1;
+                Some(cached)
             } else if let Ok(ent) = calculate_shannon_entropy(&p) {
-                file_record.entropy = Some(ent);
-                cache.put_entropy(&p, file_record.size_bytes, ent);
-            }
+                cache.put_entropy(&p, size, ent);
+                Some(ent)
+            } else {
+                None
+            };
 
             // 2. Heuristic Risk Analysis (Tier 1)
-            let risk = analyze_filename_risk(&p);
-            if risk != RiskLevel::None {
-                file_record.risk_level = Some(risk.as_str().to_string());
+            *file_record = assess_with(&p, entropy, &risk_rules);
+            file_record.content_hash = content_hash;
+            if let Some(blocks) = blocks {
+                assess_blocks(file_record, blocks);
+            }
+            assess_encryption(&p, file_record);
+            if !args.explain {
+                file_record.reasons.clear();
             }
 
-            // 3. Semantic Tag (Tier 2 - only if enabled and file is likely text)
+            // 3. Semantic Tag (Tier 2 - only if enabled). The engine skips
+            // binary content and applies the confidence floor.
             if let Some(engine) = &semantic_engine {
-                if file_record.entropy.unwrap_or(10.0) < 6.0 {
-                    if let Some(tags) = engine.classify(&p) {
-                        if tags.confidence > 0.5 {
-                            file_record.semantic_tag = Some(tags.category);
-                        }
-                    }
+                if let Some(tags) = engine.classify(&p) {
+                    file_record.semantic_tag = Some(tags.category);
+                    file_record.semantic_confidence = Some(tags.confidence);
+                    file_record.semantic_candidates = tags.candidates;
                 }
             }
         }
 
+        if hash_filter.is_some() && args.deep_entropy && args.output_format().is_human() {
+            println!(
+                "🔑 Hashed {} of {} top files",
+                hashed,
+                report.top_files.len()
+            );
+        }
+
         // 4. IQR-based entropy outlier detection (#4)
-        let entropies: Vec<f32> = stats.top_files.iter().filter_map(|f| f.entropy).collect();
+        let entropies: Vec<f32> = report
+            .top_files
+            .iter()
+            .filter_map(|f| f.analysis.entropy)
+            .collect();
 
         if let Some(outlier_report) = detect_outliers(&entropies) {
             // Map outlier indices back to file records
             let mut entropy_idx = 0;
-            for file_record in &mut stats.top_files {
+            for file_record in report.top_files.iter_mut().map(|f| &mut f.analysis) {
                 if file_record.entropy.is_some() {
                     if outlier_report.outlier_indices.contains(&entropy_idx) {
                         file_record.entropy_outlier = Some(true);
+                        if args.explain {
+                            file_record.reasons.push(format!(
+                                "entropy outlier (outside {:.2}-{:.2})",
+                                outlier_report.lower_fence, outlier_report.upper_fence
+                            ));
+                        }
                     }
                     entropy_idx += 1;
                 }
             }
 
-            if !args.json {
+            if args.output_format().is_human() {
                 println!(
                     "📊 Entropy Stats: Q1={:.2} Median={:.2} Q3={:.2} IQR={:.2}",
                     outlier_report.q1, outlier_report.median, outlier_report.q3, outlier_report.iqr
@@ -368,13 +1304,28 @@ fn main() -> Result<()> {
             }
         }
 
+        // 5. Per-extension average entropy over the sample the scan drew
+        let averages = average_entropy_by_extension(
+            &report.stats.extension_samples,
+            EXTENSION_ENTROPY_SAMPLES,
+        );
+        for (ext, avg) in averages {
+            if let Some(stat) = report.stats.extensions.get_mut(&ext) {
+                stat.avg_entropy = Some(avg);
+            }
+        }
+
+        if let Some(profile) = &mut report.stats.profile {
+            profile.analysis_ms = analysis_from.elapsed().as_millis() as u64;
+        }
+
         // Save cache
-        stats.cache_hits = Some(cache_hits);
+        report.cache_hits = Some(cache_hits);
         if let Err(e) = cache.save() {
-            if !args.json {
+            if args.output_format().is_human() {
                 eprintln!("⚠️  Failed to save entropy cache: {}", e);
             }
-        } else if !args.json && cache.entries_count() > 0 {
+        } else if args.output_format().is_human() && cache.entries_count() > 0 {
             println!(
                 "💾 Cache: {} entries ({} hits this run)",
                 cache.entries_count(),
@@ -383,35 +1334,147 @@ fn main() -> Result<()> {
         }
     }
 
-    if args.json {
-        println!("{}", serde_json::to_string_pretty(&stats)?);
+    // Paths stay absolute and extensions ungrouped in `report` for upload,
+    // webhooks and watch; only the printed copy is rewritten.
+    let rewritten_report;
+    let escape_paths = args.output_format().is_human() && !args.raw_paths;
+    let printed = if args.relative_paths || args.ext_tail_threshold.is_some() || escape_paths {
+        let mut rewritten = report.clone();
+        if args.relative_paths {
+            for file in &mut rewritten.top_files {
+                file.record.path = targets.relative_display(Path::new(&file.record.path));
+                for alias in &mut file.aliases {
+                    *alias = targets.relative_display(Path::new(alias));
+                }
+            }
+            for dir in &mut rewritten.stats.top_dirs {
+                dir.path = targets.relative_display(Path::new(&dir.path));
+            }
+            for group in rewritten.duplicates.iter_mut().flatten() {
+                for path in &mut group.paths {
+                    *path = targets.relative_display(Path::new(path));
+                }
+            }
+            for (label, _) in rewritten.breakdown.iter_mut().flatten() {
+                *label = targets.relative_display(Path::new(label));
+            }
+        }
+        if escape_paths {
+            let escape = |s: &mut String| *s = display::escape_control(s).into_owned();
+            escape(&mut rewritten.stats.root_path);
+            rewritten
+                .stats
+                .broken_symlink_paths
+                .iter_mut()
+                .for_each(escape);
+            for file in &mut rewritten.top_files {
+                escape(&mut file.record.path);
+                file.aliases.iter_mut().for_each(escape);
+            }
+            for dir in &mut rewritten.stats.top_dirs {
+                escape(&mut dir.path);
+            }
+            for group in rewritten.duplicates.iter_mut().flatten() {
+                group.paths.iter_mut().for_each(escape);
+            }
+            for (label, _) in rewritten.breakdown.iter_mut().flatten() {
+                escape(label);
+            }
+        }
+        if let Some(threshold) = &args.ext_tail_threshold {
+            let total = rewritten.stats.total_size_bytes;
+            threshold.collapse(&mut rewritten.stats.extensions, total);
+        }
+        rewritten_report = rewritten;
+        &rewritten_report
     } else {
-        print_human_report(&stats);
+        &report
+    };
+
+    match args.output_format() {
+        OutputFormat::Human => print_human_report(printed, args.summary_only, args.dedup_report),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(printed)?),
+        OutputFormat::Csv => output::write_csv(printed, args.csv_extensions, std::io::stdout())?,
+        OutputFormat::Markdown => print!("{}", output::render_markdown(printed)),
     }
 
     // PHASE 3: Upload Snapshot to Server (Time-Travel Analytics)
     if let Some(server_url) = &args.server {
-        if !args.json {
+        if args.output_format().is_human() {
             println!("📤 Uploading snapshot to {}...", server_url);
         }
-        upload_snapshot(server_url, &stats);
+        let categories = project
+            .map(ProjectManifest::category_overrides)
+            .unwrap_or_default();
+        match snapshot::upload(server_url, &report.stats, args.upload_grouping, &categories) {
+            Ok(id) if args.output_format().is_human() => match id {
+                Some(id) => println!("📤 Snapshot uploaded to {} as {}", server_url, id),
+                None => println!("📤 Snapshot uploaded successfully to {}", server_url),
+            },
+            Ok(_) => {}
+            Err(e) => println!("⚠️  Failed to upload snapshot: {}", e),
+        }
+    }
+
+    if let Some(url) = &args.webhook {
+        match webhook::send(url, &report.stats, args.webhook_template.as_deref()) {
+            Ok(()) if args.output_format().is_human() => println!("🔔 Summary posted to webhook"),
+            Ok(()) => {}
+            Err(e) => eprintln!("⚠️  Failed to post webhook: {}", e),
+        }
+    }
+
+    let alerts: Vec<String> = args
+        .alert_if
+        .iter()
+        .filter_map(|rule| rule.check(&report.stats))
+        .collect();
+    if !alerts.is_empty() {
+        for alert in &alerts {
+            eprintln!("🚨 ALERT: {}", alert);
+        }
+        if let Some(url) = &args.webhook {
+            if let Err(e) = webhook::send_alerts(url, &report.stats.root_path, &alerts) {
+                eprintln!("⚠️  Failed to post alert webhook: {}", e);
+            }
+        }
+        std::process::exit(ALERT_EXIT_CODE);
     }
 
     // PHASE 5: Watch mode -- real-time filesystem monitoring (#8)
     if args.watch {
-        println!(
-            "\n👁️  Watching '{}' for changes (Ctrl+C to stop)...",
-            root_path.display()
-        );
+        // Human lines go through println!, whose stdout is line-buffered;
+        // machine formats stream one flushed NDJSON record per change.
+        let mut ndjson =
+            (!args.output_format().is_human()).then(|| NdjsonWriter::new(std::io::stdout()));
+        if ndjson.is_none() {
+            println!(
+                "\n👁️  Watching '{}' for changes (Ctrl+C to stop)...",
+                report.stats.root_path
+            );
+        }
 
-        let watcher = watch::FileSystemWatcher::new(&root_path)
+        let mut watcher = watch::FileSystemWatcher::new(&targets.roots[0])
             .map_err(|e| anyhow::anyhow!("Failed to start watcher: {}", e))?;
+        for root in &targets.roots[1..] {
+            watcher
+                .add_path(root)
+                .map_err(|e| anyhow::anyhow!("Failed to watch {}: {}", root.display(), e))?;
+        }
 
         loop {
             let events = watcher.poll(std::time::Duration::from_secs(1));
             for event in events {
                 for path in &event.paths {
-                    println!("  {} {}", event.kind, path);
+                    match &mut ndjson {
+                        Some(out) => out.write(&serde_json::json!({
+                            "event": event.kind.to_string().to_lowercase(),
+                            "path": path,
+                        }))?,
+                        None => {
+                            println!("  {} {}", event.kind, display::path(path, args.raw_paths))
+                        }
+                    }
                 }
             }
         }
@@ -420,43 +1483,307 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn print_human_report(stats: &CliScanStats) {
+/// "permission denied" or "permission denied: 2, not found: 1", most
+/// frequent first.
+fn skipped_reasons(errors: &[ScanError]) -> String {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for error in errors {
+        // "Permission denied (os error 13)" -> "permission denied"
+        let reason = error
+            .message
+            .split(" (os error")
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        match counts.iter_mut().find(|(r, _)| *r == reason) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((reason, 1)),
+        }
+    }
+    if let [(reason, _)] = counts.as_slice() {
+        return reason.clone();
+    }
+    counts.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+    counts
+        .iter()
+        .map(|(reason, n)| format!("{}: {}", reason, n))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Groups shown in the human report; the JSON lists every group.
+const DUPLICATE_GROUPS_SHOWN: usize = 10;
+
+/// Allocated/logical ratios flagged as sparse (below) or slack (above).
+const SPARSE_RATIO: f64 = 0.5;
+const SLACK_RATIO: f64 = 1.5;
+
+/// `du`-style listing: each directory's recursive size, parents before
+/// their children and siblings largest first.
+fn print_breakdown(dirs: &[(String, u64)]) {
+    println!("📂 Breakdown by Directory:");
+    for (path, size) in dirs {
+        println!("   {:>10}  {}", format_size(*size, DECIMAL), path);
+    }
+    println!("------------------------------------------------");
+}
+
+fn print_duplicates(groups: &[DuplicateGroup]) {
+    println!(
+        "♻️  Duplicates: {} group{} | {} reclaimable",
+        groups.len(),
+        if groups.len() == 1 { "" } else { "s" },
+        format_size(dedup::reclaimable_bytes(groups), DECIMAL)
+    );
+    for group in groups.iter().take(DUPLICATE_GROUPS_SHOWN) {
+        println!(
+            "   {} x {} ({} reclaimable) blake3:{}",
+            group.paths.len(),
+            format_size(group.size_bytes, DECIMAL),
+            format_size(group.reclaimable_bytes(), DECIMAL),
+            &group.hash[..group.hash.len().min(12)]
+        );
+        for path in &group.paths {
+            println!("      ↳ {}", path);
+        }
+    }
+    if groups.len() > DUPLICATE_GROUPS_SHOWN {
+        println!(
+            "   ... {} more (use --json for the full list)",
+            groups.len() - DUPLICATE_GROUPS_SHOWN
+        );
+    }
+    println!("------------------------------------------------");
+}
+
+/// Extensions and directories listed in the `--dedup-report` breakdown.
+const DEDUP_BREAKDOWN_SHOWN: usize = 5;
+
+fn print_dedup_savings(savings: &DedupSavings) {
+    println!(
+        "💰 Dedup savings: {} reclaimable by removing {} redundant cop{} in {} group{}",
+        format_size(savings.reclaimable_bytes, DECIMAL),
+        savings.redundant_files,
+        if savings.redundant_files == 1 {
+            "y"
+        } else {
+            "ies"
+        },
+        savings.group_count,
+        if savings.group_count == 1 { "" } else { "s" }
+    );
+    println!("   By extension:");
+    for (ext, bytes) in largest_entries(&savings.by_extension) {
+        let ext = if ext.is_empty() {
+            "(no extension)".to_string()
+        } else {
+            format!(".{}", ext)
+        };
+        println!("      {:<16} {}", ext, format_size(*bytes, DECIMAL));
+    }
+    println!("   By directory:");
+    // Directories come from the printed report, already escaped unless
+    // --raw-paths was given.
+    for (dir, bytes) in largest_entries(&savings.by_directory) {
+        println!("      {:>10}  {}", format_size(*bytes, DECIMAL), dir);
+    }
+    println!("------------------------------------------------");
+}
+
+fn largest_entries(breakdown: &BTreeMap<String, u64>) -> Vec<(&String, &u64)> {
+    let mut sorted: Vec<(&String, &u64)> = breakdown.iter().collect();
+    sorted.sort_by_key(|(_, bytes)| std::cmp::Reverse(**bytes));
+    sorted.truncate(DEDUP_BREAKDOWN_SHOWN);
+    sorted
+}
+
+/// The human-readable report. `summary_only` leaves out the per-file
+/// sections, which would be empty; `dedup_report` adds the savings breakdown.
+fn print_human_report(report: &ScanReport, summary_only: bool, dedup_report: bool) {
+    let stats = &report.stats;
     println!("------------------------------------------------");
     println!(
         "✅ Scan Complete in {:.2}s",
         stats.scan_duration_ms as f64 / 1000.0
     );
-    if let Some(device) = &stats.device_type {
+    if let Some(device) = stats.device_type {
         if let Some(threads) = stats.threads_used {
-            println!("⚡ Device: {} | Threads: {}", device, threads);
+            println!("⚡ Device: {:?} | Threads: {}", device, threads);
         }
     }
     println!("------------------------------------------------");
     println!("📂 Location : {}", stats.root_path);
+    if let Some(label) = &stats.label {
+        println!("🏷️  Label    : {}", label);
+    }
     println!("📄 Files    : {}", stats.total_files);
+    println!(
+        "🗂️  Inodes   : {} ({} files + {} folders)",
+        stats.total_inodes(),
+        stats.total_files,
+        stats.total_folders
+    );
     println!(
         "💾 Total Size: {}",
         format_size(stats.total_size_bytes, DECIMAL)
     );
+    if let (Some(allocated), Some(ratio)) = (stats.total_allocated_bytes, stats.allocation_ratio())
+    {
+        println!(
+            "💽 Allocated : {} ({:.2}x logical)",
+            format_size(allocated, DECIMAL),
+            ratio
+        );
+    }
+    let sizes = &stats.size_percentiles;
+    if sizes.max > 0 {
+        println!(
+            "📏 File sizes: p50 {} | p90 {} | p99 {} | max {}",
+            format_size(sizes.p50, DECIMAL),
+            format_size(sizes.p90, DECIMAL),
+            format_size(sizes.p99, DECIMAL),
+            format_size(sizes.max, DECIMAL)
+        );
+    }
+    if stats.broken_symlinks > 0 {
+        println!("🔗 Broken symlinks: {}", stats.broken_symlinks);
+        for path in &stats.broken_symlink_paths {
+            println!("      ↳ {}", path);
+        }
+    }
+    if !stats.errors.is_empty() {
+        println!(
+            "⚠️  {} path{} skipped ({})",
+            stats.errors.len(),
+            if stats.errors.len() == 1 { "" } else { "s" },
+            skipped_reasons(&stats.errors)
+        );
+    }
+    if stats.has_many_small_files() {
+        println!(
+            "⚠️  Many small files: average {} per file; per-file overhead will dominate backups of this tree",
+            format_size(stats.total_size_bytes / stats.total_files.max(1), DECIMAL)
+        );
+    }
+    if stats.partial {
+        println!("⚠️  Partial: the time budget ran out before the scan finished; totals cover only the part walked");
+    }
+    if stats.sampled_directories > 0 {
+        let sampled = stats.sampled_directories;
+        println!(
+            "⚠️  Estimated: {} large director{} sampled; sizes are extrapolated from a subset of files and may differ from the true totals",
+            sampled,
+            if sampled == 1 { "y was" } else { "ies were" }
+        );
+    }
     println!("------------------------------------------------");
+    if !summary_only {
+        print_top_sections(report);
+    }
+    if let Some(groups) = &report.duplicates {
+        print_duplicates(groups);
+        if dedup_report {
+            print_dedup_savings(&dedup::savings(groups));
+        }
+    }
+    if let Some(dirs) = &report.breakdown {
+        print_breakdown(dirs);
+    }
+    if let Some(profile) = &stats.profile {
+        println!("⏱️  Profile:");
+        for line in profile::profile_lines(profile) {
+            println!("{}", line);
+        }
+        println!("------------------------------------------------");
+    }
+}
 
+/// Top extensions, files and directories.
+fn print_top_sections(report: &ScanReport) {
+    let stats = &report.stats;
     println!("📊 Top Extensions by Volume:");
     // Quick sort to find top 5 extensions by size
     let mut sorted_exts: Vec<(&String, &ExtensionStat)> = stats.extensions.iter().collect();
+    #[allow(clippy::unnecessary_sort_by)]
     sorted_exts.sort_by(|a, b| b.1.size.cmp(&a.1.size));
 
     for (ext, data) in sorted_exts.iter().take(5) {
+        let entropy = data
+            .avg_entropy
+            .map(|e| format!(" | Avg Entropy:{:.1}", e))
+            .unwrap_or_default();
         println!(
-            "   .{:<5} : {:>10} ({})",
+            "   .{:<5} : {:>10} {:>5.1}% ({}){}",
             ext,
             format_size(data.size, DECIMAL),
-            data.count
+            stats.percent_of_total(data.size),
+            data.count,
+            entropy
         );
     }
 
-    println!("\n🐳 Top Largest Files:");
-    for file in &stats.top_files {
-        let mut info_parts = vec![format_size(file.size_bytes, DECIMAL)];
+    let mut categories: Vec<(String, ExtensionStat)> =
+        category::rollup(&stats.extensions).into_iter().collect();
+    if !categories.is_empty() {
+        println!("\n🗂️  Categories by Volume:");
+        categories.sort_by(|a, b| b.1.size.cmp(&a.1.size).then_with(|| a.0.cmp(&b.0)));
+        for (name, data) in &categories {
+            println!(
+                "   {:<10} : {:>10} {:>5.1}% ({})",
+                name,
+                format_size(data.size, DECIMAL),
+                stats.percent_of_total(data.size),
+                data.count
+            );
+        }
+    }
+
+    if !stats.mime_types.is_empty() {
+        println!("\n🧬 Top MIME Types by Volume:");
+        let mut sorted_mimes: Vec<(&String, &ExtensionStat)> = stats.mime_types.iter().collect();
+        sorted_mimes.sort_by_key(|(_, stat)| std::cmp::Reverse(stat.size));
+        for (mime, data) in sorted_mimes.iter().take(5) {
+            println!(
+                "   {:<24} : {:>10} ({})",
+                mime,
+                format_size(data.size, DECIMAL),
+                data.count
+            );
+        }
+    }
+
+    if report.top_files.iter().any(|f| f.record.is_dir) {
+        println!("\n🐳 Top Largest Entries:");
+    } else {
+        println!("\n🐳 Top Largest Files:");
+    }
+    for AnalyzedFile {
+        record,
+        analysis: file,
+        aliases,
+    } in &report.top_files
+    {
+        let mut info_parts = vec![format_size(record.size_bytes, DECIMAL)];
+        if record.is_dir {
+            info_parts.push("📁 DIR".to_string());
+        }
+
+        if let (Some(allocated), Some(ratio)) = (record.allocated_bytes, record.allocation_ratio())
+        {
+            let flag = if ratio < SPARSE_RATIO {
+                " SPARSE"
+            } else if ratio > SLACK_RATIO {
+                " SLACK"
+            } else {
+                ""
+            };
+            info_parts.push(format!(
+                "alloc {} ({:.2}x){}",
+                format_size(allocated, DECIMAL),
+                ratio,
+                flag
+            ));
+        }
 
         // Add entropy if available
         if let Some(ent) = file.entropy {
@@ -468,24 +1795,48 @@ fn print_human_report(stats: &CliScanStats) {
             info_parts.push("⚠️OUTLIER".to_string());
         }
 
+        if file.is_likely_encrypted == Some(true) {
+            info_parts.push("🔐ENCRYPTED".to_string());
+        }
+
+        if let Some(hash) = &file.content_hash {
+            info_parts.push(format!("blake3:{}", &hash[..hash.len().min(12)]));
+        }
+
         // Add risk level if available
         if let Some(risk) = &file.risk_level {
-            let risk_icon = match risk.as_str() {
-                "Critical" => "🔴",
-                "High" => "🟠",
-                "Medium" => "🟡",
-                "Low" => "🟢",
-                _ => "⚪",
-            };
-            info_parts.push(format!("{} {}", risk_icon, risk));
+            info_parts.push(format!("{} {}", output::risk_icon(risk), risk));
         }
 
         // Add semantic tag if available
         if let Some(tag) = &file.semantic_tag {
-            info_parts.push(format!("[{}]", tag));
+            match file.semantic_confidence {
+                Some(confidence) => {
+                    info_parts.push(format!("[{} {:.0}%]", tag, confidence * 100.0))
+                }
+                None => info_parts.push(format!("[{}]", tag)),
+            }
         }
 
-        println!("   {:<50}  {}", info_parts.join(" | "), file.path);
+        println!("   {:<50}  {}", info_parts.join(" | "), record.path);
+        for alias in aliases {
+            println!("      ↪ also at {}", alias);
+        }
+        for reason in &file.reasons {
+            println!("      ↳ {}", reason);
+        }
+    }
+    if !stats.top_dirs.is_empty() {
+        println!("\n📁 Top Directories:");
+        for dir in &stats.top_dirs {
+            println!(
+                "   {:>10} ({} file{})  {}",
+                format_size(dir.total_size_bytes, DECIMAL),
+                dir.file_count,
+                if dir.file_count == 1 { "" } else { "s" },
+                dir.path
+            );
+        }
     }
     println!("------------------------------------------------");
 }
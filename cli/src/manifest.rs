@@ -0,0 +1,348 @@
+//! Declarative project manifest (`spectra run --manifest spectra.project.toml`).
+//!
+//! One versioned file declares everything a scan-and-govern run needs, so
+//! the same run can be repeated from version control:
+//!
+//! ```toml
+//! version = 1
+//! roots = ["/srv/projects", "shared"]   # relative to the manifest
+//!
+//! [ignore]                              # as in --exclude-paths-from
+//! paths = ["scratch"]
+//! globs = ["*.tmp", "node_modules/"]
+//!
+//! [categories]                          # extension overrides for uploads
+//! models = ["onnx", "safetensors"]
+//!
+//! [[risk_patterns]]
+//! pattern = "(?i)payroll"
+//! level = "high"
+//! reason = "payroll export"
+//!
+//! [[policies]]
+//! name = "Stale logs"
+//! rule = { extension = "log", min_age_days = 90 }
+//! action = "Report"
+//!
+//! [output]
+//! format = "json"
+//! analyze = true
+//! limit = 20
+//! ```
+//!
+//! The whole manifest is validated before anything runs, and every problem
+//! is reported together rather than one per attempt.
+
+use crate::exclude_file::ExcludeList;
+use crate::governance::engine::{Action, Policy};
+use crate::output::OutputFormat;
+use crate::paths_file::ScanTargets;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use spectra_core::analysis::{RiskRuleEntry, RiskRuleSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// The only manifest version this build understands.
+pub const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectManifest {
+    pub version: u32,
+    /// Roots to scan; relative roots are resolved against the manifest's
+    /// directory by [`load`](Self::load).
+    pub roots: Vec<PathBuf>,
+    #[serde(default)]
+    pub ignore: ExcludeList,
+    /// Category name to the extensions (without the dot) it claims.
+    #[serde(default)]
+    pub categories: BTreeMap<String, Vec<String>>,
+    /// Filename risk rules, as in `--risk-rules`.
+    #[serde(default)]
+    pub risk_patterns: Vec<RiskRuleEntry>,
+    #[serde(default)]
+    pub policies: Vec<Policy>,
+    #[serde(default)]
+    pub output: OutputSettings,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputSettings {
+    /// As `--format`; the command line's format when unset.
+    pub format: Option<String>,
+    /// As `--analyze`.
+    pub analyze: bool,
+    /// As `--limit`.
+    pub limit: Option<usize>,
+}
+
+impl ProjectManifest {
+    /// Read, resolve and validate a manifest.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read project manifest {}", path.display()))?;
+        let mut manifest: Self = toml::from_str(&contents)
+            .with_context(|| format!("Invalid project manifest {}", path.display()))?;
+        let base = path.parent().unwrap_or(Path::new("."));
+        for root in &mut manifest.roots {
+            *root = base.join(&*root);
+        }
+        let errors = manifest.validate();
+        if !errors.is_empty() {
+            bail!(
+                "Invalid project manifest {}:\n  - {}",
+                path.display(),
+                errors.join("\n  - ")
+            );
+        }
+        Ok(manifest)
+    }
+
+    /// Every problem with the manifest, in section order.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.version != MANIFEST_VERSION {
+            errors.push(format!(
+                "version {} is not supported (expected {})",
+                self.version, MANIFEST_VERSION
+            ));
+        }
+
+        if self.roots.is_empty() {
+            errors.push("roots: at least one root is required".to_string());
+        }
+        for root in self.roots.iter().filter(|root| !root.is_dir()) {
+            errors.push(format!("roots: {} is not a directory", root.display()));
+        }
+
+        let mut claimed: HashMap<String, &str> = HashMap::new();
+        for (category, extensions) in &self.categories {
+            if extensions.is_empty() {
+                errors.push(format!("categories.{}: lists no extensions", category));
+            }
+            for ext in extensions {
+                let ext = normalize_extension(ext);
+                match claimed.insert(ext.clone(), category) {
+                    Some(other) if other != category => errors.push(format!(
+                        "categories: .{} is claimed by both {} and {}",
+                        ext, other, category
+                    )),
+                    _ => {}
+                }
+            }
+        }
+
+        for (i, pattern) in self.risk_patterns.iter().enumerate() {
+            if let Err(e) = pattern.validate() {
+                errors.push(format!("risk_patterns[{}]: {}", i, e));
+            }
+        }
+
+        let mut names = HashSet::new();
+        for (i, policy) in self.policies.iter().enumerate() {
+            let label = format!("policies[{}] '{}'", i, policy.name);
+            if policy.name.trim().is_empty() {
+                errors.push(format!("policies[{}]: name is empty", i));
+            } else if !names.insert(policy.name.as_str()) {
+                errors.push(format!("{}: duplicate policy name", label));
+            }
+            let rule = &policy.rule;
+            if rule.extension.is_none()
+                && rule.min_size_bytes.is_none()
+                && rule.min_age_days.is_none()
+                && rule.max_age_days.is_none()
+                && rule.path_regex.is_none()
+            {
+                errors.push(format!(
+                    "{}: rule matches every file (set extension, min_size_bytes, min_age_days, max_age_days or path_regex)",
+                    label
+                ));
+            }
+            if let Action::Archive { target_path } = &policy.action {
+                if target_path.trim().is_empty() {
+                    errors.push(format!("{}: archive target_path is empty", label));
+                }
+            }
+        }
+
+        if let Some(format) = &self.output.format {
+            if let Err(e) = format.parse::<OutputFormat>() {
+                errors.push(format!("output.format: {}", e));
+            }
+        }
+        if self.output.limit == Some(0) {
+            errors.push("output.limit: must be at least 1".to_string());
+        }
+        errors
+    }
+
+    /// The roots, with `[ignore]` paths excluded under each of them.
+    pub fn targets(&self) -> ScanTargets {
+        ScanTargets {
+            roots: self.roots.clone(),
+            excludes: self
+                .roots
+                .iter()
+                .flat_map(|root| self.ignore.paths.iter().map(move |path| root.join(path)))
+                .collect(),
+        }
+    }
+
+    /// Extension to category name, for [`spectra_core::category::rollup_with`].
+    pub fn category_overrides(&self) -> HashMap<String, String> {
+        self.categories
+            .iter()
+            .flat_map(|(category, extensions)| {
+                extensions
+                    .iter()
+                    .map(move |ext| (normalize_extension(ext), category.clone()))
+            })
+            .collect()
+    }
+
+    /// The compiled risk patterns, for
+    /// [`assess_with`](spectra_core::analysis::assess_with).
+    pub fn risk_rules(&self) -> Result<RiskRuleSet> {
+        RiskRuleSet::new(self.risk_patterns.clone()).context("Invalid risk_patterns")
+    }
+}
+
+fn normalize_extension(ext: &str) -> String {
+    ext.trim_start_matches('.').to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectra_core::analysis::{analyze_filename_risk_with, RiskLevel};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_complete_manifest_parses_every_section() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("shared")).unwrap();
+        let path = dir.path().join("spectra.project.toml");
+        std::fs::write(
+            &path,
+            format!(
+                r#"
+version = 1
+roots = ['{}', "shared"]
+
+[ignore]
+paths = ["scratch"]
+globs = ["*.tmp"]
+
+[categories]
+models = ["onnx", ".SafeTensors"]
+
+[[risk_patterns]]
+pattern = "(?i)payroll"
+level = "high"
+reason = "payroll export"
+
+[[policies]]
+name = "Stale logs"
+rule = {{ extension = "log", min_age_days = 90 }}
+action = "Report"
+priority = 5
+
+[[policies]]
+name = "Archive dumps"
+rule = {{ min_size_bytes = 1000000 }}
+action = {{ Archive = {{ target_path = "/mnt/cold" }} }}
+
+[output]
+format = "json"
+analyze = true
+limit = 20
+"#,
+                dir.path().display()
+            ),
+        )
+        .unwrap();
+
+        let manifest = ProjectManifest::load(&path).unwrap();
+        assert_eq!(manifest.version, 1);
+        assert_eq!(
+            manifest.roots,
+            [dir.path().to_path_buf(), dir.path().join("shared")]
+        );
+
+        assert_eq!(manifest.ignore.paths, [PathBuf::from("scratch")]);
+        assert_eq!(manifest.ignore.globs, ["*.tmp"]);
+        let targets = manifest.targets();
+        assert_eq!(targets.excludes[1], dir.path().join("shared/scratch"));
+
+        let overrides = manifest.category_overrides();
+        assert_eq!(overrides["safetensors"], "models");
+        assert_eq!(overrides["onnx"], "models");
+
+        let rules = manifest.risk_rules().unwrap();
+        let risk = analyze_filename_risk_with(Path::new("/hr/Payroll-2024.xlsx"), &rules);
+        assert_eq!(risk.level, RiskLevel::High);
+        assert!(risk.reasons[0].contains("payroll export"));
+
+        assert_eq!(manifest.policies.len(), 2);
+        assert_eq!(manifest.policies[0].rule.extension.as_deref(), Some("log"));
+        assert_eq!(manifest.policies[0].priority, 5);
+        assert!(matches!(
+            &manifest.policies[1].action,
+            Action::Archive { target_path } if target_path == "/mnt/cold"
+        ));
+
+        assert_eq!(manifest.output.format.as_deref(), Some("json"));
+        assert!(manifest.output.analyze);
+        assert_eq!(manifest.output.limit, Some(20));
+    }
+
+    #[test]
+    fn test_validation_reports_every_error() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("spectra.project.toml");
+        std::fs::write(
+            &path,
+            r#"
+version = 2
+roots = ["missing"]
+
+[categories]
+a = ["bin"]
+b = ["BIN"]
+
+[[risk_patterns]]
+pattern = "("
+level = "severe"
+
+[[policies]]
+name = "everything"
+rule = {}
+action = "Delete"
+
+[output]
+format = "yaml"
+"#,
+        )
+        .unwrap();
+
+        let err = ProjectManifest::load(&path).unwrap_err().to_string();
+        for expected in [
+            "version 2",
+            "is not a directory",
+            ".bin is claimed by both a and b",
+            "invalid pattern",
+            "matches every file",
+            "output.format",
+        ] {
+            assert!(
+                err.contains(expected),
+                "missing '{}' in:\n{}",
+                expected,
+                err
+            );
+        }
+        assert_eq!(err.matches("\n  - ").count(), 6);
+    }
+}
@@ -0,0 +1,346 @@
+//! Scan report output formats (`--format`).
+
+use anyhow::Result;
+use humansize::{format_size, DECIMAL};
+use serde::Serialize;
+use spectra_core::ScanReport;
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// Extensions listed in the Markdown report.
+const MARKDOWN_EXTENSIONS: usize = 10;
+
+/// How the scan report is written to stdout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Emoji summary for terminals.
+    #[default]
+    Human,
+    /// The full `ScanReport` as pretty-printed JSON.
+    Json,
+    /// One row per top file, for spreadsheets.
+    Csv,
+    /// A Markdown document for issues and wikis.
+    Markdown,
+}
+
+impl OutputFormat {
+    /// Machine formats keep stdout free of banners and progress chatter.
+    pub fn is_human(self) -> bool {
+        self == Self::Human
+    }
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            "markdown" | "md" => Ok(Self::Markdown),
+            other => Err(format!(
+                "unknown format '{}' (expected human, json, csv or markdown)",
+                other
+            )),
+        }
+    }
+}
+
+const FILE_COLUMNS: [&str; 5] = [
+    "path",
+    "size_bytes",
+    "entropy",
+    "risk_level",
+    "semantic_tag",
+];
+const EXTENSION_COLUMNS: [&str; 3] = ["extension", "count", "size_bytes"];
+
+#[derive(Serialize)]
+struct FileRow<'a> {
+    path: &'a str,
+    size_bytes: u64,
+    entropy: Option<f32>,
+    risk_level: Option<&'a str>,
+    semantic_tag: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct ExtensionRow<'a> {
+    extension: &'a str,
+    count: u64,
+    size_bytes: u64,
+}
+
+/// Write the top files as CSV (`path,size_bytes,entropy,risk_level,
+/// semantic_tag`), largest first. With `extensions`, a blank line and a
+/// second table (`extension,count,size_bytes`, largest first) follow.
+pub fn write_csv<W: Write>(report: &ScanReport, extensions: bool, mut out: W) -> Result<()> {
+    // Headers are written by hand so empty tables still get one.
+    let mut files = table_writer(&mut out);
+    files.write_record(FILE_COLUMNS)?;
+    for file in &report.top_files {
+        files.serialize(FileRow {
+            path: &file.record.path,
+            size_bytes: file.record.size_bytes,
+            entropy: file.analysis.entropy,
+            risk_level: file.analysis.risk_level.as_deref(),
+            semantic_tag: file.analysis.semantic_tag.as_deref(),
+        })?;
+    }
+    files.flush()?;
+    drop(files);
+
+    if extensions {
+        writeln!(out)?;
+        let mut sorted: Vec<_> = report.stats.extensions.iter().collect();
+        sorted.sort_by(|a, b| b.1.size.cmp(&a.1.size).then_with(|| a.0.cmp(b.0)));
+        let mut table = table_writer(&mut out);
+        table.write_record(EXTENSION_COLUMNS)?;
+        for (ext, stat) in sorted {
+            table.serialize(ExtensionRow {
+                extension: ext,
+                count: stat.count,
+                size_bytes: stat.size,
+            })?;
+        }
+        table.flush()?;
+    }
+    Ok(())
+}
+
+/// Newline-delimited JSON for streamed records (`--watch --json`). Every
+/// record is flushed as soon as its line is written, so a consumer reading
+/// from a pipe sees it immediately instead of when a buffer fills.
+pub struct NdjsonWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    /// Write `record` as one compact JSON line and flush it.
+    pub fn write<T: Serialize>(&mut self, record: &T) -> Result<()> {
+        serde_json::to_writer(&mut self.out, record)?;
+        self.out.write_all(b"\n")?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+fn table_writer<W: Write>(out: W) -> csv::Writer<W> {
+    csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(out)
+}
+
+/// Traffic-light emoji for a `FileAnalysis::risk_level`.
+pub fn risk_icon(risk: &str) -> &'static str {
+    match risk {
+        "Critical" => "🔴",
+        "High" => "🟠",
+        "Medium" => "🟡",
+        "Low" => "🟢",
+        _ => "⚪",
+    }
+}
+
+/// The report as a Markdown document: a heading with the root and totals,
+/// then tables of the largest extensions and the top files.
+pub fn render_markdown(report: &ScanReport) -> String {
+    let stats = &report.stats;
+    let mut md = String::new();
+    let _ = writeln!(
+        md,
+        "# Spectra scan: `{}`\n",
+        stats.root_path.replace('`', "'")
+    );
+    let _ = writeln!(md, "- **Files:** {}", stats.total_files);
+    let _ = writeln!(md, "- **Folders:** {}", stats.total_folders);
+    let _ = writeln!(
+        md,
+        "- **Total size:** {}",
+        format_size(stats.total_size_bytes, DECIMAL)
+    );
+    let _ = writeln!(
+        md,
+        "- **Duration:** {:.2}s",
+        stats.scan_duration_ms as f64 / 1000.0
+    );
+    if stats.partial {
+        let _ = writeln!(
+            md,
+            "- **Partial:** the time budget ran out before the scan finished"
+        );
+    }
+
+    let mut extensions: Vec<_> = stats.extensions.iter().collect();
+    extensions.sort_by(|a, b| b.1.size.cmp(&a.1.size).then_with(|| a.0.cmp(b.0)));
+    if !extensions.is_empty() {
+        md.push_str("\n## Top extensions\n\n| Extension | Size | Files |\n| --- | ---: | ---: |\n");
+        for (ext, stat) in extensions.into_iter().take(MARKDOWN_EXTENSIONS) {
+            let _ = writeln!(
+                md,
+                "| .{} | {} | {} |",
+                escape_cell(ext),
+                format_size(stat.size, DECIMAL),
+                stat.count
+            );
+        }
+    }
+
+    if !report.top_files.is_empty() {
+        md.push_str("\n## Top files\n\n| Path | Size | Risk |\n| --- | ---: | --- |\n");
+        for file in &report.top_files {
+            let risk = file
+                .analysis
+                .risk_level
+                .as_deref()
+                .map(|risk| format!("{} {}", risk_icon(risk), risk))
+                .unwrap_or_default();
+            let _ = writeln!(
+                md,
+                "| {} | {} | {} |",
+                escape_cell(&file.record.path),
+                format_size(file.record.size_bytes, DECIMAL),
+                risk
+            );
+        }
+    }
+    md
+}
+
+/// Escape a table cell so pipes and line breaks in paths can't split it.
+fn escape_cell(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace(['\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectra_core::testing::stats_with_extensions;
+    use spectra_core::{FileRecord, ScanStats};
+
+    fn sample_report() -> ScanReport {
+        let stats = ScanStats {
+            top_files: vec![
+                FileRecord {
+                    path: "/data/q1, final \"v2\".xlsx".to_string(),
+                    size_bytes: 3_000,
+                    ..Default::default()
+                },
+                FileRecord {
+                    path: "/data/notes.txt".to_string(),
+                    size_bytes: 500,
+                    ..Default::default()
+                },
+            ],
+            ..stats_with_extensions(&[("xlsx", 1, 3_000), ("txt", 2, 500)])
+        };
+        let mut report = ScanReport::from(stats);
+        report.top_files[0].analysis.entropy = Some(7.5);
+        report.top_files[0].analysis.risk_level = Some("High".to_string());
+        report
+    }
+
+    fn parse(section: &str) -> Vec<csv::StringRecord> {
+        csv::Reader::from_reader(section.as_bytes())
+            .records()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_csv_round_trips_paths_with_commas_and_quotes() {
+        let mut out = Vec::new();
+        write_csv(&sample_report(), false, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("path,size_bytes,entropy,risk_level,semantic_tag\n"));
+
+        let rows = parse(&text);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(&rows[0][0], "/data/q1, final \"v2\".xlsx");
+        assert_eq!(&rows[0][1], "3000");
+        assert_eq!(&rows[0][2], "7.5");
+        assert_eq!(&rows[0][3], "High");
+        assert_eq!(&rows[0][4], "");
+        assert_eq!(&rows[1][0], "/data/notes.txt");
+    }
+
+    #[test]
+    fn test_markdown_tables_escape_pipes() {
+        let mut report = sample_report();
+        report.top_files[1].record.path = "/data/a|b.txt".to_string();
+        let md = render_markdown(&report);
+
+        assert!(md.starts_with("# Spectra scan: `"));
+        assert!(md.contains("- **Files:** 3"));
+        assert!(md.contains("| Extension | Size | Files |"));
+        assert!(md.contains("| .xlsx | 3 kB | 1 |"));
+        assert!(md.contains("| Path | Size | Risk |"));
+        assert!(md.contains("| 🟠 High |"));
+        assert!(md.contains("| /data/a\\|b.txt | 500 B |  |"));
+        // Every table row keeps its column count.
+        for line in md.lines().filter(|l| l.starts_with('|')) {
+            let cells = line.replace("\\|", "").matches('|').count();
+            assert_eq!(cells, 4, "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_csv_extension_section() {
+        let mut out = Vec::new();
+        write_csv(&sample_report(), true, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let (files, extensions) = text.split_once("\n\n").unwrap();
+        assert_eq!(parse(files).len(), 2);
+
+        assert!(extensions.starts_with("extension,count,size_bytes\n"));
+        let rows = parse(extensions);
+        assert_eq!(
+            rows.iter()
+                .map(|r| (r[0].to_string(), r[2].to_string()))
+                .collect::<Vec<_>>(),
+            [
+                ("xlsx".to_string(), "3000".to_string()),
+                ("txt".to_string(), "500".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ndjson_records_reach_a_pipe_reader_one_at_a_time() {
+        use std::io::{BufRead, BufReader, BufWriter};
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let (reader, writer) = std::io::pipe().unwrap();
+        let (line_tx, line_rx) = mpsc::channel();
+        let reader = std::thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                line_tx.send(line.unwrap()).unwrap();
+            }
+        });
+
+        // A BufWriter would hold both short records until dropped; the
+        // first must arrive while the writer is still open.
+        let mut out = NdjsonWriter::new(BufWriter::new(writer));
+        out.write(&serde_json::json!({"event": "created", "path": "/a"}))
+            .unwrap();
+        let first = line_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(first, r#"{"event":"created","path":"/a"}"#);
+
+        out.write(&serde_json::json!({"event": "deleted", "path": "/b"}))
+            .unwrap();
+        let second = line_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(second, r#"{"event":"deleted","path":"/b"}"#);
+
+        drop(out);
+        reader.join().unwrap();
+    }
+}
@@ -0,0 +1,249 @@
+//! Curated scan targets read from a file (`--paths-file`).
+//!
+//! Each non-empty line names a root to scan. Lines starting with `!` name a
+//! subtree to exclude from every root, and lines starting with `#` are
+//! comments. Relative paths are relative to the paths file's directory:
+//!
+//! ```text
+//! # nightly capacity sweep
+//! /srv/projects
+//! /srv/shared
+//! !/srv/projects/scratch
+//! ```
+
+use anyhow::{bail, Context, Result};
+use spectra_core::{breakdown, ScanStats, Scanner};
+use std::path::{Path, PathBuf};
+
+/// Roots to scan and subtrees to skip, as declared in a paths file.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ScanTargets {
+    pub roots: Vec<PathBuf>,
+    pub excludes: Vec<PathBuf>,
+}
+
+impl ScanTargets {
+    /// Read and [`parse`](Self::parse) a paths file, resolving relative
+    /// roots and exclusions against the file's directory.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read paths file {}", path.display()))?;
+        let mut targets = Self::parse(&contents);
+        if targets.roots.is_empty() {
+            bail!("Paths file {} lists no roots to scan", path.display());
+        }
+        let base = path.parent().unwrap_or(Path::new(""));
+        for entry in targets.roots.iter_mut().chain(&mut targets.excludes) {
+            if entry.is_relative() {
+                *entry = base.join(&*entry);
+            }
+        }
+        Ok(targets)
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let mut targets = Self::default();
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.strip_prefix('!') {
+                Some(excluded) => targets.excludes.push(PathBuf::from(excluded.trim())),
+                None => targets.roots.push(PathBuf::from(line)),
+            }
+        }
+        targets
+    }
+
+    /// Render `path` relative to the root containing it, for reports that
+    /// should not expose the full directory structure.
+    ///
+    /// With a single root the result is plain relative (`sub/file.txt`). With
+    /// several roots it is prefixed by the root's own name (`projects/sub/file.txt`)
+    /// so entries from different roots stay distinguishable; a root itself is
+    /// shown by its name (or `.` when it's the only one). Paths outside every
+    /// root are returned unchanged.
+    pub fn relative_display(&self, path: &Path) -> String {
+        for root in &self.roots {
+            let Ok(rel) = path.strip_prefix(root) else {
+                continue;
+            };
+            let shown = match root.file_name() {
+                Some(name) if self.roots.len() > 1 && rel.as_os_str().is_empty() => {
+                    PathBuf::from(name)
+                }
+                Some(name) if self.roots.len() > 1 => Path::new(name).join(rel),
+                _ if rel.as_os_str().is_empty() => PathBuf::from(breakdown::ROOT_LABEL),
+                _ => rel.to_path_buf(),
+            };
+            return shown.display().to_string();
+        }
+        path.display().to_string()
+    }
+
+    /// Pre-count the files [`scan`](Self::scan) will visit under each root,
+    /// in root order (see [`Scanner::count_files`]).
+    pub fn count_files<F>(&self, mut make_scanner: F) -> Result<Vec<u64>>
+    where
+        F: FnMut(&Path) -> Scanner,
+    {
+        self.roots
+            .iter()
+            .map(|root| {
+                make_scanner(root)
+                    .exclude_paths(self.excludes.iter().cloned())
+                    .count_files()
+            })
+            .collect()
+    }
+
+    /// Per-directory sizes down to `depth` under each root, in root order
+    /// (see [`breakdown::summarize_by_depth`]). With several roots, labels
+    /// are prefixed with their root to tell them apart.
+    pub fn breakdown<F>(&self, depth: usize, mut make_scanner: F) -> Result<Vec<(String, u64)>>
+    where
+        F: FnMut(&Path) -> Scanner,
+    {
+        let several = self.roots.len() > 1;
+        let mut dirs = Vec::new();
+        for root in &self.roots {
+            let scanner = make_scanner(root).exclude_paths(self.excludes.iter().cloned());
+            for (label, size) in breakdown::summarize_by_depth(&scanner, depth)? {
+                let label = if !several {
+                    label
+                } else if label == breakdown::ROOT_LABEL {
+                    root.display().to_string()
+                } else {
+                    root.join(&label).display().to_string()
+                };
+                dirs.push((label, size));
+            }
+        }
+        Ok(dirs)
+    }
+
+    /// Scan every root with the exclusions applied and merge the results.
+    ///
+    /// `make_scanner` builds the scanner for each root so the caller can attach
+    /// progress reporting and other settings.
+    pub fn scan<F>(&self, top_limit: usize, mut make_scanner: F) -> Result<ScanStats>
+    where
+        F: FnMut(&Path) -> Scanner,
+    {
+        let mut merged: Option<ScanStats> = None;
+        for root in &self.roots {
+            let stats = make_scanner(root)
+                .exclude_paths(self.excludes.iter().cloned())
+                .scan()?;
+            match merged.as_mut() {
+                Some(acc) => acc.merge(stats, top_limit),
+                None => merged = Some(stats),
+            }
+        }
+        let mut merged = merged.unwrap_or_default();
+        merged.root_path = self
+            .roots
+            .iter()
+            .map(|r| r.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        Ok(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_skips_blank_and_comment_lines() {
+        let targets = ScanTargets::parse("# comment\n\n/data/a\n  !/data/a/tmp  \n/data/b\n");
+        assert_eq!(
+            targets.roots,
+            vec![PathBuf::from("/data/a"), PathBuf::from("/data/b")]
+        );
+        assert_eq!(targets.excludes, vec![PathBuf::from("/data/a/tmp")]);
+    }
+
+    #[test]
+    fn test_paths_file_merges_roots_minus_exclusion() {
+        let dir = tempdir().unwrap();
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        std::fs::create_dir_all(a.join("cache")).unwrap();
+        std::fs::create_dir_all(&b).unwrap();
+        std::fs::write(a.join("one.txt"), vec![0u8; 100]).unwrap();
+        std::fs::write(a.join("cache/huge.bin"), vec![0u8; 5000]).unwrap();
+        std::fs::write(b.join("two.txt"), vec![0u8; 200]).unwrap();
+        // Not listed in the paths file at all.
+        std::fs::write(dir.path().join("outside.txt"), vec![0u8; 300]).unwrap();
+
+        let list = dir.path().join("dirs.txt");
+        std::fs::write(
+            &list,
+            format!(
+                "# scan targets\n{}\n{}\n!{}\n",
+                a.display(),
+                b.display(),
+                a.join("cache").display()
+            ),
+        )
+        .unwrap();
+
+        let targets = ScanTargets::load(&list).unwrap();
+        let stats = targets.scan(10, |root| Scanner::new(root, 10)).unwrap();
+
+        assert_eq!(stats.total_files, 2);
+        assert_eq!(stats.total_size_bytes, 300);
+        assert_eq!(stats.extensions["txt"].count, 2);
+        assert!(!stats.extensions.contains_key("bin"));
+        assert_eq!(stats.top_files[0].size_bytes, 200);
+    }
+
+    #[test]
+    fn test_relative_entries_resolve_against_the_paths_file() {
+        let dir = tempdir().unwrap();
+        let list = dir.path().join("lists/dirs.txt");
+        std::fs::create_dir_all(list.parent().unwrap()).unwrap();
+        let absolute = dir.path().join("abs");
+        std::fs::write(
+            &list,
+            format!("data\n../shared\n!data/tmp\n{}\n", absolute.display()),
+        )
+        .unwrap();
+
+        let targets = ScanTargets::load(&list).unwrap();
+        let lists = dir.path().join("lists");
+        assert_eq!(
+            targets.roots,
+            [lists.join("data"), lists.join("../shared"), absolute]
+        );
+        assert_eq!(targets.excludes, [lists.join("data/tmp")]);
+    }
+
+    #[test]
+    fn test_relative_display_strips_scan_root() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub/big.bin"), vec![0u8; 300]).unwrap();
+
+        let single = ScanTargets {
+            roots: vec![dir.path().to_path_buf()],
+            excludes: Vec::new(),
+        };
+        let stats = single.scan(5, |root| Scanner::new(root, 5)).unwrap();
+        let shown = single.relative_display(Path::new(&stats.top_files[0].path));
+        assert_eq!(Path::new(&shown), Path::new("sub/big.bin"));
+
+        let multi = ScanTargets {
+            roots: vec![PathBuf::from("/data/a"), PathBuf::from("/srv/b")],
+            excludes: Vec::new(),
+        };
+        let shown = multi.relative_display(Path::new("/srv/b/x/y.txt"));
+        assert_eq!(Path::new(&shown), Path::new("b/x/y.txt"));
+        assert_eq!(multi.relative_display(Path::new("/srv/b")), "b");
+        assert_eq!(single.relative_display(dir.path()), ".");
+        assert_eq!(multi.relative_display(Path::new("/other/z")), "/other/z");
+    }
+}
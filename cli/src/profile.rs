@@ -0,0 +1,68 @@
+//! Human-readable `--profile` section.
+
+use spectra_core::ScanProfile;
+
+/// One line per walker thread followed by the aggregation and post-scan
+/// timings. Workers that read nothing are still listed, so an idle thread
+/// shows up as contention rather than disappearing.
+pub fn profile_lines(profile: &ScanProfile) -> Vec<String> {
+    let mut lines: Vec<String> = profile
+        .workers
+        .iter()
+        .map(|w| {
+            format!(
+                "   worker {:<3}: {:>10} files | {:>8} dirs",
+                w.worker, w.files, w.directories
+            )
+        })
+        .collect();
+    lines.push(format!(
+        "   aggregator : {} ms waiting on walk | {} ms reading metadata",
+        profile.walk_wait_ms, profile.metadata_ms
+    ));
+    if profile.hashing_ms > 0 || profile.analysis_ms > 0 {
+        lines.push(format!(
+            "   post-scan  : {} ms hashing | {} ms analysis",
+            profile.hashing_ms, profile.analysis_ms
+        ));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectra_core::Scanner;
+    use std::fs::File;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_profile_output_counts_sum_to_total_files() {
+        let dir = tempdir().unwrap();
+        for d in 0..6 {
+            let sub = dir.path().join(format!("dir_{}", d));
+            std::fs::create_dir(&sub).unwrap();
+            for f in 0..30 {
+                File::create(sub.join(format!("file_{}.log", f))).unwrap();
+            }
+        }
+        let stats = Scanner::new(dir.path(), 5)
+            .with_threads(3)
+            .with_profile(true)
+            .scan()
+            .unwrap();
+
+        let lines = profile_lines(stats.profile.as_ref().unwrap());
+        let worker_files: Vec<u64> = lines
+            .iter()
+            .filter(|line| line.trim_start().starts_with("worker"))
+            .map(|line| {
+                let (_, counts) = line.split_once(':').unwrap();
+                counts.split_whitespace().next().unwrap().parse().unwrap()
+            })
+            .collect();
+        assert_eq!(worker_files.len(), 3);
+        assert_eq!(worker_files.iter().sum::<u64>(), stats.total_files);
+        assert_eq!(stats.total_files, 180);
+    }
+}
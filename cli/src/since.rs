@@ -0,0 +1,75 @@
+//! Parsing for `--changed-since` cutoffs and `--time-budget` durations.
+
+use chrono::{DateTime, Local, NaiveDate};
+use std::time::{Duration, SystemTime};
+
+/// Accepts a relative age (`30m`, `24h`, `7d`, `2w`), a local date
+/// (`2025-01-31`, meaning midnight) or an RFC 3339 timestamp.
+pub fn parse_since(s: &str) -> Result<SystemTime, String> {
+    let s = s.trim();
+    if let Some(age) = parse_age(s) {
+        return SystemTime::now()
+            .checked_sub(age)
+            .ok_or_else(|| format!("'{}' reaches before the epoch", s));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return date
+            .and_hms_opt(0, 0, 0)
+            .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+            .map(SystemTime::from)
+            .ok_or_else(|| format!("'{}' is not a valid local date", s));
+    }
+    DateTime::parse_from_rfc3339(s)
+        .map(SystemTime::from)
+        .map_err(|_| {
+            format!(
+                "'{}': expected an age like 24h or 7d, a date like 2025-01-31, or an RFC 3339 timestamp",
+                s
+            )
+        })
+}
+
+/// Accepts a relative age such as `90s`, `5m` or `2h`.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    parse_age(s.trim()).ok_or_else(|| format!("'{}': expected a duration like 90s, 5m or 2h", s))
+}
+
+fn parse_age(s: &str) -> Option<Duration> {
+    let unit_at = s.find(|c: char| !c.is_ascii_digit())?;
+    let (number, unit) = s.split_at(unit_at);
+    let number: u64 = number.parse().ok()?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        "w" => 7 * 86_400,
+        _ => return None,
+    };
+    Some(Duration::from_secs(number.checked_mul(seconds)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_since_formats() {
+        let day_ago = parse_since("24h").unwrap();
+        let elapsed = SystemTime::now().duration_since(day_ago).unwrap();
+        assert!((elapsed.as_secs() as i64 - 86_400).abs() < 5);
+
+        let rfc = parse_since("2025-01-31T12:00:00Z").unwrap();
+        assert_eq!(
+            rfc.duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            1_738_324_800
+        );
+        assert!(parse_since("2025-01-31").is_ok());
+        assert!(parse_since("yesterday").is_err());
+        assert!(parse_since("5y").is_err());
+        assert_eq!(parse_duration("5m"), Ok(Duration::from_secs(300)));
+        assert!(parse_duration("5").is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! Snapshot upload to a Spectra Server (`--server`).
+
+use crate::http;
+use anyhow::{bail, Result};
+use serde::Serialize;
+use spectra_core::{category, ExtensionStat, ScanStats};
+use std::collections::HashMap;
+
+/// Raw extensions kept in a snapshot; categories are few enough to send all.
+const TOP_EXTENSIONS: usize = 10;
+
+/// Prefix for category keys when they are sent alongside raw extensions, so
+/// a category can never collide with an extension of the same name.
+pub const CATEGORY_KEY_PREFIX: &str = "category:";
+
+/// What the snapshot's `top_extensions` list is keyed by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UploadGrouping {
+    /// The ten largest raw extensions.
+    #[default]
+    Extensions,
+    /// Every category from `spectra_core::category` (`images`, `code`, ...).
+    Categories,
+    /// The ten largest raw extensions followed by `category:<name>` entries.
+    Both,
+}
+
+impl std::str::FromStr for UploadGrouping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "extensions" => Ok(Self::Extensions),
+            "categories" => Ok(Self::Categories),
+            "both" => Ok(Self::Both),
+            other => Err(format!(
+                "unknown grouping '{}' (expected extensions, categories or both)",
+                other
+            )),
+        }
+    }
+}
+
+/// The body of `POST /api/v1/ingest`.
+#[derive(Debug, Serialize)]
+pub struct Snapshot {
+    pub agent_id: String,
+    pub timestamp: i64,
+    pub hostname: String,
+    pub total_size_bytes: u64,
+    pub file_count: u64,
+    /// `(key, bytes, files)`, largest first within each grouping.
+    pub top_extensions: Vec<(String, u64, u64)>,
+    /// Random UUID, fixed when the snapshot is built and sent unchanged on
+    /// every retry so the server stores it once.
+    pub snapshot_id: String,
+}
+
+impl Snapshot {
+    /// `categories` overrides the built-in extension categories (see
+    /// [`category::rollup_with`]).
+    pub fn from_stats(
+        stats: &ScanStats,
+        grouping: UploadGrouping,
+        categories: &HashMap<String, String>,
+    ) -> Self {
+        let now = chrono::Utc::now().timestamp();
+        let top_extensions = match grouping {
+            UploadGrouping::Extensions => largest(&stats.extensions, Some(TOP_EXTENSIONS), ""),
+            UploadGrouping::Categories => largest(
+                &category::rollup_with(&stats.extensions, categories),
+                None,
+                "",
+            ),
+            UploadGrouping::Both => {
+                let mut entries = largest(&stats.extensions, Some(TOP_EXTENSIONS), "");
+                entries.extend(largest(
+                    &category::rollup_with(&stats.extensions, categories),
+                    None,
+                    CATEGORY_KEY_PREFIX,
+                ));
+                entries
+            }
+        };
+        Self {
+            agent_id: format!("agent_{}", now),
+            timestamp: now,
+            hostname: std::env::var("COMPUTERNAME")
+                .or_else(|_| std::env::var("HOSTNAME"))
+                .unwrap_or_else(|_| "unknown".to_string()),
+            total_size_bytes: stats.total_size_bytes,
+            file_count: stats.total_files,
+            top_extensions,
+            snapshot_id: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+}
+
+fn largest(
+    stats: &HashMap<String, ExtensionStat>,
+    limit: Option<usize>,
+    prefix: &str,
+) -> Vec<(String, u64, u64)> {
+    let mut sorted: Vec<(&String, &ExtensionStat)> = stats.iter().collect();
+    sorted.sort_by(|a, b| b.1.size.cmp(&a.1.size).then_with(|| a.0.cmp(b.0)));
+    sorted
+        .into_iter()
+        .take(limit.unwrap_or(usize::MAX))
+        .map(|(key, stat)| (format!("{}{}", prefix, key), stat.size, stat.count))
+        .collect()
+}
+
+/// POST a snapshot of `stats` to the server's ingest endpoint. Returns the
+/// record id the server stored it under, when the server reports one.
+pub fn upload(
+    server_url: &str,
+    stats: &ScanStats,
+    grouping: UploadGrouping,
+    categories: &HashMap<String, String>,
+) -> Result<Option<String>> {
+    let url = format!("{}/api/v1/ingest", server_url);
+    let snapshot = Snapshot::from_stats(stats, grouping, categories);
+    let client = http::client();
+    let response = http::send_with_retry(|| http::with_api_key(client.post(&url)).json(&snapshot))?;
+    let status = response.status();
+    if !status.is_success() {
+        // The server explains rejections in a JSON `error` field.
+        let reason = response
+            .json::<serde_json::Value>()
+            .ok()
+            .and_then(|body| body.get("error")?.as_str().map(str::to_string));
+        match reason {
+            Some(reason) => bail!("server responded with status {}: {}", status, reason),
+            None => bail!("server responded with status {}", status),
+        }
+    }
+    // Older servers answer with a bare "Snapshot stored" string.
+    let id = response
+        .json::<serde_json::Value>()
+        .ok()
+        .and_then(|body| body.get("id")?.as_str().map(str::to_string));
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::mock;
+    use spectra_core::testing::stats_with_extensions;
+
+    fn sample_stats() -> ScanStats {
+        stats_with_extensions(&[("png", 2, 1_000), ("jpg", 1, 2_000), ("rs", 3, 2_500)])
+    }
+
+    fn keys(body: &str) -> Vec<String> {
+        let json: serde_json::Value = serde_json::from_str(body).unwrap();
+        json["top_extensions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry[0].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_uploaded_snapshot_contains_category_keys() {
+        let (url, server) = mock::server();
+        upload(
+            &url,
+            &sample_stats(),
+            UploadGrouping::Categories,
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        let (request_line, body) = server.join().unwrap();
+        assert!(request_line.starts_with("POST /api/v1/ingest"));
+        assert_eq!(keys(&body), ["images", "code"]);
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["top_extensions"][0][1], 3_000);
+        assert_eq!(json["top_extensions"][0][2], 3);
+
+        let (url, server) = mock::server();
+        upload(&url, &sample_stats(), UploadGrouping::Both, &HashMap::new()).unwrap();
+        let (_, body) = server.join().unwrap();
+        assert_eq!(
+            keys(&body),
+            ["rs", "jpg", "png", "category:images", "category:code"]
+        );
+    }
+
+    #[test]
+    fn test_default_grouping_sends_raw_extensions() {
+        let snapshot =
+            Snapshot::from_stats(&sample_stats(), UploadGrouping::default(), &HashMap::new());
+        assert_eq!(
+            snapshot.top_extensions,
+            [
+                ("rs".to_string(), 2_500, 3),
+                ("jpg".to_string(), 2_000, 1),
+                ("png".to_string(), 1_000, 2),
+            ]
+        );
+    }
+}
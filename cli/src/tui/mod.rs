@@ -0,0 +1,206 @@
+//! Interactive terminal UI (`--tui`, built with the `tui` feature).
+//!
+//! The scan runs on a worker thread and streams its progress callback to
+//! the UI; once it finishes, the extension table, top files and a
+//! collapsible tree of the top directories can be browsed from the keyboard.
+
+mod state;
+
+use state::{Input, Pane, TuiState};
+
+use anyhow::Result;
+use humansize::{format_size, DECIMAL};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use spectra_core::{ScanProgress, ScanStats};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+/// How often the UI redraws while waiting for keys or progress.
+const TICK: Duration = Duration::from_millis(100);
+
+/// Run `scan` on a worker thread, showing its progress and then its
+/// results until the user quits. `scan` should forward every progress
+/// update to the sender it's given (e.g. from `Scanner::with_progress`).
+pub fn run<F>(scan: F) -> Result<()>
+where
+    F: FnOnce(Sender<ScanProgress>) -> Result<ScanStats> + Send,
+{
+    let (progress_tx, progress) = mpsc::channel();
+    let (done_tx, done) = mpsc::channel();
+    // Quitting mid-scan still waits for the walk to finish when the scope
+    // ends.
+    std::thread::scope(|scope| {
+        scope.spawn(move || {
+            let _ = done_tx.send(scan(progress_tx));
+        });
+        let mut terminal = ratatui::init();
+        let result = event_loop(&mut terminal, &progress, &done);
+        ratatui::restore();
+        result
+    })
+}
+
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    progress: &Receiver<ScanProgress>,
+    done: &Receiver<Result<ScanStats>>,
+) -> Result<()> {
+    let mut state = TuiState::new();
+    while !state.quit {
+        if let Some(latest) = progress.try_iter().last() {
+            state.on_progress(latest);
+        }
+        if let Ok(stats) = done.try_recv() {
+            state.on_complete(stats?);
+        }
+        terminal.draw(|frame| draw(frame, &state))?;
+        if !event::poll(TICK)? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let input = match key.code {
+                KeyCode::Up | KeyCode::Char('k') => Input::Up,
+                KeyCode::Down | KeyCode::Char('j') => Input::Down,
+                KeyCode::Left | KeyCode::Char('h') => Input::Left,
+                KeyCode::Right | KeyCode::Enter | KeyCode::Char('l') => Input::Right,
+                KeyCode::Tab => Input::NextPane,
+                KeyCode::Esc | KeyCode::Char('q') => Input::Quit,
+                _ => continue,
+            };
+            state.handle(input);
+        }
+    }
+    Ok(())
+}
+
+fn draw(frame: &mut Frame, state: &TuiState) {
+    let [status, body, help] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let p = &state.progress;
+    let heading = if state.is_scanning() {
+        format!(
+            "Scanning... {} files, {} folders, {}  {}",
+            p.files_scanned,
+            p.folders_scanned,
+            format_size(p.bytes_scanned, DECIMAL),
+            p.current_path
+        )
+    } else {
+        format!(
+            "{}: {} files, {} folders, {}",
+            p.current_path,
+            p.files_scanned,
+            p.folders_scanned,
+            format_size(p.bytes_scanned, DECIMAL)
+        )
+    };
+    frame.render_widget(
+        Paragraph::new(heading).block(Block::bordered().title(" Spectra ")),
+        status,
+    );
+    frame.render_widget(
+        Paragraph::new("↑/↓ move  ←/→ collapse/expand  Tab switch pane  q quit")
+            .style(Style::default().fg(Color::DarkGray)),
+        help,
+    );
+    if state.is_scanning() {
+        return;
+    }
+
+    let [left, tree] =
+        Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)]).areas(body);
+    let [extensions, files] =
+        Layout::vertical([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(left);
+    draw_extensions(frame, state, extensions);
+    draw_files(frame, state, files);
+    draw_tree(frame, state, tree);
+}
+
+fn pane_block(state: &TuiState, pane: Pane, title: &str) -> Block<'static> {
+    let block = Block::bordered().title(format!(" {} ", title));
+    if state.focus == pane {
+        block.border_style(Style::default().fg(Color::Cyan))
+    } else {
+        block
+    }
+}
+
+fn highlight() -> Style {
+    Style::default().add_modifier(Modifier::REVERSED)
+}
+
+fn draw_extensions(frame: &mut Frame, state: &TuiState, area: Rect) {
+    let rows = state.extensions.iter().map(|(ext, stat)| {
+        Row::new([
+            format!(".{}", ext),
+            format_size(stat.size, DECIMAL),
+            stat.count.to_string(),
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(8),
+            Constraint::Length(12),
+            Constraint::Length(10),
+        ],
+    )
+    .header(Row::new(["Extension", "Size", "Files"]).style(Style::default().fg(Color::Yellow)))
+    .block(pane_block(state, Pane::Extensions, "Extensions"))
+    .row_highlight_style(highlight());
+    let mut table_state =
+        TableState::default().with_selected(Some(state.selected(Pane::Extensions)));
+    frame.render_stateful_widget(table, area, &mut table_state);
+}
+
+fn draw_files(frame: &mut Frame, state: &TuiState, area: Rect) {
+    let items = state.top_files.iter().map(|file| {
+        ListItem::new(format!(
+            "{:>10}  {}",
+            format_size(file.size_bytes, DECIMAL),
+            file.path
+        ))
+    });
+    let list = List::new(items)
+        .block(pane_block(state, Pane::Files, "Top Files"))
+        .highlight_style(highlight());
+    let mut list_state = ListState::default().with_selected(Some(state.selected(Pane::Files)));
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn draw_tree(frame: &mut Frame, state: &TuiState, area: Rect) {
+    let tree = &state.tree;
+    let items = tree.visible().into_iter().map(|i| {
+        let node = &tree.nodes[i];
+        let marker = match (node.children.is_empty(), node.expanded) {
+            (true, _) => " ",
+            (false, true) => "▾",
+            (false, false) => "▸",
+        };
+        ListItem::new(format!(
+            "{}{} {}  {} ({} files)",
+            "  ".repeat(tree.depth(i)),
+            marker,
+            node.name,
+            format_size(node.dir.total_size_bytes, DECIMAL),
+            node.dir.file_count
+        ))
+    });
+    let list = List::new(items)
+        .block(pane_block(state, Pane::Tree, "Directories"))
+        .highlight_style(highlight());
+    let mut list_state = ListState::default().with_selected(Some(state.selected(Pane::Tree)));
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
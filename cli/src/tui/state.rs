@@ -0,0 +1,326 @@
+//! What the TUI shows, independent of how it's drawn.
+
+use spectra_core::{DirRecord, ExtensionStat, FileRecord, ScanProgress, ScanStats};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The pane that receives navigation keys.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Pane {
+    #[default]
+    Extensions,
+    Files,
+    Tree,
+}
+
+impl Pane {
+    fn next(self) -> Self {
+        match self {
+            Self::Extensions => Self::Files,
+            Self::Files => Self::Tree,
+            Self::Tree => Self::Extensions,
+        }
+    }
+}
+
+/// Key presses, already mapped from the terminal's key codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Up,
+    Down,
+    /// Collapse the selected directory, or jump to its parent.
+    Left,
+    /// Expand the selected directory.
+    Right,
+    NextPane,
+    Quit,
+}
+
+/// A directory from `ScanStats::top_dirs`, nested under the closest listed
+/// ancestor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+    pub dir: DirRecord,
+    /// Path relative to the nearest listed ancestor (or the scan root).
+    pub name: String,
+    pub parent: Option<usize>,
+    /// Largest first.
+    pub children: Vec<usize>,
+    pub expanded: bool,
+}
+
+/// Navigable directory tree built from the scan's top directories.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirTree {
+    pub nodes: Vec<TreeNode>,
+    /// Top-level nodes, largest first.
+    pub roots: Vec<usize>,
+}
+
+impl DirTree {
+    pub fn new(root: &str, dirs: &[DirRecord]) -> Self {
+        let index: HashMap<&Path, usize> = dirs
+            .iter()
+            .enumerate()
+            .map(|(i, dir)| (Path::new(&dir.path), i))
+            .collect();
+        let mut nodes: Vec<TreeNode> = dirs
+            .iter()
+            .map(|dir| {
+                let path = Path::new(&dir.path);
+                let parent = path.ancestors().skip(1).find_map(|a| index.get(a).copied());
+                let base = parent.map_or(Path::new(root), |p| Path::new(&dirs[p].path));
+                let name = path
+                    .strip_prefix(base)
+                    .unwrap_or(path)
+                    .display()
+                    .to_string();
+                TreeNode {
+                    dir: dir.clone(),
+                    name,
+                    parent,
+                    children: Vec::new(),
+                    expanded: false,
+                }
+            })
+            .collect();
+
+        let mut roots = Vec::new();
+        for i in 0..nodes.len() {
+            match nodes[i].parent {
+                Some(parent) => nodes[parent].children.push(i),
+                None => roots.push(i),
+            }
+        }
+        let by_size = |nodes: &[TreeNode], list: &mut Vec<usize>| {
+            list.sort_by_key(|&i| std::cmp::Reverse(nodes[i].dir.total_size_bytes));
+        };
+        by_size(&nodes, &mut roots);
+        for i in 0..nodes.len() {
+            let mut children = std::mem::take(&mut nodes[i].children);
+            by_size(&nodes, &mut children);
+            nodes[i].children = children;
+        }
+        Self { nodes, roots }
+    }
+
+    /// Node indices in display order, skipping children of collapsed nodes.
+    pub fn visible(&self) -> Vec<usize> {
+        let mut rows = Vec::new();
+        let mut stack: Vec<usize> = self.roots.iter().rev().copied().collect();
+        while let Some(i) = stack.pop() {
+            rows.push(i);
+            if self.nodes[i].expanded {
+                stack.extend(self.nodes[i].children.iter().rev());
+            }
+        }
+        rows
+    }
+
+    /// Nesting level of node `i` (0 for top-level nodes).
+    pub fn depth(&self, mut i: usize) -> usize {
+        let mut depth = 0;
+        while let Some(parent) = self.nodes[i].parent {
+            depth += 1;
+            i = parent;
+        }
+        depth
+    }
+}
+
+/// Everything the TUI renders.
+#[derive(Debug, Default)]
+pub struct TuiState {
+    /// Latest update from the scanner's progress callback.
+    pub progress: ScanProgress,
+    /// Set once the scan has finished.
+    pub stats: Option<ScanStats>,
+    /// Largest first.
+    pub extensions: Vec<(String, ExtensionStat)>,
+    pub top_files: Vec<FileRecord>,
+    pub tree: DirTree,
+    pub focus: Pane,
+    /// Selected row in each pane, in [`Pane`] order.
+    pub selected: [usize; 3],
+    pub quit: bool,
+}
+
+impl TuiState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_scanning(&self) -> bool {
+        self.stats.is_none()
+    }
+
+    pub fn on_progress(&mut self, progress: ScanProgress) {
+        self.progress = progress;
+    }
+
+    /// Load the finished scan into the tables and tree.
+    pub fn on_complete(&mut self, stats: ScanStats) {
+        let mut extensions: Vec<_> = stats
+            .extensions
+            .iter()
+            .map(|(ext, stat)| (ext.clone(), stat.clone()))
+            .collect();
+        extensions.sort_by(|a, b| b.1.size.cmp(&a.1.size).then_with(|| a.0.cmp(&b.0)));
+        self.extensions = extensions;
+        self.top_files = stats.top_files.clone();
+        self.tree = DirTree::new(&stats.root_path, &stats.top_dirs);
+        self.progress = ScanProgress {
+            files_scanned: stats.total_files,
+            folders_scanned: stats.total_folders,
+            bytes_scanned: stats.total_size_bytes,
+            current_path: stats.root_path.clone(),
+        };
+        self.selected = [0; 3];
+        self.stats = Some(stats);
+    }
+
+    /// Rows in `pane`.
+    pub fn len(&self, pane: Pane) -> usize {
+        match pane {
+            Pane::Extensions => self.extensions.len(),
+            Pane::Files => self.top_files.len(),
+            Pane::Tree => self.tree.visible().len(),
+        }
+    }
+
+    pub fn selected(&self, pane: Pane) -> usize {
+        self.selected[pane as usize]
+    }
+
+    /// Index into `tree.nodes` of the selected directory.
+    pub fn selected_dir(&self) -> Option<usize> {
+        self.tree.visible().get(self.selected(Pane::Tree)).copied()
+    }
+
+    pub fn handle(&mut self, input: Input) {
+        let pane = self.focus;
+        let len = self.len(pane);
+        let row = &mut self.selected[pane as usize];
+        match input {
+            Input::Quit => self.quit = true,
+            Input::NextPane => self.focus = pane.next(),
+            Input::Up => *row = row.saturating_sub(1),
+            Input::Down => *row = (*row + 1).min(len.saturating_sub(1)),
+            Input::Right if pane == Pane::Tree => {
+                if let Some(i) = self.selected_dir() {
+                    let node = &mut self.tree.nodes[i];
+                    node.expanded = !node.children.is_empty();
+                }
+            }
+            Input::Left if pane == Pane::Tree => {
+                let Some(i) = self.selected_dir() else {
+                    return;
+                };
+                if self.tree.nodes[i].expanded {
+                    self.tree.nodes[i].expanded = false;
+                } else if let Some(parent) = self.tree.nodes[i].parent {
+                    self.tree.nodes[parent].expanded = false;
+                    let rows = self.tree.visible();
+                    self.selected[Pane::Tree as usize] =
+                        rows.iter().position(|&r| r == parent).unwrap_or(0);
+                }
+            }
+            Input::Left | Input::Right => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectra_core::testing::stats_with_extensions;
+
+    fn dir(path: &str, size: u64) -> DirRecord {
+        DirRecord {
+            path: path.to_string(),
+            total_size_bytes: size,
+            file_count: 1,
+        }
+    }
+
+    fn sample_stats() -> ScanStats {
+        ScanStats {
+            root_path: "/r".to_string(),
+            total_files: 4,
+            total_folders: 3,
+            top_files: vec![
+                FileRecord {
+                    path: "/r/a/b/big.iso".to_string(),
+                    size_bytes: 600,
+                    ..Default::default()
+                },
+                FileRecord {
+                    path: "/r/c/notes.txt".to_string(),
+                    size_bytes: 100,
+                    ..Default::default()
+                },
+            ],
+            top_dirs: vec![dir("/r/a", 800), dir("/r/a/b", 600), dir("/r/c", 200)],
+            ..stats_with_extensions(&[("txt", 1, 100), ("iso", 1, 600), ("rs", 1, 300)])
+        }
+    }
+
+    #[test]
+    fn test_state_tracks_progress_then_loads_results() {
+        let mut state = TuiState::new();
+        state.on_progress(ScanProgress {
+            files_scanned: 2,
+            bytes_scanned: 300,
+            ..Default::default()
+        });
+        assert!(state.is_scanning());
+        assert_eq!(state.progress.files_scanned, 2);
+
+        state.on_complete(sample_stats());
+        assert!(!state.is_scanning());
+        assert_eq!(state.progress.files_scanned, 4);
+        let exts: Vec<&str> = state.extensions.iter().map(|(e, _)| e.as_str()).collect();
+        assert_eq!(exts, ["iso", "rs", "txt"]);
+        assert_eq!(state.top_files.len(), 2);
+
+        // /r/a/b nests under /r/a; only top-level dirs show until expanded.
+        let names = |state: &TuiState| -> Vec<String> {
+            state
+                .tree
+                .visible()
+                .into_iter()
+                .map(|i| state.tree.nodes[i].name.clone())
+                .collect()
+        };
+        assert_eq!(names(&state), ["a", "c"]);
+        state.handle(Input::NextPane);
+        state.handle(Input::NextPane);
+        assert_eq!(state.focus, Pane::Tree);
+        state.handle(Input::Right);
+        assert_eq!(names(&state), ["a", "b", "c"]);
+        assert_eq!(state.tree.depth(state.tree.visible()[1]), 1);
+
+        state.handle(Input::Down);
+        assert_eq!(state.selected_dir(), Some(1));
+        state.handle(Input::Left);
+        assert_eq!(names(&state), ["a", "c"]);
+        assert_eq!(state.selected(Pane::Tree), 0);
+    }
+
+    #[test]
+    fn test_selection_stays_in_bounds() {
+        let mut state = TuiState::new();
+        state.on_complete(sample_stats());
+        for _ in 0..10 {
+            state.handle(Input::Down);
+        }
+        assert_eq!(state.selected(Pane::Extensions), 2);
+        state.handle(Input::Up);
+        assert_eq!(state.selected(Pane::Extensions), 1);
+        // Other panes keep their own selection.
+        state.handle(Input::NextPane);
+        assert_eq!(state.selected(Pane::Files), 0);
+        state.handle(Input::Quit);
+        assert!(state.quit);
+    }
+}
@@ -36,7 +36,7 @@ impl std::fmt::Display for WatchEventKind {
 /// - inotify on Linux
 /// - FSEvents on macOS
 pub struct FileSystemWatcher {
-    _watcher: RecommendedWatcher,
+    watcher: RecommendedWatcher,
     rx: mpsc::Receiver<Result<notify::Event, notify::Error>>,
 }
 
@@ -54,10 +54,13 @@ impl FileSystemWatcher {
 
         watcher.watch(path, RecursiveMode::Recursive)?;
 
-        Ok(Self {
-            _watcher: watcher,
-            rx,
-        })
+        Ok(Self { watcher, rx })
+    }
+
+    /// Watch an additional directory recursively (e.g. every root of a
+    /// `--paths-file` scan).
+    pub fn add_path(&mut self, path: &Path) -> Result<(), notify::Error> {
+        self.watcher.watch(path, RecursiveMode::Recursive)
     }
 
     /// Poll for events, blocking up to `timeout`.
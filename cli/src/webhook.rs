@@ -0,0 +1,129 @@
+//! Post scan summaries to a Slack/Teams/generic webhook (`--webhook`).
+
+use crate::http;
+use anyhow::{bail, Result};
+use humansize::{format_size, DECIMAL};
+use serde::Serialize;
+use spectra_core::ScanStats;
+
+/// The compact summary posted when no template is given.
+#[derive(Debug, Serialize)]
+pub struct WebhookSummary {
+    pub root_path: String,
+    pub total_files: u64,
+    pub total_folders: u64,
+    pub total_size_bytes: u64,
+    pub scan_duration_ms: u128,
+    /// Up to five largest extensions as `(extension, bytes)`.
+    pub top_extensions: Vec<(String, u64)>,
+}
+
+impl WebhookSummary {
+    pub fn from_stats(stats: &ScanStats) -> Self {
+        let mut exts: Vec<(String, u64)> = stats
+            .extensions
+            .iter()
+            .map(|(ext, stat)| (ext.clone(), stat.size))
+            .collect();
+        exts.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+        exts.truncate(5);
+        Self {
+            root_path: stats.root_path.clone(),
+            total_files: stats.total_files,
+            total_folders: stats.total_folders,
+            total_size_bytes: stats.total_size_bytes,
+            scan_duration_ms: stats.scan_duration_ms,
+            top_extensions: exts,
+        }
+    }
+
+    /// Fill `{root}`, `{files}`, `{folders}`, `{size}` and `{duration_ms}`
+    /// placeholders in a message template.
+    pub fn render(&self, template: &str) -> String {
+        template
+            .replace("{root}", &self.root_path)
+            .replace("{files}", &self.total_files.to_string())
+            .replace("{folders}", &self.total_folders.to_string())
+            .replace("{size}", &format_size(self.total_size_bytes, DECIMAL))
+            .replace("{duration_ms}", &self.scan_duration_ms.to_string())
+    }
+}
+
+/// POST the summary to `url`. With a template, the body is a Slack-style
+/// `{"text": "..."}` message (also accepted by Teams incoming webhooks).
+pub fn send(url: &str, stats: &ScanStats, template: Option<&str>) -> Result<()> {
+    let summary = WebhookSummary::from_stats(stats);
+    let body = match template {
+        Some(template) => serde_json::json!({ "text": summary.render(template) }),
+        None => serde_json::to_value(&summary)?,
+    };
+
+    let client = http::client();
+    let response = http::send_with_retry(|| client.post(url).json(&body))?;
+    if !response.status().is_success() {
+        bail!("webhook responded with status {}", response.status());
+    }
+    Ok(())
+}
+
+/// POST a Slack-style alert message listing the breached thresholds.
+pub fn send_alerts(url: &str, root_path: &str, alerts: &[String]) -> Result<()> {
+    let body = serde_json::json!({
+        "text": format!("🚨 Spectra alert for {}: {}", root_path, alerts.join("; ")),
+        "alerts": alerts,
+    });
+    let client = http::client();
+    let response = http::send_with_retry(|| client.post(url).json(&body))?;
+    if !response.status().is_success() {
+        bail!("webhook responded with status {}", response.status());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::mock;
+    use spectra_core::testing::stats_with_extensions;
+
+    fn sample_stats() -> ScanStats {
+        ScanStats {
+            root_path: "/data".to_string(),
+            total_files: 42,
+            total_folders: 3,
+            total_size_bytes: 1_500_000,
+            ..stats_with_extensions(&[("log", 40, 1_400_000)])
+        }
+    }
+
+    #[test]
+    fn test_webhook_receives_summary_post() {
+        let (url, server) = mock::server();
+        let url = format!("{}/hook", url);
+        send(&url, &sample_stats(), None).unwrap();
+
+        let (request_line, body) = server.join().unwrap();
+        assert!(request_line.starts_with("POST /hook"));
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["root_path"], "/data");
+        assert_eq!(json["total_files"], 42);
+        assert_eq!(json["total_size_bytes"], 1_500_000);
+        assert_eq!(json["top_extensions"][0][0], "log");
+    }
+
+    #[test]
+    fn test_template_renders_slack_text() {
+        let (url, server) = mock::server();
+        let url = format!("{}/hook", url);
+        send(
+            &url,
+            &sample_stats(),
+            Some("Scanned {root}: {files} files, {size}"),
+        )
+        .unwrap();
+
+        let (_, body) = server.join().unwrap();
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["text"], "Scanned /data: 42 files, 1.50 MB");
+    }
+}
@@ -15,20 +15,26 @@
 //! - "What did the filesystem look like at time T?"
 
 use axum::{
-    extract::{Path, Query, Request, State},
-    http::{header, HeaderName, Method, StatusCode},
+    extract::{rejection::JsonRejection, Path, Query, Request, State},
+    http::{header, HeaderName, HeaderValue, Method, StatusCode},
     middleware::{self, Next},
     response::Response,
-    routing::{get, post},
+    routing::{delete, get, post},
     Json, Router,
 };
+use clap::Parser;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use surrealdb::engine::local::Mem;
+use surrealdb::engine::any::Any;
+use surrealdb::opt::auth::Root;
 use surrealdb::Surreal;
 use tokio::net::TcpListener;
 use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::Instrument;
 
 // --- Data Models ---
 
@@ -42,6 +48,51 @@ struct AgentSnapshot {
     file_count: u64,
     /// (Extension, Total Size, File Count) - Enhanced for granularity
     top_extensions: Vec<(String, u64, u64)>,
+    /// Client-generated UUID. When present it becomes the record id, so a
+    /// retried upload replaces the first copy instead of duplicating it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    snapshot_id: Option<String>,
+}
+
+/// How far ahead of the server clock an ingested timestamp may be before the
+/// snapshot is rejected as bogus.
+const MAX_FUTURE_SKEW_SECS: i64 = 86_400;
+
+impl AgentSnapshot {
+    /// Reject snapshots that would poison the time-series math: no agent to
+    /// file them under, or a timestamp that can't be a real scan time.
+    fn validate(&self, now: i64) -> Result<(), String> {
+        if self.agent_id.trim().is_empty() {
+            return Err("agent_id must not be empty".to_string());
+        }
+        if self.timestamp <= 0 {
+            return Err(format!(
+                "timestamp must be positive, got {}",
+                self.timestamp
+            ));
+        }
+        if self.timestamp > now.saturating_add(MAX_FUTURE_SKEW_SECS) {
+            return Err(format!(
+                "timestamp {} is more than {}s in the future",
+                self.timestamp, MAX_FUTURE_SKEW_SECS
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Response of POST /api/v1/ingest
+#[derive(Serialize, Deserialize, Debug)]
+struct IngestResponse {
+    /// SurrealDB record id of the stored snapshot (`snapshots:<id>`)
+    id: String,
+    timestamp: i64,
+}
+
+/// Just the record id of a stored row.
+#[derive(Deserialize)]
+struct RecordId {
+    id: surrealdb::sql::Thing,
 }
 
 /// Velocity report showing data growth/shrinkage between two points in time
@@ -72,13 +123,30 @@ struct TimeRange {
     end: i64,
 }
 
-/// Query parameter for single timestamp
+/// Query parameter for single timestamp (`?at=<ts>` or `?timestamp=<ts>`)
 #[derive(Deserialize)]
 struct TimestampQuery {
-    #[serde(default)]
+    #[serde(default, alias = "at")]
     timestamp: Option<i64>,
 }
 
+/// Query parameters for GET /api/v1/snapshots
+#[derive(Deserialize, Debug)]
+struct SnapshotListQuery {
+    /// Only this agent's snapshots; every agent's when absent.
+    #[serde(default)]
+    agent_id: Option<String>,
+    /// Page size, capped at [`SNAPSHOT_PAGE_CAP`].
+    #[serde(default = "default_page_size")]
+    limit: usize,
+    #[serde(default)]
+    offset: usize,
+}
+
+fn default_page_size() -> usize {
+    100
+}
+
 /// Time-bucketed aggregate for time-series visualization (#2)
 #[derive(Serialize, Deserialize, Debug)]
 struct TimeSeriesBucket {
@@ -97,6 +165,103 @@ struct AggregateResponse {
     truncated: bool,
 }
 
+/// Rolled-up view of every agent's snapshot at one point in time.
+#[derive(Serialize, Deserialize, Debug)]
+struct FleetSummary {
+    /// The requested timestamp; `None` means each agent's latest snapshot.
+    at: Option<i64>,
+    total_size_bytes: u64,
+    total_files: u64,
+    /// (Extension, Total Size, File Count) summed over the agents' top
+    /// extensions, largest first. Agents only upload their largest
+    /// extensions, so this undercounts the long tail.
+    top_extensions: Vec<(String, u64, u64)>,
+    /// Agents with a snapshot at or before `at`, heaviest first.
+    agents: Vec<AgentContribution>,
+}
+
+/// One agent's share of a [`FleetSummary`].
+#[derive(Serialize, Deserialize, Debug)]
+struct AgentContribution {
+    agent_id: String,
+    hostname: String,
+    /// When the snapshot used for this agent was taken.
+    timestamp: i64,
+    total_size_bytes: u64,
+    file_count: u64,
+    /// Percentage of the fleet's total bytes.
+    share_percent: f64,
+}
+
+/// Period-over-period growth that stands out from an agent's history.
+#[derive(Serialize, Deserialize, Debug)]
+struct Anomaly {
+    t_start: i64,
+    t_end: i64,
+    delta_bytes: i64,
+    /// The extension whose size changed most over the window.
+    top_extension: Option<ExtensionDelta>,
+}
+
+/// Response of GET /api/v1/anomalies/:agent_id
+#[derive(Serialize, Deserialize, Debug)]
+struct AnomalyReport {
+    agent_id: String,
+    k: f64,
+    /// Mean and (population) standard deviation of the byte deltas between
+    /// consecutive snapshots.
+    mean_delta: f64,
+    stddev_delta: f64,
+    /// Windows whose delta exceeds `mean_delta + k * stddev_delta`, oldest
+    /// first.
+    anomalies: Vec<Anomaly>,
+}
+
+/// Query parameters for anomaly detection
+#[derive(Deserialize)]
+struct AnomalyQuery {
+    /// Standard deviations above the mean a delta must exceed (default: 3)
+    #[serde(default = "default_anomaly_k")]
+    k: f64,
+}
+
+fn default_anomaly_k() -> f64 {
+    3.0
+}
+
+/// Projected size at a point in the future.
+#[derive(Serialize, Deserialize, Debug)]
+struct Projection {
+    days_ahead: u32,
+    total_size_bytes: u64,
+}
+
+/// Response of GET /api/v1/forecast/:agent_id
+///
+/// Projections are measured from the agent's latest snapshot. With fewer
+/// than `MIN_FORECAST_SNAPSHOTS` snapshots no fit is attempted: `message`
+/// explains why and the forecast fields are empty.
+#[derive(Serialize, Deserialize, Debug)]
+struct ForecastReport {
+    agent_id: String,
+    snapshot_count: usize,
+    /// Slope of the least-squares fit of total size over time.
+    bytes_per_day: Option<f64>,
+    projections: Vec<Projection>,
+    capacity_bytes: Option<u64>,
+    /// Days from the latest snapshot until the fit reaches `capacity_bytes`.
+    /// `None` when no capacity was given or the trend is flat or shrinking.
+    days_until_full: Option<f64>,
+    message: Option<String>,
+}
+
+/// Query parameters for forecasting
+#[derive(Deserialize)]
+struct ForecastQuery {
+    /// Disk capacity to project against, in bytes
+    capacity_bytes: Option<u64>,
+}
+
 /// Query parameters for aggregation
 #[derive(Deserialize)]
 struct AggregateQuery {
@@ -111,7 +276,7 @@ fn default_bucket_size() -> i64 {
     3600
 }
 
-async fn ensure_index(db: &Surreal<surrealdb::engine::local::Db>, name: &str, fields: &str) {
+async fn ensure_index(db: &Db, name: &str, fields: &str) {
     let query_if = format!(
         "DEFINE INDEX IF NOT EXISTS {} ON snapshots FIELDS {}",
         name, fields
@@ -152,28 +317,307 @@ struct Policy {
 
 // --- Database Logic ---
 
+type Db = Surreal<Any>;
+
+/// Where snapshots are stored, parsed from `--db-url` / `SPECTRA_DB_URL`
+/// or given by `--data-dir` / `SPECTRA_DATA_DIR`.
+///
+/// - `mem://` (default): in-process, lost on restart
+/// - `rocksdb://path` (or `--data-dir path`): an embedded RocksDB store
+///   that survives restarts
+/// - `ws://host:port` or `wss://host:port`: a shared remote SurrealDB, so
+///   several server instances can serve the same data
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DbTarget {
+    Memory,
+    Local { path: PathBuf },
+    Remote { url: String },
+}
+
+impl DbTarget {
+    fn endpoint(&self) -> String {
+        match self {
+            DbTarget::Memory => "mem://".to_string(),
+            DbTarget::Local { path } => format!("rocksdb://{}", path.display()),
+            DbTarget::Remote { url } => url.clone(),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            DbTarget::Memory => "in-memory mode".to_string(),
+            DbTarget::Local { path } => format!("RocksDB at {}", path.display()),
+            DbTarget::Remote { url } => format!("remote {}", url),
+        }
+    }
+}
+
+impl FromStr for DbTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.split_once("://") {
+            Some(("mem" | "memory", _)) => Ok(DbTarget::Memory),
+            Some(("rocksdb", path)) if !path.is_empty() => Ok(DbTarget::Local {
+                path: PathBuf::from(path),
+            }),
+            Some(("ws" | "wss", host)) if !host.is_empty() => Ok(DbTarget::Remote {
+                url: s.to_string(),
+            }),
+            _ => Err(format!(
+                "unsupported database URL '{}' (expected mem://, rocksdb://path, ws://host:port or wss://host:port)",
+                s
+            )),
+        }
+    }
+}
+
+/// Connect to `target` and select the telemetry namespace. Remote servers
+/// are signed into with `SPECTRA_DB_USER` / `SPECTRA_DB_PASS` when set.
+async fn connect_db(target: &DbTarget, database: &str) -> surrealdb::Result<Db> {
+    let db = surrealdb::engine::any::connect(target.endpoint()).await?;
+    if let DbTarget::Remote { .. } = target {
+        if let (Ok(username), Ok(password)) = (
+            std::env::var("SPECTRA_DB_USER"),
+            std::env::var("SPECTRA_DB_PASS"),
+        ) {
+            db.signin(Root {
+                username: &username,
+                password: &password,
+            })
+            .await?;
+        }
+    }
+    db.use_ns("spectra").use_db(database).await?;
+    Ok(db)
+}
+
+/// Command-line options for the federation server.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct ServerArgs {
+    /// Snapshot store: mem:// (default), rocksdb://path for an embedded
+    /// store, or ws(s)://host:port for a shared remote SurrealDB
+    #[arg(long, env = "SPECTRA_DB_URL", default_value = "mem://")]
+    db_url: DbTarget,
+
+    /// Keep snapshots in an embedded RocksDB store in this directory, so
+    /// they survive restarts (same as --db-url rocksdb://<path>)
+    #[arg(long, env = "SPECTRA_DATA_DIR", conflicts_with = "db_url")]
+    data_dir: Option<PathBuf>,
+
+    /// Delete snapshots older than this many days (checked hourly). Keeps
+    /// everything when unset.
+    #[arg(long, env = "SPECTRA_RETENTION_DAYS")]
+    retention_days: Option<u64>,
+}
+
+impl ServerArgs {
+    fn db_target(&self) -> DbTarget {
+        match &self.data_dir {
+            Some(path) => DbTarget::Local { path: path.clone() },
+            None => self.db_url.clone(),
+        }
+    }
+}
+
 struct AppState {
-    db: Surreal<surrealdb::engine::local::Db>,
+    db: Db,
+    /// Shared secret clients must present; `None` disables authentication.
+    api_key: Option<String>,
 }
 
-// --- Middleware ---
+/// JSON body sent with every handler error response.
+#[derive(Serialize, Deserialize, Debug)]
+struct ErrorBody {
+    error: String,
+}
 
-/// API key authentication middleware.
-async fn require_api_key(request: Request, next: Next) -> Result<Response, StatusCode> {
-    let expected_key = std::env::var("SPECTRA_API_KEY").ok();
+/// Error half of a handler result: a status plus a JSON `ErrorBody`.
+type ApiError = (StatusCode, Json<ErrorBody>);
 
-    let Some(expected) = expected_key else {
+fn api_error(status: StatusCode, message: impl Into<String>) -> ApiError {
+    (
+        status,
+        Json(ErrorBody {
+            error: message.into(),
+        }),
+    )
+}
+
+// --- Middleware ---
+
+/// API key authentication middleware. When the server has a key, every
+/// request must carry `Authorization: Bearer <key>` (older clients may send
+/// `X-API-Key: <key>` instead) or is rejected with 401.
+async fn require_api_key(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let Some(expected) = state.api_key.as_deref() else {
         return Ok(next.run(request).await);
     };
 
-    let provided = request
-        .headers()
-        .get("x-api-key")
-        .and_then(|v| v.to_str().ok());
+    let headers = request.headers();
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| headers.get("x-api-key").and_then(|v| v.to_str().ok()));
 
     match provided {
-        Some(key) if key == expected => Ok(next.run(request).await),
-        _ => Err(StatusCode::UNAUTHORIZED),
+        Some(key) if key.trim() == expected => Ok(next.run(request).await),
+        Some(_) => Err(api_error(StatusCode::UNAUTHORIZED, "invalid API key")),
+        None => Err(api_error(
+            StatusCode::UNAUTHORIZED,
+            "missing API key: send Authorization: Bearer <key>",
+        )),
+    }
+}
+
+/// Header carrying the correlation id for a request.
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Propagate the caller's `X-Request-Id` (or assign a fresh one), run the
+/// request inside a span tagged with it, and echo it on the response so a
+/// single ingest can be followed through the logs.
+async fn request_id(request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && v.len() <= 128)
+        .map(str::to_owned)
+        .unwrap_or_else(new_request_id);
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    let mut response = next.run(request).instrument(span).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// Process-unique id: start time in nanoseconds plus a sequence number.
+fn new_request_id() -> String {
+    static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+    let nanos = chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default();
+    format!(
+        "{:x}-{:04x}",
+        nanos,
+        SEQUENCE.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+// --- Queries ---
+
+/// Fetch the agent's snapshot closest at-or-before `ts`, or the most recent
+/// one when `ts` is `None`. Shared by the velocity and snapshot endpoints.
+async fn snapshot_at_or_before(
+    db: &Db,
+    agent_id: &str,
+    ts: Option<i64>,
+) -> surrealdb::Result<Option<AgentSnapshot>> {
+    let query = match ts {
+        Some(ts) => {
+            db.query(
+                "SELECT * FROM snapshots
+                 WHERE agent_id = $agent_id AND timestamp <= $ts
+                 ORDER BY timestamp DESC LIMIT 1",
+            )
+            .bind(("agent_id", agent_id))
+            .bind(("ts", ts))
+            .await
+        }
+        None => {
+            db.query(
+                "SELECT * FROM snapshots
+                 WHERE agent_id = $agent_id
+                 ORDER BY timestamp DESC LIMIT 1",
+            )
+            .bind(("agent_id", agent_id))
+            .await
+        }
+    };
+    query.and_then(|mut response| response.take(0))
+}
+
+/// Fetch the agent's most recent `AGGREGATE_SNAPSHOT_CAP` snapshots, oldest
+/// first. Shared by the anomaly and forecast endpoints.
+async fn snapshot_history(db: &Db, agent_id: &str) -> surrealdb::Result<Vec<AgentSnapshot>> {
+    let mut snapshots: Vec<AgentSnapshot> = db
+        .query(
+            "SELECT * FROM snapshots
+             WHERE agent_id = $agent_id
+             ORDER BY timestamp DESC
+             LIMIT $limit",
+        )
+        .bind(("agent_id", agent_id))
+        .bind(("limit", AGGREGATE_SNAPSHOT_CAP as i64))
+        .await
+        .and_then(|mut response| response.take(0))?;
+    snapshots.reverse();
+    Ok(snapshots)
+}
+
+/// Delete snapshots taken before `before` (Unix seconds), for one agent or
+/// for all of them. Returns how many were removed.
+async fn delete_snapshots_before(
+    db: &Db,
+    agent_id: Option<&str>,
+    before: i64,
+) -> surrealdb::Result<u64> {
+    let filter = if agent_id.is_some() {
+        "WHERE timestamp < $before AND agent_id = $agent_id"
+    } else {
+        "WHERE timestamp < $before"
+    };
+    let count: Option<Count> = db
+        .query(format!(
+            "SELECT count() FROM snapshots {filter} GROUP ALL;
+             DELETE snapshots {filter};"
+        ))
+        .bind(("before", before))
+        .bind(("agent_id", agent_id.unwrap_or_default()))
+        .await
+        .and_then(|response| response.check())
+        .and_then(|mut response| response.take(0))?;
+    Ok(count.map_or(0, |c| c.count))
+}
+
+/// Apply the `--retention-days` policy as of `now` (Unix seconds).
+async fn apply_retention(db: &Db, retention_days: u64, now: i64) -> surrealdb::Result<u64> {
+    let cutoff = now.saturating_sub((retention_days as i64).saturating_mul(86_400));
+    delete_snapshots_before(db, None, cutoff).await
+}
+
+/// How often the retention task runs.
+const RETENTION_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3_600);
+
+/// Background task enforcing `--retention-days`. Runs once at startup and
+/// then every `RETENTION_INTERVAL`; failures are logged and retried on the
+/// next tick.
+async fn retention_task(state: Arc<AppState>, retention_days: u64) {
+    let mut ticker = tokio::time::interval(RETENTION_INTERVAL);
+    loop {
+        ticker.tick().await;
+        let now = chrono::Utc::now().timestamp();
+        match apply_retention(&state.db, retention_days, now).await {
+            Ok(0) => {}
+            Ok(deleted) => tracing::info!(
+                "🧹 Retention: deleted {} snapshots older than {} days",
+                deleted,
+                retention_days
+            ),
+            Err(e) => tracing::error!("Retention cleanup failed: {:?}", e),
+        }
     }
 }
 
@@ -181,28 +625,70 @@ async fn require_api_key(request: Request, next: Next) -> Result<Response, Statu
 
 /// POST /api/v1/ingest
 ///
-/// Ingest a snapshot from an agent (The "Write" Path)
+/// Ingest a snapshot from an agent (The "Write" Path). Snapshots carrying a
+/// `snapshot_id` are upserted under that id, so retries are idempotent.
+/// Malformed or invalid snapshots are rejected with 400. Responds with the stored
+/// record's id.
 async fn ingest_snapshot(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<AgentSnapshot>,
-) -> Json<String> {
-    let created: Result<Vec<AgentSnapshot>, _> =
-        state.db.create("snapshots").content(&payload).await;
+    payload: Result<Json<AgentSnapshot>, JsonRejection>,
+) -> Result<Json<IngestResponse>, ApiError> {
+    let Json(payload) =
+        payload.map_err(|rejection| api_error(StatusCode::BAD_REQUEST, rejection.body_text()))?;
+    if let Err(reason) = payload.validate(chrono::Utc::now().timestamp()) {
+        tracing::warn!(
+            "⚠️  Rejected snapshot from '{}': {}",
+            payload.agent_id,
+            reason
+        );
+        return Err(api_error(StatusCode::BAD_REQUEST, reason));
+    }
 
-    match created {
-        Ok(_) => {
+    let stored: surrealdb::Result<Option<RecordId>> = match payload.snapshot_id.as_deref() {
+        Some(id) if !id.is_empty() => {
+            state
+                .db
+                .update::<Option<RecordId>>(("snapshots", id))
+                .content(&payload)
+                .await
+        }
+        _ => state
+            .db
+            .create::<Vec<RecordId>>("snapshots")
+            .content(&payload)
+            .await
+            .map(|records| records.into_iter().next()),
+    };
+
+    match stored {
+        Ok(Some(record)) => {
+            let id = record.id.to_string();
             tracing::info!(
-                "📡 Ingested Snapshot: {} @ {} ({}B, {} files)",
+                "📡 Ingested Snapshot {}: {} @ {} ({}B, {} files)",
+                id,
                 payload.agent_id,
                 payload.timestamp,
                 payload.total_size_bytes,
                 payload.file_count
             );
-            Json("Snapshot stored".to_string())
+            Ok(Json(IngestResponse {
+                id,
+                timestamp: payload.timestamp,
+            }))
+        }
+        Ok(None) => {
+            tracing::error!("Store returned no record for snapshot");
+            Err(api_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to store snapshot",
+            ))
         }
         Err(e) => {
             tracing::error!("Failed to store snapshot: {:?}", e);
-            Json(format!("Error: {}", e))
+            Err(api_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to store snapshot",
+            ))
         }
     }
 }
@@ -213,7 +699,7 @@ async fn ingest_snapshot(
 async fn get_agent_history(
     State(state): State<Arc<AppState>>,
     Path(agent_id): Path<String>,
-) -> Json<Vec<i64>> {
+) -> Result<Json<Vec<i64>>, ApiError> {
     let query_result: Result<Vec<i64>, _> = state
         .db
         .query("SELECT VALUE timestamp FROM snapshots WHERE agent_id = $agent_id ORDER BY timestamp DESC")
@@ -228,15 +714,163 @@ async fn get_agent_history(
                 timestamps.len(),
                 agent_id
             );
-            Json(timestamps)
+            Ok(Json(timestamps))
         }
         Err(e) => {
             tracing::error!("Failed to retrieve history: {:?}", e);
-            Json(vec![])
+            Err(api_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to retrieve history",
+            ))
         }
     }
 }
 
+/// Largest page GET /api/v1/snapshots returns, whatever `limit` asks for.
+const SNAPSHOT_PAGE_CAP: usize = 1_000;
+
+/// Header carrying the number of snapshots matching a listing, across pages.
+const TOTAL_COUNT_HEADER: &str = "x-total-count";
+
+#[derive(Deserialize)]
+struct Count {
+    count: u64,
+}
+
+/// GET /api/v1/snapshots?agent_id=<id>&limit=<n>&offset=<n>
+///
+/// Browse stored snapshots, newest first, a page at a time. The total number
+/// of matching snapshots is returned in the `X-Total-Count` header.
+async fn list_snapshots(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<SnapshotListQuery>,
+) -> Result<([(HeaderName, String); 1], Json<Vec<AgentSnapshot>>), ApiError> {
+    let filter = if params.agent_id.is_some() {
+        "WHERE agent_id = $agent_id"
+    } else {
+        ""
+    };
+    let limit = params.limit.min(SNAPSHOT_PAGE_CAP);
+    let result: surrealdb::Result<(Vec<AgentSnapshot>, Option<Count>)> = state
+        .db
+        .query(format!(
+            "SELECT * FROM snapshots {filter} ORDER BY timestamp DESC LIMIT $limit START $offset;
+             SELECT count() FROM snapshots {filter} GROUP ALL;"
+        ))
+        .bind(("agent_id", params.agent_id.as_deref().unwrap_or_default()))
+        .bind(("limit", limit as i64))
+        .bind(("offset", params.offset as i64))
+        .await
+        .and_then(|mut response| Ok((response.take(0)?, response.take(1)?)));
+
+    match result {
+        Ok((snapshots, count)) => {
+            let total = count.map_or(0, |c| c.count);
+            Ok((
+                [(
+                    HeaderName::from_static(TOTAL_COUNT_HEADER),
+                    total.to_string(),
+                )],
+                Json(snapshots),
+            ))
+        }
+        Err(e) => {
+            tracing::error!("Failed to list snapshots: {:?}", e);
+            Err(api_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to list snapshots",
+            ))
+        }
+    }
+}
+
+/// Query parameters for manual snapshot deletion
+#[derive(Deserialize)]
+struct DeleteSnapshotsQuery {
+    /// Delete snapshots taken strictly before this Unix timestamp
+    before: i64,
+}
+
+/// Response of DELETE /api/v1/snapshots/:agent_id
+#[derive(Serialize, Deserialize, Debug)]
+struct DeleteReport {
+    agent_id: String,
+    before: i64,
+    deleted: u64,
+}
+
+/// DELETE /api/v1/snapshots/:agent_id?before=<ts>
+///
+/// Manually prune an agent's history.
+async fn delete_agent_snapshots(
+    State(state): State<Arc<AppState>>,
+    Path(agent_id): Path<String>,
+    Query(params): Query<DeleteSnapshotsQuery>,
+) -> Result<Json<DeleteReport>, ApiError> {
+    match delete_snapshots_before(&state.db, Some(&agent_id), params.before).await {
+        Ok(deleted) => {
+            tracing::info!(
+                "🧹 Deleted {} snapshots for {} before {}",
+                deleted,
+                agent_id,
+                params.before
+            );
+            Ok(Json(DeleteReport {
+                agent_id,
+                before: params.before,
+                deleted,
+            }))
+        }
+        Err(e) => {
+            tracing::error!("Failed to delete snapshots: {:?}", e);
+            Err(api_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to delete snapshots",
+            ))
+        }
+    }
+}
+
+/// Per-extension change from `start` to `end`, largest absolute size change
+/// first. Extensions in only one snapshot count from (or to) zero.
+fn extension_deltas(start: &AgentSnapshot, end: &AgentSnapshot) -> Vec<ExtensionDelta> {
+    // Build a map of start extensions for O(1) lookup
+    let mut start_ext_map: HashMap<String, (u64, u64)> = HashMap::new();
+    for (ext, size, count) in &start.top_extensions {
+        start_ext_map.insert(ext.clone(), (*size, *count));
+    }
+
+    let mut extension_deltas = Vec::new();
+
+    for (ext, end_size, end_count) in &end.top_extensions {
+        if let Some((start_size, start_count)) = start_ext_map.get(ext) {
+            extension_deltas.push(ExtensionDelta {
+                extension: ext.clone(),
+                size_delta: (*end_size as i64) - (*start_size as i64),
+                count_delta: (*end_count as i64) - (*start_count as i64),
+            });
+            start_ext_map.remove(ext);
+        } else {
+            extension_deltas.push(ExtensionDelta {
+                extension: ext.clone(),
+                size_delta: *end_size as i64,
+                count_delta: *end_count as i64,
+            });
+        }
+    }
+
+    for (ext, (start_size, start_count)) in start_ext_map {
+        extension_deltas.push(ExtensionDelta {
+            extension: ext,
+            size_delta: -(start_size as i64),
+            count_delta: -(start_count as i64),
+        });
+    }
+
+    extension_deltas.sort_by(|a, b| b.size_delta.abs().cmp(&a.size_delta.abs()));
+    extension_deltas
+}
+
 /// GET /api/v1/velocity/:agent_id?start=<ts>&end=<ts>
 ///
 /// Calculate Data Velocity between two points in time
@@ -244,32 +878,10 @@ async fn get_velocity(
     State(state): State<Arc<AppState>>,
     Path(agent_id): Path<String>,
     Query(range): Query<TimeRange>,
-) -> Json<VelocityReport> {
-    // Fetch the snapshot closest to the start time
-    let start_snap_result: Result<Option<AgentSnapshot>, _> = state
-        .db
-        .query(
-            "SELECT * FROM snapshots
-             WHERE agent_id = $agent_id AND timestamp <= $ts
-             ORDER BY timestamp DESC LIMIT 1",
-        )
-        .bind(("agent_id", &agent_id))
-        .bind(("ts", range.start))
-        .await
-        .and_then(|mut response| response.take(0));
-
-    // Fetch the snapshot closest to the end time
-    let end_snap_result: Result<Option<AgentSnapshot>, _> = state
-        .db
-        .query(
-            "SELECT * FROM snapshots
-             WHERE agent_id = $agent_id AND timestamp <= $ts
-             ORDER BY timestamp DESC LIMIT 1",
-        )
-        .bind(("agent_id", &agent_id))
-        .bind(("ts", range.end))
-        .await
-        .and_then(|mut response| response.take(0));
+) -> Result<Json<VelocityReport>, ApiError> {
+    // Fetch the snapshots closest to the start and end times
+    let start_snap_result = snapshot_at_or_before(&state.db, &agent_id, Some(range.start)).await;
+    let end_snap_result = snapshot_at_or_before(&state.db, &agent_id, Some(range.end)).await;
 
     // Calculate velocity if both snapshots exist
     match (start_snap_result, end_snap_result) {
@@ -279,40 +891,7 @@ async fn get_velocity(
             let file_diff = (end_snap.file_count as i64) - (start_snap.file_count as i64);
             let duration = end_snap.timestamp - start_snap.timestamp;
 
-            // Build a map of start extensions for O(1) lookup
-            let mut start_ext_map: HashMap<String, (u64, u64)> = HashMap::new();
-            for (ext, size, count) in &start_snap.top_extensions {
-                start_ext_map.insert(ext.clone(), (*size, *count));
-            }
-
-            let mut extension_deltas = Vec::new();
-
-            for (ext, end_size, end_count) in &end_snap.top_extensions {
-                if let Some((start_size, start_count)) = start_ext_map.get(ext) {
-                    extension_deltas.push(ExtensionDelta {
-                        extension: ext.clone(),
-                        size_delta: (*end_size as i64) - (*start_size as i64),
-                        count_delta: (*end_count as i64) - (*start_count as i64),
-                    });
-                    start_ext_map.remove(ext);
-                } else {
-                    extension_deltas.push(ExtensionDelta {
-                        extension: ext.clone(),
-                        size_delta: *end_size as i64,
-                        count_delta: *end_count as i64,
-                    });
-                }
-            }
-
-            for (ext, (start_size, start_count)) in start_ext_map {
-                extension_deltas.push(ExtensionDelta {
-                    extension: ext,
-                    size_delta: -(start_size as i64),
-                    count_delta: -(start_count as i64),
-                });
-            }
-
-            extension_deltas.sort_by(|a, b| b.size_delta.abs().cmp(&a.size_delta.abs()));
+            let extension_deltas = extension_deltas(&start_snap, &end_snap);
 
             let velocity = if duration > 0 {
                 size_diff as f64 / duration as f64
@@ -328,7 +907,7 @@ async fn get_velocity(
                 end_snap.timestamp
             );
 
-            Json(VelocityReport {
+            Ok(Json(VelocityReport {
                 agent_id,
                 t_start: start_snap.timestamp,
                 t_end: end_snap.timestamp,
@@ -337,7 +916,14 @@ async fn get_velocity(
                 growth_files: file_diff,
                 bytes_per_second: velocity,
                 extension_deltas,
-            })
+            }))
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            tracing::error!("Failed to query snapshots for velocity: {:?}", e);
+            Err(api_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to query snapshots",
+            ))
         }
         _ => {
             tracing::warn!(
@@ -346,7 +932,7 @@ async fn get_velocity(
                 range.start,
                 range.end
             );
-            Json(VelocityReport {
+            Ok(Json(VelocityReport {
                 agent_id,
                 t_start: 0,
                 t_end: 0,
@@ -355,68 +941,324 @@ async fn get_velocity(
                 growth_files: 0,
                 bytes_per_second: 0.0,
                 extension_deltas: vec![],
-            })
+            }))
         }
     }
 }
 
-/// GET /api/v1/snapshot/:agent_id?timestamp=<ts>  (#2 - Time-Travel)
+/// GET /api/v1/snapshot/:agent_id?at=<ts>  (#2 - Time-Travel)
 ///
-/// Retrieve the full snapshot at or closest before a given timestamp.
-/// If no timestamp is provided, returns the most recent snapshot.
+/// Retrieve the raw stored snapshot at or closest before a given timestamp
+/// (`timestamp` is accepted as an alias for `at`). If no timestamp is
+/// provided, returns the most recent snapshot. Responds 404 when the agent
+/// has no snapshot in range.
 async fn get_snapshot_at_time(
     State(state): State<Arc<AppState>>,
     Path(agent_id): Path<String>,
     Query(params): Query<TimestampQuery>,
-) -> Json<Option<AgentSnapshot>> {
-    let query = match params.timestamp {
-        Some(ts) => {
-            state
-                .db
-                .query(
-                    "SELECT * FROM snapshots
-                     WHERE agent_id = $agent_id AND timestamp <= $ts
-                     ORDER BY timestamp DESC LIMIT 1",
-                )
-                .bind(("agent_id", &agent_id))
-                .bind(("ts", ts))
-                .await
+) -> Result<Json<AgentSnapshot>, ApiError> {
+    match snapshot_at_or_before(&state.db, &agent_id, params.timestamp).await {
+        Ok(Some(s)) => {
+            tracing::info!(
+                "📸 Snapshot retrieved for {} @ {} ({}B, {} files)",
+                agent_id,
+                s.timestamp,
+                s.total_size_bytes,
+                s.file_count
+            );
+            Ok(Json(s))
         }
-        None => {
-            state
-                .db
-                .query(
-                    "SELECT * FROM snapshots
-                     WHERE agent_id = $agent_id
-                     ORDER BY timestamp DESC LIMIT 1",
-                )
-                .bind(("agent_id", &agent_id))
-                .await
+        Ok(None) => Err(api_error(
+            StatusCode::NOT_FOUND,
+            format!("no snapshot for agent '{}' in range", agent_id),
+        )),
+        Err(e) => {
+            tracing::error!("Failed to retrieve snapshot: {:?}", e);
+            Err(api_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to retrieve snapshot",
+            ))
         }
-    };
+    }
+}
 
-    let result: Result<Option<AgentSnapshot>, _> = query.and_then(|mut response| response.take(0));
+/// GET /api/v1/anomalies/:agent_id?k=<n>
+///
+/// Flag growth spikes: walks the agent's snapshots in time order and reports
+/// every window between consecutive snapshots whose byte delta exceeds the
+/// mean delta by more than `k` standard deviations. Only the most recent
+/// 10,000 snapshots are considered.
+async fn get_anomalies(
+    State(state): State<Arc<AppState>>,
+    Path(agent_id): Path<String>,
+    Query(params): Query<AnomalyQuery>,
+) -> Result<Json<AnomalyReport>, ApiError> {
+    match snapshot_history(&state.db, &agent_id).await {
+        Ok(snapshots) => {
+            let report = detect_anomalies(agent_id, &snapshots, params.k);
+            tracing::info!(
+                "🚨 {} anomalies over {} snapshots for {}",
+                report.anomalies.len(),
+                snapshots.len(),
+                report.agent_id
+            );
+            Ok(Json(report))
+        }
+        Err(e) => {
+            tracing::error!("Failed to query snapshots for anomalies: {:?}", e);
+            Err(api_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to query snapshots",
+            ))
+        }
+    }
+}
 
-    match result {
-        Ok(snap) => {
-            if let Some(s) = &snap {
-                tracing::info!(
-                    "📸 Snapshot retrieved for {} @ {} ({}B, {} files)",
-                    agent_id,
-                    s.timestamp,
-                    s.total_size_bytes,
-                    s.file_count
-                );
+/// Find the spikes in `snapshots`, which must be in time order.
+fn detect_anomalies(agent_id: String, snapshots: &[AgentSnapshot], k: f64) -> AnomalyReport {
+    let deltas: Vec<i64> = snapshots
+        .windows(2)
+        .map(|pair| pair[1].total_size_bytes as i64 - pair[0].total_size_bytes as i64)
+        .collect();
+    let n = deltas.len().max(1) as f64;
+    let mean_delta = deltas.iter().map(|&d| d as f64).sum::<f64>() / n;
+    let variance = deltas
+        .iter()
+        .map(|&d| (d as f64 - mean_delta).powi(2))
+        .sum::<f64>()
+        / n;
+    let stddev_delta = variance.sqrt();
+    let threshold = mean_delta + k * stddev_delta;
+
+    let anomalies = snapshots
+        .windows(2)
+        .zip(&deltas)
+        // A flat history has no spread; nothing stands out from it.
+        .filter(|(_, &delta)| stddev_delta > 0.0 && delta as f64 > threshold)
+        .map(|(pair, &delta)| Anomaly {
+            t_start: pair[0].timestamp,
+            t_end: pair[1].timestamp,
+            delta_bytes: delta,
+            top_extension: extension_deltas(&pair[0], &pair[1]).into_iter().next(),
+        })
+        .collect();
+
+    AnomalyReport {
+        agent_id,
+        k,
+        mean_delta,
+        stddev_delta,
+        anomalies,
+    }
+}
+
+/// Fewest snapshots a forecast is fitted to.
+const MIN_FORECAST_SNAPSHOTS: usize = 3;
+
+/// How far ahead forecasts project, in days.
+const FORECAST_HORIZONS: [u32; 3] = [30, 90, 180];
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// GET /api/v1/forecast/:agent_id?capacity_bytes=<n>
+///
+/// Capacity planning: fits a straight line through the agent's
+/// (timestamp, total_size_bytes) history and projects it forward.
+async fn get_forecast(
+    State(state): State<Arc<AppState>>,
+    Path(agent_id): Path<String>,
+    Query(params): Query<ForecastQuery>,
+) -> Result<Json<ForecastReport>, ApiError> {
+    match snapshot_history(&state.db, &agent_id).await {
+        Ok(snapshots) => {
+            let report = forecast(agent_id, &snapshots, params.capacity_bytes);
+            match report.bytes_per_day {
+                Some(rate) => tracing::info!(
+                    "🔮 Forecast for {}: {:.0} bytes/day over {} snapshots",
+                    report.agent_id,
+                    rate,
+                    report.snapshot_count
+                ),
+                None => tracing::warn!(
+                    "⚠️  Insufficient data for forecast: {} ({} snapshots)",
+                    report.agent_id,
+                    report.snapshot_count
+                ),
             }
-            Json(snap)
+            Ok(Json(report))
         }
         Err(e) => {
-            tracing::error!("Failed to retrieve snapshot: {:?}", e);
-            Json(None)
+            tracing::error!("Failed to query snapshots for forecast: {:?}", e);
+            Err(api_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to query snapshots",
+            ))
         }
     }
 }
 
+/// Fit and project `snapshots`, which must be in time order.
+fn forecast(
+    agent_id: String,
+    snapshots: &[AgentSnapshot],
+    capacity_bytes: Option<u64>,
+) -> ForecastReport {
+    let insufficient = |message: String| ForecastReport {
+        agent_id: agent_id.clone(),
+        snapshot_count: snapshots.len(),
+        bytes_per_day: None,
+        projections: vec![],
+        capacity_bytes,
+        days_until_full: None,
+        message: Some(message),
+    };
+    let Some(latest) = snapshots.last() else {
+        return insufficient("insufficient data: no snapshots".to_string());
+    };
+    if snapshots.len() < MIN_FORECAST_SNAPSHOTS {
+        return insufficient(format!(
+            "insufficient data: need at least {} snapshots, have {}",
+            MIN_FORECAST_SNAPSHOTS,
+            snapshots.len()
+        ));
+    }
+
+    // Least squares in days relative to the latest snapshot, which keeps the
+    // numbers small and makes the intercept the fitted size "now".
+    let points: Vec<(f64, f64)> = snapshots
+        .iter()
+        .map(|s| {
+            (
+                (s.timestamp - latest.timestamp) as f64 / SECONDS_PER_DAY,
+                s.total_size_bytes as f64,
+            )
+        })
+        .collect();
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if sxx == 0.0 {
+        return insufficient("insufficient data: all snapshots share one timestamp".to_string());
+    }
+    let sxy: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let slope = sxy / sxx;
+    let now = mean_y - slope * mean_x;
+
+    let projections = FORECAST_HORIZONS
+        .iter()
+        .map(|&days| Projection {
+            days_ahead: days,
+            total_size_bytes: (now + slope * days as f64).max(0.0).round() as u64,
+        })
+        .collect();
+    let days_until_full = capacity_bytes
+        .filter(|_| slope > 0.0)
+        .map(|capacity| ((capacity as f64 - now) / slope).max(0.0));
+
+    ForecastReport {
+        agent_id,
+        snapshot_count: snapshots.len(),
+        bytes_per_day: Some(slope),
+        projections,
+        capacity_bytes,
+        days_until_full,
+        message: None,
+    }
+}
+
+#[derive(Deserialize)]
+struct AgentIdRow {
+    agent_id: String,
+}
+
+/// GET /api/v1/fleet/summary?at=<ts>
+///
+/// Roll up the whole fleet: for each known agent, the snapshot at or closest
+/// before `at` (the latest when omitted), summed into fleet totals and a
+/// merged extension breakdown, with each agent's contribution. Agents with
+/// no snapshot that early are left out.
+async fn get_fleet_summary(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<TimestampQuery>,
+) -> Result<Json<FleetSummary>, ApiError> {
+    let internal = |e: surrealdb::Error| {
+        tracing::error!("Failed to build fleet summary: {:?}", e);
+        api_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "failed to build fleet summary",
+        )
+    };
+    let agents: Vec<AgentIdRow> = state
+        .db
+        .query("SELECT agent_id FROM snapshots GROUP BY agent_id")
+        .await
+        .and_then(|mut response| response.take(0))
+        .map_err(internal)?;
+
+    let mut snapshots = Vec::new();
+    for AgentIdRow { agent_id } in agents {
+        if let Some(snap) = snapshot_at_or_before(&state.db, &agent_id, params.timestamp)
+            .await
+            .map_err(internal)?
+        {
+            snapshots.push(snap);
+        }
+    }
+
+    Ok(Json(fleet_summary(params.timestamp, snapshots)))
+}
+
+/// Sum one snapshot per agent into a [`FleetSummary`].
+fn fleet_summary(at: Option<i64>, snapshots: Vec<AgentSnapshot>) -> FleetSummary {
+    let total_size_bytes: u64 = snapshots.iter().map(|s| s.total_size_bytes).sum();
+    let total_files = snapshots.iter().map(|s| s.file_count).sum();
+
+    let mut extensions: HashMap<String, (u64, u64)> = HashMap::new();
+    for (ext, size, count) in snapshots.iter().flat_map(|s| &s.top_extensions) {
+        let entry = extensions.entry(ext.clone()).or_default();
+        entry.0 += size;
+        entry.1 += count;
+    }
+    let mut top_extensions: Vec<(String, u64, u64)> = extensions
+        .into_iter()
+        .map(|(ext, (size, count))| (ext, size, count))
+        .collect();
+    top_extensions.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let mut agents: Vec<AgentContribution> = snapshots
+        .into_iter()
+        .map(|s| AgentContribution {
+            share_percent: if total_size_bytes == 0 {
+                0.0
+            } else {
+                s.total_size_bytes as f64 * 100.0 / total_size_bytes as f64
+            },
+            agent_id: s.agent_id,
+            hostname: s.hostname,
+            timestamp: s.timestamp,
+            total_size_bytes: s.total_size_bytes,
+            file_count: s.file_count,
+        })
+        .collect();
+    agents.sort_by(|a, b| {
+        b.total_size_bytes
+            .cmp(&a.total_size_bytes)
+            .then_with(|| a.agent_id.cmp(&b.agent_id))
+    });
+
+    FleetSummary {
+        at,
+        total_size_bytes,
+        total_files,
+        top_extensions,
+        agents,
+    }
+}
+
 /// GET /api/v1/aggregate/:agent_id?start=<ts>&end=<ts>&bucket_seconds=<n>  (#2 - Time-Travel)
 ///
 /// Time-series aggregation with configurable bucket sizes.
@@ -428,7 +1270,7 @@ async fn get_aggregate(
     State(state): State<Arc<AppState>>,
     Path(agent_id): Path<String>,
     Query(params): Query<AggregateQuery>,
-) -> Json<AggregateResponse> {
+) -> Result<Json<AggregateResponse>, ApiError> {
     // Fetch snapshots in the time range (capped to bound memory).
     // LIMIT N+1 so we can detect whether the cap was hit.
     let limit = AGGREGATE_SNAPSHOT_CAP + 1;
@@ -506,14 +1348,14 @@ async fn get_aggregate(
                 params.end,
                 if truncated { " [TRUNCATED]" } else { "" }
             );
-            Json(AggregateResponse { buckets, truncated })
+            Ok(Json(AggregateResponse { buckets, truncated }))
         }
         Err(e) => {
             tracing::error!("Failed to aggregate: {:?}", e);
-            Json(AggregateResponse {
-                buckets: vec![],
-                truncated: false,
-            })
+            Err(api_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to aggregate snapshots",
+            ))
         }
     }
 }
@@ -536,6 +1378,32 @@ async fn get_policies(State(_state): State<Arc<AppState>>) -> Json<Vec<Policy>>
 
 // --- Main ---
 
+/// All API routes with authentication and request-id middleware. The request
+/// id layer is outermost so rejected requests are tagged too.
+fn router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/api/v1/ingest", post(ingest_snapshot))
+        .route("/api/v1/snapshots", get(list_snapshots))
+        .route(
+            "/api/v1/snapshots/:agent_id",
+            delete(delete_agent_snapshots),
+        )
+        .route("/api/v1/history/:agent_id", get(get_agent_history))
+        .route("/api/v1/velocity/:agent_id", get(get_velocity))
+        .route("/api/v1/snapshot/:agent_id", get(get_snapshot_at_time))
+        .route("/api/v1/aggregate/:agent_id", get(get_aggregate))
+        .route("/api/v1/fleet/summary", get(get_fleet_summary))
+        .route("/api/v1/anomalies/:agent_id", get(get_anomalies))
+        .route("/api/v1/forecast/:agent_id", get(get_forecast))
+        .route("/api/v1/policies", get(get_policies))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            require_api_key,
+        ))
+        .layer(middleware::from_fn(request_id))
+        .with_state(state)
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing_subscriber::fmt()
@@ -543,17 +1411,33 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .compact()
         .init();
 
-    let db = Surreal::new::<Mem>(()).await?;
-    db.use_ns("spectra").use_db("telemetry").await?;
+    let args = ServerArgs::parse();
+    let target = args.db_target();
+    let db = connect_db(&target, "telemetry").await?;
 
     // Create indexes for query performance.
     // Tries IF NOT EXISTS, then falls back for older SurrealDB versions.
     ensure_index(&db, "idx_snapshots_agent", "agent_id").await;
     ensure_index(&db, "idx_snapshots_agent_time", "agent_id, timestamp").await;
 
-    tracing::info!("🗄️  Database initialized (in-memory mode) with indexes");
+    tracing::info!(
+        "🗄️  Database initialized ({}) with indexes",
+        target.describe()
+    );
+
+    let api_key = std::env::var("SPECTRA_API_KEY")
+        .ok()
+        .filter(|key| !key.is_empty());
+    let auth_enabled = api_key.is_some();
+    let shared_state = Arc::new(AppState { db, api_key });
 
-    let shared_state = Arc::new(AppState { db });
+    match args.retention_days {
+        Some(days) => {
+            tracing::info!("🧹 Retention enabled: keeping {} days of snapshots", days);
+            tokio::spawn(retention_task(shared_state.clone(), days));
+        }
+        None => tracing::info!("🧹 Retention disabled: keeping all snapshots"),
+    }
 
     let cors = CorsLayer::new()
         .allow_origin(AllowOrigin::list(
@@ -564,10 +1448,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .split(',')
                 .filter_map(|s| s.trim().parse().ok()),
         ))
-        .allow_methods([Method::GET, Method::POST])
-        .allow_headers([header::CONTENT_TYPE, HeaderName::from_static("x-api-key")]);
-
-    if std::env::var("SPECTRA_API_KEY").is_ok() {
+        .allow_methods([Method::GET, Method::POST, Method::DELETE])
+        .allow_headers([
+            header::CONTENT_TYPE,
+            header::AUTHORIZATION,
+            HeaderName::from_static("x-api-key"),
+            HeaderName::from_static(REQUEST_ID_HEADER),
+        ])
+        .expose_headers([
+            HeaderName::from_static(REQUEST_ID_HEADER),
+            HeaderName::from_static(TOTAL_COUNT_HEADER),
+        ]);
+
+    if auth_enabled {
         tracing::info!("🔐 API key authentication enabled");
     } else {
         tracing::warn!(
@@ -575,28 +1468,563 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         );
     }
 
-    let app = Router::new()
-        .route("/api/v1/ingest", post(ingest_snapshot))
-        .route("/api/v1/history/:agent_id", get(get_agent_history))
-        .route("/api/v1/velocity/:agent_id", get(get_velocity))
-        .route("/api/v1/snapshot/:agent_id", get(get_snapshot_at_time))
-        .route("/api/v1/aggregate/:agent_id", get(get_aggregate))
-        .route("/api/v1/policies", get(get_policies))
-        .layer(middleware::from_fn(require_api_key))
-        .layer(cors)
-        .with_state(shared_state);
+    let app = router(shared_state).layer(cors);
 
     let listener = TcpListener::bind("0.0.0.0:3000").await?;
     tracing::info!("🚀 Spectra Brain (Time-Travel Enabled) listening on port 3000");
     tracing::info!("📡 Endpoints:");
     tracing::info!("   POST   /api/v1/ingest");
+    tracing::info!("   GET    /api/v1/snapshots?agent_id=<id>&limit=<n>&offset=<n>");
+    tracing::info!("   DELETE /api/v1/snapshots/:agent_id?before=<ts>");
     tracing::info!("   GET    /api/v1/history/:agent_id");
     tracing::info!("   GET    /api/v1/velocity/:agent_id?start=<ts>&end=<ts>");
-    tracing::info!("   GET    /api/v1/snapshot/:agent_id?timestamp=<ts>");
+    tracing::info!("   GET    /api/v1/snapshot/:agent_id?at=<ts>");
     tracing::info!("   GET    /api/v1/aggregate/:agent_id?start=<ts>&end=<ts>&bucket_seconds=<n>");
+    tracing::info!("   GET    /api/v1/fleet/summary?at=<ts>");
+    tracing::info!("   GET    /api/v1/anomalies/:agent_id?k=<n>");
+    tracing::info!("   GET    /api/v1/forecast/:agent_id?capacity_bytes=<n>");
     tracing::info!("   GET    /api/v1/policies");
 
     axum::serve(listener, app).await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    async fn test_state() -> Arc<AppState> {
+        let db = connect_db(&DbTarget::Memory, "test").await.unwrap();
+        Arc::new(AppState { db, api_key: None })
+    }
+
+    fn snapshot(agent_id: &str, timestamp: i64, total_size_bytes: u64) -> AgentSnapshot {
+        AgentSnapshot {
+            agent_id: agent_id.to_string(),
+            timestamp,
+            hostname: "test-host".to_string(),
+            total_size_bytes,
+            file_count: total_size_bytes / 100,
+            top_extensions: vec![("log".to_string(), total_size_bytes, 10)],
+            snapshot_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_at_returns_raw_record() {
+        let state = test_state().await;
+        ingest_snapshot(
+            State(state.clone()),
+            Ok(Json(snapshot("agent-a", 1_000, 5_000))),
+        )
+        .await
+        .unwrap();
+        ingest_snapshot(
+            State(state.clone()),
+            Ok(Json(snapshot("agent-a", 2_000, 7_000))),
+        )
+        .await
+        .unwrap();
+
+        let Json(snap) = get_snapshot_at_time(
+            State(state.clone()),
+            Path("agent-a".to_string()),
+            Query(TimestampQuery {
+                timestamp: Some(1_500),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(snap.timestamp, 1_000);
+        assert_eq!(snap.total_size_bytes, 5_000);
+        assert_eq!(snap.hostname, "test-host");
+        assert_eq!(snap.top_extensions, vec![("log".to_string(), 5_000, 10)]);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_at_before_first_is_not_found() {
+        let state = test_state().await;
+        ingest_snapshot(
+            State(state.clone()),
+            Ok(Json(snapshot("agent-a", 1_000, 5_000))),
+        )
+        .await
+        .unwrap();
+
+        let result = get_snapshot_at_time(
+            State(state),
+            Path("agent-a".to_string()),
+            Query(TimestampQuery {
+                timestamp: Some(10),
+            }),
+        )
+        .await;
+
+        assert_eq!(result.unwrap_err().0, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_returns_record_id() {
+        let state = test_state().await;
+        let Json(first) = ingest_snapshot(
+            State(state.clone()),
+            Ok(Json(snapshot("agent-a", 1_000, 5_000))),
+        )
+        .await
+        .unwrap();
+        assert!(first.id.starts_with("snapshots:"), "{}", first.id);
+        assert!(first.id.len() > "snapshots:".len());
+        assert_eq!(first.timestamp, 1_000);
+
+        let Json(second) = ingest_snapshot(
+            State(state.clone()),
+            Ok(Json(snapshot("agent-a", 2_000, 5_000))),
+        )
+        .await
+        .unwrap();
+        assert_ne!(second.id, first.id);
+
+        // A client-supplied snapshot_id becomes the record id.
+        let mut snap = snapshot("agent-a", 3_000, 5_000);
+        snap.snapshot_id = Some("run42".to_string());
+        let Json(upserted) = ingest_snapshot(State(state), Ok(Json(snap))).await.unwrap();
+        assert_eq!(upserted.id, "snapshots:run42");
+    }
+
+    #[tokio::test]
+    async fn test_ingest_with_same_snapshot_id_stores_one_row() {
+        let state = test_state().await;
+        let mut snap = snapshot("agent-a", 1_000, 5_000);
+        snap.snapshot_id = Some("6f1c2a4e-8d3b-4c7a-9e15-2b7d0f3a9c41".to_string());
+        for _ in 0..2 {
+            ingest_snapshot(State(state.clone()), Ok(Json(snap.clone())))
+                .await
+                .unwrap();
+        }
+
+        let Json(timestamps) = get_agent_history(State(state), Path("agent-a".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(timestamps, vec![1_000]);
+    }
+
+    #[tokio::test]
+    async fn test_fleet_summary_sums_agents_at_time() {
+        let state = test_state().await;
+        let snap = |agent: &str, ts, size, ext: &str| {
+            let mut s = snapshot(agent, ts, size);
+            s.top_extensions = vec![(ext.to_string(), size, 10)];
+            s
+        };
+        for s in [
+            snap("agent-a", 1_000, 5_000, "log"),
+            snap("agent-a", 3_000, 9_000, "log"),
+            snap("agent-b", 2_000, 15_000, "mp4"),
+            snap("agent-c", 5_000, 1_000, "log"),
+        ] {
+            ingest_snapshot(State(state.clone()), Ok(Json(s)))
+                .await
+                .unwrap();
+        }
+
+        // At 2_500: agent-a's first snapshot and agent-b's; agent-c is later.
+        let Json(fleet) = get_fleet_summary(
+            State(state),
+            Query(TimestampQuery {
+                timestamp: Some(2_500),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(fleet.total_size_bytes, 20_000);
+        assert_eq!(fleet.total_files, 200);
+        assert_eq!(
+            fleet.top_extensions,
+            vec![
+                ("mp4".to_string(), 15_000, 10),
+                ("log".to_string(), 5_000, 10)
+            ]
+        );
+        let agents: Vec<(&str, i64)> = fleet
+            .agents
+            .iter()
+            .map(|a| (a.agent_id.as_str(), a.timestamp))
+            .collect();
+        assert_eq!(agents, vec![("agent-b", 2_000), ("agent-a", 1_000)]);
+        assert_eq!(fleet.agents[0].share_percent, 75.0);
+    }
+
+    #[tokio::test]
+    async fn test_anomalies_flag_only_the_spike() {
+        let state = test_state().await;
+        // Steady growth of 100 bytes per hour, except for one 50 kB jump of
+        // `iso` files between hours 12 and 13.
+        let mut size = 10_000;
+        for hour in 1..=20 {
+            size += if hour == 13 { 50_000 } else { 100 };
+            let mut snap = snapshot("agent-a", hour * 3_600, size);
+            snap.top_extensions = vec![("log".to_string(), 10_000 + hour as u64 * 100, 10)];
+            if hour >= 13 {
+                snap.top_extensions.push(("iso".to_string(), 50_000, 1));
+            }
+            ingest_snapshot(State(state.clone()), Ok(Json(snap)))
+                .await
+                .unwrap();
+        }
+
+        let Json(report) = get_anomalies(
+            State(state),
+            Path("agent-a".to_string()),
+            Query(AnomalyQuery { k: 3.0 }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.anomalies.len(), 1, "{:?}", report);
+        let spike = &report.anomalies[0];
+        assert_eq!((spike.t_start, spike.t_end), (12 * 3_600, 13 * 3_600));
+        assert_eq!(spike.delta_bytes, 50_000);
+        let top = spike.top_extension.as_ref().unwrap();
+        assert_eq!((top.extension.as_str(), top.size_delta), ("iso", 50_000));
+    }
+
+    #[tokio::test]
+    async fn test_forecast_projects_linear_growth() {
+        let state = test_state().await;
+        let day = 86_400;
+        let forecast_for = |state: Arc<AppState>| {
+            get_forecast(
+                State(state),
+                Path("agent-a".to_string()),
+                Query(ForecastQuery {
+                    capacity_bytes: Some(2_000_000),
+                }),
+            )
+        };
+
+        // Two points are not enough to trust a trend.
+        for d in 0..2 {
+            let snap = snapshot("agent-a", (d + 1) * day, 1_000_000 + d as u64 * 10_000);
+            ingest_snapshot(State(state.clone()), Ok(Json(snap)))
+                .await
+                .unwrap();
+        }
+        let Json(report) = forecast_for(state.clone()).await.unwrap();
+        assert!(report.bytes_per_day.is_none());
+        assert!(report.projections.is_empty());
+        assert!(report.message.unwrap().contains("insufficient data"));
+
+        // 10 kB a day, ending at 1.09 MB on day 9.
+        for d in 2..10 {
+            let snap = snapshot("agent-a", (d + 1) * day, 1_000_000 + d as u64 * 10_000);
+            ingest_snapshot(State(state.clone()), Ok(Json(snap)))
+                .await
+                .unwrap();
+        }
+        let Json(report) = forecast_for(state).await.unwrap();
+        assert_eq!(report.snapshot_count, 10);
+        assert!((report.bytes_per_day.unwrap() - 10_000.0).abs() < 1.0);
+        let at_30 = &report.projections[0];
+        assert_eq!(at_30.days_ahead, 30);
+        assert!(at_30.total_size_bytes.abs_diff(1_390_000) < 10);
+        // (2_000_000 - 1_090_000) / 10_000 per day
+        assert!((report.days_until_full.unwrap() - 91.0).abs() < 0.01);
+        assert!(report.message.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_retention_keeps_only_recent_snapshots() {
+        let state = test_state().await;
+        let day = 86_400;
+        let now = 100 * day;
+        for (agent, age_days) in [
+            ("agent-a", 90),
+            ("agent-a", 31),
+            ("agent-a", 5),
+            ("agent-b", 45),
+        ] {
+            let snap = snapshot(agent, now - age_days * day, 1_000);
+            ingest_snapshot(State(state.clone()), Ok(Json(snap)))
+                .await
+                .unwrap();
+        }
+        let remaining = |state: Arc<AppState>, agent: &'static str| async move {
+            let Json(history) = get_agent_history(State(state), Path(agent.to_string()))
+                .await
+                .unwrap();
+            history
+        };
+
+        let deleted = apply_retention(&state.db, 30, now).await.unwrap();
+        assert_eq!(deleted, 3);
+        assert_eq!(
+            remaining(state.clone(), "agent-a").await,
+            vec![now - 5 * day]
+        );
+        assert!(remaining(state.clone(), "agent-b").await.is_empty());
+
+        // Manual pruning only touches the named agent.
+        ingest_snapshot(
+            State(state.clone()),
+            Ok(Json(snapshot("agent-b", now, 1_000))),
+        )
+        .await
+        .unwrap();
+        let Json(report) = delete_agent_snapshots(
+            State(state.clone()),
+            Path("agent-a".to_string()),
+            Query(DeleteSnapshotsQuery { before: now }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.deleted, 1);
+        assert!(remaining(state.clone(), "agent-a").await.is_empty());
+        assert_eq!(remaining(state, "agent-b").await, vec![now]);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_rejects_malformed_snapshots() {
+        let app = router(test_state().await);
+        let post = |snap: &AgentSnapshot| {
+            axum::http::Request::builder()
+                .method(Method::POST)
+                .uri("/api/v1/ingest")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::to_vec(snap).unwrap()))
+                .unwrap()
+        };
+
+        let now = chrono::Utc::now().timestamp();
+        let valid = snapshot("agent-a", now, 5_000);
+        let response = app.clone().oneshot(post(&valid)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(post(&snapshot("", now, 5_000)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: ErrorBody = serde_json::from_slice(&body).unwrap();
+        assert!(error.error.contains("agent_id"), "{}", error.error);
+
+        for timestamp in [0, -5, now + 7 * 86_400] {
+            let response = app
+                .clone()
+                .oneshot(post(&snapshot("agent-a", timestamp, 5_000)))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", timestamp);
+        }
+
+        // Bodies that aren't a snapshot at all get the same JSON error shape.
+        let response = app
+            .oneshot(
+                axum::http::Request::builder()
+                    .method(Method::POST)
+                    .uri("/api/v1/ingest")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from("{\"agent_id\": 5"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: ErrorBody = serde_json::from_slice(&body).unwrap();
+        assert!(!error.error.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_listing_pages_newest_first() {
+        let state = test_state().await;
+        for ts in 1..=5 {
+            ingest_snapshot(State(state.clone()), Ok(Json(snapshot("agent-a", ts, 100))))
+                .await
+                .unwrap();
+        }
+        ingest_snapshot(State(state.clone()), Ok(Json(snapshot("agent-b", 9, 100))))
+            .await
+            .unwrap();
+
+        let (headers, Json(page)) = list_snapshots(
+            State(state.clone()),
+            Query(SnapshotListQuery {
+                agent_id: Some("agent-a".to_string()),
+                limit: 2,
+                offset: 2,
+            }),
+        )
+        .await
+        .unwrap();
+        let timestamps: Vec<i64> = page.iter().map(|s| s.timestamp).collect();
+        assert_eq!(timestamps, vec![3, 2]);
+        assert_eq!(headers[0].1, "5");
+
+        // Without an agent every snapshot counts; oversized pages are capped.
+        let (headers, Json(page)) = list_snapshots(
+            State(state),
+            Query(SnapshotListQuery {
+                agent_id: None,
+                limit: 1_000_000,
+                offset: 0,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(page.len(), 6);
+        assert_eq!(page[0].agent_id, "agent-b");
+        assert_eq!(headers[0].1, "6");
+    }
+
+    #[tokio::test]
+    async fn test_local_store_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = DbTarget::Local {
+            path: dir.path().join("data"),
+        };
+
+        let db = connect_db(&target, "test").await.unwrap();
+        let state = Arc::new(AppState { db, api_key: None });
+        ingest_snapshot(
+            State(state.clone()),
+            Ok(Json(snapshot("agent-a", 1_000, 5_000))),
+        )
+        .await
+        .unwrap();
+        drop(state);
+
+        // RocksDB holds a lock on the directory until the first engine has
+        // shut down, which happens in the background after the drop.
+        let mut reopened = None;
+        for _ in 0..50 {
+            match connect_db(&target, "test").await {
+                Ok(db) => {
+                    reopened = Some(db);
+                    break;
+                }
+                Err(_) => tokio::time::sleep(std::time::Duration::from_millis(100)).await,
+            }
+        }
+        let state = Arc::new(AppState {
+            db: reopened.expect("store should reopen after restart"),
+            api_key: None,
+        });
+
+        let Json(snap) = get_snapshot_at_time(
+            State(state),
+            Path("agent-a".to_string()),
+            Query(TimestampQuery {
+                timestamp: Some(1_000),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(snap.total_size_bytes, 5_000);
+    }
+
+    #[test]
+    fn test_db_url_selects_engine() {
+        assert_eq!("mem://".parse::<DbTarget>(), Ok(DbTarget::Memory));
+        assert_eq!(
+            "ws://db.internal:8000".parse::<DbTarget>(),
+            Ok(DbTarget::Remote {
+                url: "ws://db.internal:8000".to_string()
+            })
+        );
+        assert!(matches!(
+            "wss://db.example.com".parse::<DbTarget>(),
+            Ok(DbTarget::Remote { .. })
+        ));
+        assert_eq!(
+            "rocksdb:///var/lib/spectra".parse::<DbTarget>(),
+            Ok(DbTarget::Local {
+                path: PathBuf::from("/var/lib/spectra")
+            })
+        );
+        assert!("ws://".parse::<DbTarget>().is_err());
+        assert!("postgres://db:5432".parse::<DbTarget>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_api_key_requires_bearer_token() {
+        let db = connect_db(&DbTarget::Memory, "test").await.unwrap();
+        let app = router(Arc::new(AppState {
+            db,
+            api_key: Some("s3cret".to_string()),
+        }));
+        let get_policies = |auth: Option<(HeaderName, &str)>| {
+            let mut request = axum::http::Request::builder().uri("/api/v1/policies");
+            if let Some((name, value)) = auth {
+                request = request.header(name, value);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        for (auth, expected) in [
+            (None, StatusCode::UNAUTHORIZED),
+            (
+                Some((header::AUTHORIZATION, "Bearer wrong")),
+                StatusCode::UNAUTHORIZED,
+            ),
+            // The key alone, without the scheme, is not accepted.
+            (
+                Some((header::AUTHORIZATION, "s3cret")),
+                StatusCode::UNAUTHORIZED,
+            ),
+            (
+                Some((header::AUTHORIZATION, "Bearer s3cret")),
+                StatusCode::OK,
+            ),
+            (
+                Some((HeaderName::from_static("x-api-key"), "s3cret")),
+                StatusCode::OK,
+            ),
+        ] {
+            let description = format!("{:?}", auth);
+            let response = app.clone().oneshot(get_policies(auth)).await.unwrap();
+            assert_eq!(response.status(), expected, "{}", description);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_id_is_echoed_or_generated() {
+        let app = router(test_state().await);
+
+        let response = app
+            .clone()
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/api/v1/policies")
+                    .header(REQUEST_ID_HEADER, "trace-abc-123")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "trace-abc-123");
+
+        let response = app
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/api/v1/policies")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let generated = response.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+        assert!(!generated.is_empty());
+        assert_ne!(generated, "trace-abc-123");
+    }
+}
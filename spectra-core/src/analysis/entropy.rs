@@ -0,0 +1,454 @@
+use super::text::is_likely_text;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+const SAMPLE_SIZE: usize = 8192; // Read first 8KB
+
+/// Entropy (bits/byte) above which content is likely encrypted or compressed.
+pub const HIGH_ENTROPY_THRESHOLD: f32 = 7.5;
+
+/// Chi-square band (255 degrees of freedom, roughly the 0.5th to 99.5th
+/// percentile) that uniformly random bytes fall in. Encrypted data lands
+/// inside it; compressed data has enough structure left to land far above.
+pub const CHI_SQUARE_RANDOM_MIN: f64 = 200.0;
+pub const CHI_SQUARE_RANDOM_MAX: f64 = 320.0;
+
+/// Fewest sampled bytes the chi-square test is meaningful for (five
+/// expected occurrences of each byte value).
+pub const CHI_SQUARE_MIN_SAMPLE: u64 = 256 * 5;
+
+/// Smallest block [`block_entropy`] is worth computing over: much below
+/// this, even random data can't reach [`HIGH_ENTROPY_THRESHOLD`].
+pub const MIN_ENTROPY_BLOCK_SIZE: usize = 4096;
+
+/// Coarse content class derived from header entropy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntropyClass {
+    /// Plain text, source, structured data.
+    Text,
+    /// Compressed archives and media; high entropy but expected.
+    Compressed,
+    /// Indistinguishable from random bytes.
+    Encrypted,
+}
+
+/// Lower bounds (bits/byte) for [`EntropyClass::Compressed`] and
+/// [`EntropyClass::Encrypted`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EntropyThresholds {
+    pub compressed: f32,
+    pub encrypted: f32,
+}
+
+impl Default for EntropyThresholds {
+    fn default() -> Self {
+        // Zip/JPEG headers typically land around 7.6-7.9; ciphertext sampled
+        // over 8KB sits within a few hundredths of the 8.0 ceiling.
+        Self {
+            compressed: 6.0,
+            encrypted: 7.95,
+        }
+    }
+}
+
+/// Bucket an entropy value using `thresholds`.
+pub fn classify_entropy(entropy: f32, thresholds: &EntropyThresholds) -> EntropyClass {
+    if entropy >= thresholds.encrypted {
+        EntropyClass::Encrypted
+    } else if entropy >= thresholds.compressed {
+        EntropyClass::Compressed
+    } else {
+        EntropyClass::Text
+    }
+}
+
+/// Byte frequency table fed incrementally, so entropy can be computed over
+/// data streamed in chunks (e.g. the chunks read for hashing).
+#[derive(Debug, Clone)]
+pub struct ByteFrequencies {
+    counts: [u64; 256],
+    total: u64,
+}
+
+impl Default for ByteFrequencies {
+    fn default() -> Self {
+        Self {
+            counts: [0; 256],
+            total: 0,
+        }
+    }
+}
+
+impl ByteFrequencies {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.counts[byte as usize] += 1;
+        }
+        self.total += data.len() as u64;
+    }
+
+    /// Shannon entropy of everything fed so far, 0.0 (uniform) to 8.0
+    /// (random); 0.0 when nothing was fed.
+    pub fn entropy(&self) -> f32 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let len = self.total as f32;
+        let mut entropy = 0.0;
+        for &count in self.counts.iter() {
+            if count > 0 {
+                let p = count as f32 / len;
+                entropy -= p * p.log2();
+            }
+        }
+        entropy
+    }
+
+    /// Bytes fed so far.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Pearson's chi-square statistic of the byte counts against a uniform
+    /// distribution (255 degrees of freedom); 0.0 when nothing was fed.
+    pub fn chi_square(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let expected = self.total as f64 / 256.0;
+        self.counts
+            .iter()
+            .map(|&count| (count as f64 - expected).powi(2) / expected)
+            .sum()
+    }
+}
+
+/// Calculates Shannon Entropy.
+/// Returns a value between 0.0 (uniform) and 8.0 (random).
+pub fn calculate_shannon_entropy(path: &Path) -> io::Result<f32> {
+    Ok(sample_frequencies(path)?.entropy())
+}
+
+/// Chi-square statistic of the first 8KB's byte frequencies against a
+/// uniform distribution. Random (encrypted) data scores near 255, the
+/// degrees of freedom; compressed data, which keeps some structure, scores
+/// much higher. See [`is_random_like`].
+pub fn chi_square_uniformity(path: &Path) -> io::Result<f64> {
+    Ok(sample_frequencies(path)?.chi_square())
+}
+
+/// Whether a sample with this entropy and chi-square looks like encrypted
+/// rather than merely compressed data: entropy above
+/// [`HIGH_ENTROPY_THRESHOLD`] and chi-square within
+/// [`CHI_SQUARE_RANDOM_MIN`]..=[`CHI_SQUARE_RANDOM_MAX`].
+pub fn is_random_like(entropy: f32, chi_square: f64) -> bool {
+    entropy > HIGH_ENTROPY_THRESHOLD
+        && (CHI_SQUARE_RANDOM_MIN..=CHI_SQUARE_RANDOM_MAX).contains(&chi_square)
+}
+
+/// [`is_random_like`] on the file's first 8KB, or `None` when the file is
+/// shorter than [`CHI_SQUARE_MIN_SAMPLE`] and the test can't tell. A head
+/// that [`is_likely_text`] is never encrypted, so the test is skipped.
+pub fn detect_encryption(path: &Path) -> io::Result<Option<bool>> {
+    let sample = read_sample(path)?;
+    if sample.len() < CHI_SQUARE_MIN_SAMPLE as usize {
+        return Ok(None);
+    }
+    if is_likely_text(&sample) {
+        return Ok(Some(false));
+    }
+    let mut frequencies = ByteFrequencies::new();
+    frequencies.update(&sample);
+    Ok(Some(is_random_like(
+        frequencies.entropy(),
+        frequencies.chi_square(),
+    )))
+}
+
+/// Byte frequencies of the file's head (up to 8KB): read once for speed.
+fn sample_frequencies(path: &Path) -> io::Result<ByteFrequencies> {
+    let mut frequencies = ByteFrequencies::new();
+    frequencies.update(&read_sample(path)?);
+    Ok(frequencies)
+}
+
+fn read_sample(path: &Path) -> io::Result<Vec<u8>> {
+    let mut sample = Vec::with_capacity(SAMPLE_SIZE);
+    File::open(path)?
+        .take(SAMPLE_SIZE as u64)
+        .read_to_end(&mut sample)?;
+    Ok(sample)
+}
+
+/// Shannon entropy of the whole file rather than its first 8KB, for files
+/// whose header doesn't represent the body (a text preamble in front of an
+/// encrypted payload, or vice versa). Streams the file in 64KB chunks, so
+/// memory stays constant whatever its size; cost is a full read.
+pub fn calculate_shannon_entropy_full(path: &Path) -> io::Result<f32> {
+    let mut frequencies = ByteFrequencies::new();
+    crate::hash::for_each_chunk(File::open(path)?, |chunk| frequencies.update(chunk))?;
+    Ok(frequencies.entropy())
+}
+
+/// Shannon entropy of each consecutive `block_size` block of the file, in
+/// order (the last block may be shorter). A file that's mostly plain but
+/// contains an encrypted or packed region shows up as a few blocks near
+/// 8.0 among low ones, which a single whole-file number averages away.
+/// An empty file yields no blocks.
+pub fn block_entropy(path: &Path, block_size: usize) -> io::Result<Vec<f32>> {
+    Ok(entropy_profile(path, block_size)?.1)
+}
+
+/// Whole-file entropy (as [`calculate_shannon_entropy_full`]) and the
+/// [`block_entropy`] profile from a single read. Blocks are tallied as the
+/// file streams past in 64KB chunks, so memory stays constant whatever the
+/// block size.
+pub fn entropy_profile(path: &Path, block_size: usize) -> io::Result<(f32, Vec<f32>)> {
+    let mut profile = EntropyProfile::new(block_size);
+    crate::hash::for_each_chunk(File::open(path)?, |chunk| profile.update(chunk))?;
+    Ok(profile.finish())
+}
+
+/// Streaming tally behind [`entropy_profile`], fed a chunk at a time so
+/// other passes over the same bytes (hashing) can share the read.
+pub(crate) struct EntropyProfile {
+    block_size: u64,
+    whole: ByteFrequencies,
+    block: ByteFrequencies,
+    blocks: Vec<f32>,
+}
+
+impl EntropyProfile {
+    pub(crate) fn new(block_size: usize) -> Self {
+        Self {
+            block_size: block_size.max(1) as u64,
+            whole: ByteFrequencies::new(),
+            block: ByteFrequencies::new(),
+            blocks: Vec::new(),
+        }
+    }
+
+    pub(crate) fn update(&mut self, mut chunk: &[u8]) {
+        self.whole.update(chunk);
+        while !chunk.is_empty() {
+            let room = usize::try_from(self.block_size - self.block.total()).unwrap_or(usize::MAX);
+            let (head, rest) = chunk.split_at(room.min(chunk.len()));
+            self.block.update(head);
+            if self.block.total() == self.block_size {
+                self.blocks.push(std::mem::take(&mut self.block).entropy());
+            }
+            chunk = rest;
+        }
+    }
+
+    /// Whole-stream entropy and the block profile, last partial block included.
+    pub(crate) fn finish(mut self) -> (f32, Vec<f32>) {
+        if self.block.total() > 0 {
+            self.blocks.push(self.block.entropy());
+        }
+        (self.whole.entropy(), self.blocks)
+    }
+}
+
+/// Highest entropy in a [`block_entropy`] profile; `None` when it's empty.
+pub fn max_block_entropy(blocks: &[f32]) -> Option<f32> {
+    blocks.iter().copied().reduce(f32::max)
+}
+
+/// Population variance of a [`block_entropy`] profile: near 0 for
+/// uniform content, large when plain and encrypted regions are mixed.
+/// 0.0 for an empty profile.
+pub fn entropy_variance(blocks: &[f32]) -> f32 {
+    if blocks.is_empty() {
+        return 0.0;
+    }
+    let n = blocks.len() as f32;
+    let mean = blocks.iter().sum::<f32>() / n;
+    blocks.iter().map(|e| (e - mean).powi(2)).sum::<f32>() / n
+}
+
+/// Files read per extension by [`average_entropy_by_extension`].
+pub const EXTENSION_ENTROPY_SAMPLES: usize = 32;
+
+/// Running mean entropy per extension over the first
+/// `samples_per_extension` readable files of each sample, e.g.
+/// `ScanStats::extension_samples`. Keys are kept as given, so they line up
+/// with `ScanStats::extensions`; extensions with no readable file are left
+/// out.
+pub fn average_entropy_by_extension(
+    samples: &HashMap<String, Vec<String>>,
+    samples_per_extension: usize,
+) -> HashMap<String, f32> {
+    samples
+        .iter()
+        .filter_map(|(ext, paths)| {
+            let mut mean = 0.0;
+            let mut n = 0;
+            for path in paths {
+                if n >= samples_per_extension {
+                    break;
+                }
+                if let Ok(entropy) = calculate_shannon_entropy(Path::new(path)) {
+                    n += 1;
+                    mean += (entropy - mean) / n as f32;
+                }
+            }
+            (n > 0).then(|| (ext.clone(), mean))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::noise;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_low_entropy() {
+        // Repeated bytes should have 0 entropy
+        let mut file = NamedTempFile::new().unwrap();
+        let zeros = [0u8; 1000];
+        file.write_all(&zeros).unwrap();
+
+        let ent = calculate_shannon_entropy(file.path()).unwrap();
+        assert_eq!(ent, 0.0);
+    }
+
+    #[test]
+    fn test_medium_entropy() {
+        // Text data should have moderate entropy
+        let mut file = NamedTempFile::new().unwrap();
+        let text = b"The quick brown fox jumps over the lazy dog. ".repeat(10);
+        file.write_all(&text).unwrap();
+
+        let ent = calculate_shannon_entropy(file.path()).unwrap();
+        assert!(ent > 3.0 && ent < 6.0);
+    }
+
+    #[test]
+    fn test_full_entropy_sees_past_the_header() {
+        // A 16KB text preamble in front of 1MB of noise.
+        let mut file = NamedTempFile::new().unwrap();
+        let text = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(300);
+        file.write_all(&text[..16 * 1024]).unwrap();
+        file.write_all(&noise(1 << 20, 0x9e37_79b9_7f4a_7c15))
+            .unwrap();
+
+        let head = calculate_shannon_entropy(file.path()).unwrap();
+        let full = calculate_shannon_entropy_full(file.path()).unwrap();
+        assert!(head < 5.0, "{}", head);
+        assert!(full > HIGH_ENTROPY_THRESHOLD, "{}", full);
+    }
+
+    #[test]
+    fn test_block_entropy_separates_zeros_from_noise() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&[0u8; 64 * 1024]).unwrap();
+        file.write_all(&noise(64 * 1024, 0x2545_f491_4f6c_dd1d))
+            .unwrap();
+
+        let blocks = block_entropy(file.path(), 32 * 1024).unwrap();
+        assert_eq!(blocks.len(), 4);
+        assert_eq!(blocks[..2], [0.0, 0.0]);
+        assert!(blocks[2..].iter().all(|&e| e > 7.9), "{:?}", blocks);
+        assert!(max_block_entropy(&blocks).unwrap() > HIGH_ENTROPY_THRESHOLD);
+        assert!(entropy_variance(&blocks) > 15.0);
+
+        // Blocks that don't line up with the 64KB read chunks.
+        let (whole, odd) = entropy_profile(file.path(), 100_000).unwrap();
+        assert_eq!(odd.len(), 2);
+        assert!(odd[0] < odd[1], "{:?}", odd);
+        let full = calculate_shannon_entropy_full(file.path()).unwrap();
+        assert!((whole - full).abs() < 1e-6, "{} vs {}", whole, full);
+
+        let uniform = block_entropy(file.path(), 128 * 1024).unwrap();
+        assert_eq!(uniform.len(), 1);
+        assert_eq!(entropy_variance(&uniform), 0.0);
+        assert_eq!(max_block_entropy(&[]), None);
+    }
+
+    #[test]
+    fn test_chi_square_separates_random_from_compressed() {
+        use flate2::{write::ZlibEncoder, Compression};
+
+        let random = noise(SAMPLE_SIZE, 0x9e37_79b9_7f4a_7c15);
+        let text: String = (0..20_000)
+            .map(|i| format!("row {}: order {} shipped to depot {}\n", i, i * 7, i % 13))
+            .collect();
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert!(compressed.len() >= SAMPLE_SIZE);
+
+        let dir = tempfile::tempdir().unwrap();
+        let (enc, zlib) = (dir.path().join("blob.enc"), dir.path().join("blob.z"));
+        std::fs::write(&enc, &random).unwrap();
+        std::fs::write(&zlib, &compressed).unwrap();
+
+        let random_chi = chi_square_uniformity(&enc).unwrap();
+        let zlib_chi = chi_square_uniformity(&zlib).unwrap();
+        assert!(
+            (CHI_SQUARE_RANDOM_MIN..=CHI_SQUARE_RANDOM_MAX).contains(&random_chi),
+            "{}",
+            random_chi
+        );
+        assert!(zlib_chi > CHI_SQUARE_RANDOM_MAX * 2.0, "{}", zlib_chi);
+        assert_eq!(detect_encryption(&enc).unwrap(), Some(true));
+        assert_eq!(detect_encryption(&zlib).unwrap(), Some(false));
+
+        // Too little data to judge.
+        std::fs::write(&enc, &random[..100]).unwrap();
+        assert_eq!(detect_encryption(&enc).unwrap(), None);
+        // Uniform counts but low entropy can't be encrypted.
+        assert!(!is_random_like(6.0, 255.0));
+    }
+
+    #[test]
+    fn test_classify_entropy_defaults() {
+        let thresholds = EntropyThresholds::default();
+        assert_eq!(classify_entropy(3.2, &thresholds), EntropyClass::Text);
+        assert_eq!(classify_entropy(7.9, &thresholds), EntropyClass::Compressed);
+        assert_eq!(classify_entropy(7.99, &thresholds), EntropyClass::Encrypted);
+    }
+
+    #[test]
+    fn test_zero_filled_extension_averages_near_zero() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..50 {
+            let path = dir.path().join(format!("blob_{}.dat", i));
+            std::fs::write(&path, vec![0u8; 4096]).unwrap();
+        }
+        std::fs::write(
+            dir.path().join("notes.txt"),
+            b"The quick brown fox jumps over the lazy dog.",
+        )
+        .unwrap();
+
+        // The scan draws the sample; no second walk.
+        let stats = crate::Scanner::new(dir.path(), 0)
+            .with_extension_samples(EXTENSION_ENTROPY_SAMPLES)
+            .scan()
+            .unwrap();
+        assert_eq!(
+            stats.extension_samples["dat"].len(),
+            EXTENSION_ENTROPY_SAMPLES
+        );
+        assert_eq!(stats.extension_samples["txt"].len(), 1);
+
+        let averages =
+            average_entropy_by_extension(&stats.extension_samples, EXTENSION_ENTROPY_SAMPLES);
+        assert!(averages["dat"] < 0.01);
+        assert!(averages["txt"] > 3.0);
+    }
+}
@@ -0,0 +1,117 @@
+//! Text-vs-binary detection from a content sample.
+//!
+//! One score, so every feature that needs to know "is this text" (semantic
+//! classification, secret scanning, encryption detection) agrees and can be
+//! tuned in one place.
+
+/// Scores at or above this are treated as text.
+pub const TEXT_LIKELIHOOD_THRESHOLD: f32 = 0.5;
+
+/// Ceiling for any sample containing a NUL byte. Text encodings used on
+/// disk (ASCII, UTF-8, Latin-1) never contain one, so a NUL keeps the
+/// sample below [`TEXT_LIKELIHOOD_THRESHOLD`] however clean the rest is.
+const NUL_CAP: f32 = 0.4;
+
+/// How strongly control characters count against a sample: at 20% of the
+/// bytes the score reaches zero.
+const CONTROL_WEIGHT: f32 = 5.0;
+
+/// How likely `sample` is to be text, from 0.0 (binary) to 1.0 (text).
+///
+/// The score is the share of bytes that form valid UTF-8, reduced by the
+/// share of control characters other than whitespace, escape and form
+/// feed, and capped below the text threshold when a NUL byte is present. A
+/// multi-byte character cut off at the end of the sample is not held
+/// against it. An empty sample scores 0.0.
+pub fn detect_text_likelihood(sample: &[u8]) -> f32 {
+    if sample.is_empty() {
+        return 0.0;
+    }
+    let mut valid = 0;
+    let mut chunks = sample.utf8_chunks().peekable();
+    while let Some(chunk) = chunks.next() {
+        valid += chunk.valid().len();
+        // Only the final chunk can end in a truncated character.
+        let tail = chunk.invalid();
+        if chunks.peek().is_none() && is_truncated_utf8(tail) {
+            valid += tail.len();
+        }
+    }
+    let control = sample.iter().filter(|&&b| is_binary_control(b)).count();
+
+    let len = sample.len() as f32;
+    let utf8_share = valid as f32 / len;
+    let control_penalty = (control as f32 / len * CONTROL_WEIGHT).min(1.0);
+    let score = utf8_share * (1.0 - control_penalty);
+    if sample.contains(&0) {
+        score.min(NUL_CAP)
+    } else {
+        score
+    }
+}
+
+/// Whether `sample` scores as text (see [`detect_text_likelihood`]).
+pub fn is_likely_text(sample: &[u8]) -> bool {
+    detect_text_likelihood(sample) >= TEXT_LIKELIHOOD_THRESHOLD
+}
+
+/// Control bytes that don't occur in ordinary text files.
+fn is_binary_control(byte: u8) -> bool {
+    matches!(byte, 0x00..=0x08 | 0x0e..=0x1a | 0x1c..=0x1f | 0x7f)
+}
+
+/// A leading byte followed by fewer continuation bytes than it announces.
+fn is_truncated_utf8(tail: &[u8]) -> bool {
+    let Some(&lead) = tail.first() else {
+        return false;
+    };
+    let expected = match lead {
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => return false,
+    };
+    tail.len() < expected && tail[1..].iter().all(|b| b & 0xc0 == 0x80)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::noise;
+
+    const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+    #[test]
+    fn test_ascii_text_scores_high() {
+        let text = b"fn main() {\n\tprintln!(\"hello\");\n}\r\n".repeat(50);
+        assert!(detect_text_likelihood(&text) > 0.99);
+        assert!(is_likely_text(&text));
+
+        let accented = "Résumé: naïve café, 日本語 テキスト\n".repeat(40);
+        // Cut mid-character, as a fixed-size sample would.
+        let bytes = &accented.as_bytes()[..accented.len() - 2];
+        assert!(detect_text_likelihood(bytes) > 0.99);
+    }
+
+    #[test]
+    fn test_random_bytes_score_low() {
+        let score = detect_text_likelihood(&noise(8192, SEED));
+        assert!(score < 0.3, "{}", score);
+        assert!(!is_likely_text(&noise(8192, SEED)));
+        assert_eq!(detect_text_likelihood(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_text_with_embedded_binary_scores_in_between() {
+        let mut doc = "Quarterly report\n\nRevenue grew in every region.\n"
+            .repeat(20)
+            .into_bytes();
+        doc.extend(noise(64, SEED));
+        doc.extend(b"\nAppendix follows.\n".repeat(10));
+
+        let score = detect_text_likelihood(&doc);
+        let random = detect_text_likelihood(&noise(doc.len(), SEED));
+        assert!(score > random, "{} vs {}", score, random);
+        assert!(score > 0.2 && score < 0.99, "{}", score);
+    }
+}
@@ -0,0 +1,61 @@
+//! Back-pressure between jwalk's directory readers and the scan aggregator.
+//!
+//! jwalk hands finished directory listings to the consumer through unbounded
+//! queues, so on very wide trees the readers can buffer millions of entries
+//! before aggregation catches up. [`QueueGate`] counts entries that have been
+//! read but not yet consumed and parks readers once that count reaches the
+//! bound.
+//!
+//! jwalk yields entries in directory order, so the consumer may be waiting on
+//! the very listing a parked reader holds. A reader therefore only stays
+//! parked while the consumer keeps draining; if nothing is drained for
+//! [`STALL_TIMEOUT`] it proceeds anyway. The bound is thus soft: it can be
+//! exceeded by at most one directory listing per walker thread.
+
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+const STALL_TIMEOUT: Duration = Duration::from_millis(20);
+
+#[derive(Debug)]
+pub(crate) struct QueueGate {
+    bound: usize,
+    in_flight: Mutex<usize>,
+    drained: Condvar,
+}
+
+impl QueueGate {
+    pub(crate) fn new(bound: usize) -> Self {
+        Self {
+            bound: bound.max(1),
+            in_flight: Mutex::new(0),
+            drained: Condvar::new(),
+        }
+    }
+
+    /// Called by a reader before publishing `count` entries.
+    pub(crate) fn admit(&self, count: usize) {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        while *in_flight >= self.bound {
+            let (guard, wait) = self
+                .drained
+                .wait_timeout(in_flight, STALL_TIMEOUT)
+                .unwrap_or_else(|e| e.into_inner());
+            in_flight = guard;
+            if wait.timed_out() {
+                // The consumer is stalled, most likely on our listing.
+                break;
+            }
+        }
+        *in_flight += count;
+    }
+
+    /// Called by the consumer for every entry it takes off the walk.
+    pub(crate) fn release(&self) {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        *in_flight = in_flight.saturating_sub(1);
+        if *in_flight < self.bound {
+            self.drained.notify_all();
+        }
+    }
+}
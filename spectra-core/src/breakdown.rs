@@ -0,0 +1,177 @@
+//! Per-directory size rollups in the spirit of `du --max-depth`: which
+//! subfolder is actually eating the disk.
+//!
+//! Unlike `ScanStats::top_dirs`, which keeps only the largest directories at
+//! any depth, this walks every file so each directory down to the requested
+//! depth gets an exact total.
+
+use crate::Scanner;
+use anyhow::Result;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Label of the scan root in a breakdown.
+pub const ROOT_LABEL: &str = ".";
+
+/// Recursive size of every directory within `depth` levels below the
+/// scanner's root.
+///
+/// Paths are relative to the root and `/`-separated; the root itself is
+/// [`ROOT_LABEL`] and always comes first. The rest follow in tree order
+/// with siblings largest first, so a directory is listed right before its
+/// children. Files deeper than `depth` count towards their ancestor at
+/// `depth`. The walk honours the scanner's exclusions, extension filter,
+/// hidden-entry and symlink settings, as in [`Scanner::count_files`];
+/// unreadable entries are left out.
+pub fn summarize_by_depth(scanner: &Scanner, depth: usize) -> Result<Vec<(String, u64)>> {
+    // Keyed by the `/`-joined path below the root ("" for the root), so a
+    // directory's key is allocated once, when it's first seen.
+    let mut sizes: HashMap<String, u64> = HashMap::new();
+    sizes.insert(String::new(), 0);
+    let mut relative = String::new();
+    for entry in scanner.listing_walker()?.into_iter().flatten() {
+        let path = entry.path();
+        let Ok(components) = path.strip_prefix(&scanner.root) else {
+            continue;
+        };
+        relative.clear();
+        for component in components.components() {
+            if !relative.is_empty() {
+                relative.push('/');
+            }
+            relative.push_str(&component.as_os_str().to_string_lossy());
+        }
+        if entry.file_type().is_dir() {
+            // Listed even when empty, like du.
+            if components.components().count() <= depth && !sizes.contains_key(&relative) {
+                sizes.insert(relative.clone(), 0);
+            }
+            continue;
+        }
+        if !entry.file_type().is_file() || !scanner.is_included(&entry.file_name) {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        // Every directory above the file, from the root down, capped at depth.
+        let parents =
+            std::iter::once(0).chain(relative.match_indices('/').map(|(end, _)| end).take(depth));
+        for end in parents {
+            let dir = &relative[..end];
+            match sizes.get_mut(dir) {
+                Some(size) => *size += meta.len(),
+                None => {
+                    sizes.insert(dir.to_string(), meta.len());
+                }
+            }
+        }
+    }
+
+    let mut dirs: Vec<(String, u64)> = sizes
+        .iter()
+        .map(|(dir, size)| (dir.clone(), *size))
+        .collect();
+    dirs.sort_by(|(a, _), (b, _)| tree_order(a, b, &sizes));
+    for (dir, _) in &mut dirs {
+        if dir.is_empty() {
+            *dir = ROOT_LABEL.to_string();
+        }
+    }
+    Ok(dirs)
+}
+
+/// Parents before their children; siblings, with everything below them,
+/// largest first and then by name.
+fn tree_order(a: &str, b: &str, sizes: &HashMap<String, u64>) -> Ordering {
+    let shared = components(a)
+        .zip(components(b))
+        .take_while(|(x, y)| x == y)
+        .count();
+    match (components(a).nth(shared), components(b).nth(shared)) {
+        (Some(x), Some(y)) => {
+            let size = |dir: &str| sizes.get(prefix(dir, shared + 1)).copied().unwrap_or(0);
+            size(b).cmp(&size(a)).then_with(|| x.cmp(y))
+        }
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn components(dir: &str) -> impl Iterator<Item = &str> {
+    dir.split('/').filter(|c| !c.is_empty())
+}
+
+/// The first `n` (at least one) components of `dir`.
+fn prefix(dir: &str, n: usize) -> &str {
+    dir.match_indices('/')
+        .nth(n - 1)
+        .map_or(dir, |(end, _)| &dir[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_depth_one_rolls_up_subfolders() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("photos/2023")).unwrap();
+        fs::create_dir_all(root.join("photos/2024")).unwrap();
+        fs::create_dir_all(root.join("code/src")).unwrap();
+        fs::create_dir(root.join("empty")).unwrap();
+        fs::write(root.join("notes.txt"), vec![0u8; 10]).unwrap();
+        fs::write(root.join("photos/cover.jpg"), vec![0u8; 100]).unwrap();
+        fs::write(root.join("photos/2023/a.jpg"), vec![0u8; 1_000]).unwrap();
+        fs::write(root.join("photos/2024/b.jpg"), vec![0u8; 2_000]).unwrap();
+        fs::write(root.join("code/src/main.rs"), vec![0u8; 500]).unwrap();
+
+        let scanner = Scanner::new(root, 10);
+        assert_eq!(
+            summarize_by_depth(&scanner, 1).unwrap(),
+            [
+                (".".to_string(), 3_610),
+                ("photos".to_string(), 3_100),
+                ("code".to_string(), 500),
+                ("empty".to_string(), 0),
+            ]
+        );
+
+        // One level deeper, each folder is followed by its own subfolders.
+        let labels: Vec<String> = summarize_by_depth(&scanner, 2)
+            .unwrap()
+            .into_iter()
+            .map(|(label, _)| label)
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                ".",
+                "photos",
+                "photos/2024",
+                "photos/2023",
+                "code",
+                "code/src",
+                "empty"
+            ]
+        );
+        assert_eq!(
+            summarize_by_depth(&scanner, 0).unwrap(),
+            [(".".to_string(), 3_610)]
+        );
+
+        // Exclusions apply as in the scan.
+        let without_photos = Scanner::new(root, 10).exclude(["photos"]);
+        assert_eq!(
+            summarize_by_depth(&without_photos, 1).unwrap(),
+            [
+                (".".to_string(), 510),
+                ("code".to_string(), 500),
+                ("empty".to_string(), 0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! Coarse file categories (`images`, `video`, `code`, ...) keyed off the
+//! extension, for dashboards where dozens of raw extensions are too noisy.
+
+use crate::ExtensionStat;
+use std::collections::HashMap;
+
+/// Category for extensions that aren't in the map.
+pub const OTHER_CATEGORY: &str = "other";
+
+const CATEGORIES: &[(&str, &[&str])] = &[
+    (
+        "images",
+        &[
+            "jpg", "jpeg", "png", "gif", "bmp", "tif", "tiff", "webp", "heic", "svg", "ico", "raw",
+            "cr2", "nef", "psd",
+        ],
+    ),
+    (
+        "video",
+        &[
+            "mp4", "mkv", "mov", "avi", "wmv", "webm", "m4v", "mpg", "mpeg", "flv",
+        ],
+    ),
+    (
+        "audio",
+        &["mp3", "wav", "flac", "aac", "ogg", "m4a", "wma", "opus"],
+    ),
+    (
+        "documents",
+        &[
+            "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "odt", "ods", "odp", "rtf", "txt",
+            "md",
+        ],
+    ),
+    (
+        "archives",
+        &[
+            "zip", "tar", "gz", "tgz", "bz2", "xz", "zst", "7z", "rar", "iso", "dmg",
+        ],
+    ),
+    (
+        "code",
+        &[
+            "rs", "c", "h", "cpp", "hpp", "cc", "go", "py", "js", "ts", "tsx", "jsx", "java", "kt",
+            "cs", "rb", "php", "swift", "sh", "ps1", "html", "css", "scss", "vue",
+        ],
+    ),
+    (
+        "data",
+        &[
+            "json", "csv", "xml", "yaml", "yml", "toml", "parquet", "db", "sqlite", "sql", "log",
+        ],
+    ),
+    (
+        "binaries",
+        &[
+            "exe", "dll", "so", "dylib", "bin", "o", "a", "lib", "msi", "deb", "rpm", "class",
+            "jar",
+        ],
+    ),
+];
+
+/// The category an extension (without the dot, any case) belongs to, or
+/// [`OTHER_CATEGORY`].
+pub fn category_of(extension: &str) -> &'static str {
+    let extension = extension.to_ascii_lowercase();
+    CATEGORIES
+        .iter()
+        .find(|(_, extensions)| extensions.contains(&extension.as_str()))
+        .map_or(OTHER_CATEGORY, |(category, _)| category)
+}
+
+/// Sum per-extension stats into per-category stats. `avg_entropy` is not
+/// carried over.
+pub fn rollup(extensions: &HashMap<String, ExtensionStat>) -> HashMap<String, ExtensionStat> {
+    rollup_with(extensions, &HashMap::new())
+}
+
+/// [`rollup`] with site-specific categories: an extension that's a key of
+/// `overrides` (lowercase, without the dot) goes to the category it maps to
+/// instead of the built-in one.
+pub fn rollup_with(
+    extensions: &HashMap<String, ExtensionStat>,
+    overrides: &HashMap<String, String>,
+) -> HashMap<String, ExtensionStat> {
+    let mut categories: HashMap<String, ExtensionStat> = HashMap::new();
+    for (ext, stat) in extensions {
+        let category = overrides
+            .get(&ext.to_ascii_lowercase())
+            .map_or_else(|| category_of(ext), String::as_str);
+        let entry = categories.entry(category.to_string()).or_default();
+        entry.count += stat.count;
+        entry.size += stat.size;
+    }
+    categories
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rollup_groups_extensions() {
+        let stat = |count, size| ExtensionStat {
+            count,
+            size,
+            ..Default::default()
+        };
+        let extensions = HashMap::from([
+            ("JPG".to_string(), stat(2, 200)),
+            ("png".to_string(), stat(1, 50)),
+            ("mkv".to_string(), stat(1, 5000)),
+            ("xyz".to_string(), stat(3, 3)),
+        ]);
+
+        let categories = rollup(&extensions);
+        assert_eq!(categories["images"].count, 3);
+        assert_eq!(categories["images"].size, 250);
+        assert_eq!(categories["video"].size, 5000);
+        assert_eq!(categories[OTHER_CATEGORY].count, 3);
+        assert_eq!(category_of("rs"), "code");
+
+        let overrides = HashMap::from([
+            ("png".to_string(), "diagrams".to_string()),
+            ("xyz".to_string(), "models".to_string()),
+        ]);
+        let custom = rollup_with(&extensions, &overrides);
+        assert_eq!(custom["images"].count, 2);
+        assert_eq!(custom["diagrams"].size, 50);
+        assert_eq!(custom["models"].count, 3);
+        assert!(!custom.contains_key(OTHER_CATEGORY));
+    }
+}
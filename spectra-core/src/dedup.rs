@@ -0,0 +1,279 @@
+//! Byte-for-byte duplicate detection.
+//!
+//! Files are bucketed by size first, so only files that share a size are
+//! ever read. Within a bucket an optional prehash of the first
+//! [`PREHASH_BYTES`] splits off files that differ early, and the survivors
+//! get a full BLAKE3 hash. Hashing runs on the walker's rayon pool.
+//!
+//! Hard links to the same file are counted once: they share storage, so
+//! deleting one reclaims nothing.
+
+use crate::hash::{hash_file, hash_reader};
+use crate::report::file_identity;
+use jwalk::rayon::prelude::*;
+use jwalk::WalkDir;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Bytes read for the prehash.
+pub const PREHASH_BYTES: u64 = 4096;
+
+#[derive(Debug, Clone)]
+pub struct DedupConfig {
+    /// Ignore files smaller than this. Defaults to 1 so empty files, which
+    /// are all trivially identical, aren't reported.
+    pub min_size_bytes: u64,
+    /// Compare the first [`PREHASH_BYTES`] before hashing whole files.
+    pub prehash: bool,
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        Self {
+            min_size_bytes: 1,
+            prehash: true,
+        }
+    }
+}
+
+/// Files with identical contents.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    /// BLAKE3 hash of the contents, lowercase hex.
+    pub hash: String,
+    /// Size of each copy.
+    pub size_bytes: u64,
+    /// Every copy, sorted.
+    pub paths: Vec<String>,
+}
+
+impl DuplicateGroup {
+    /// Space freed by keeping one copy: `size * (copies - 1)`.
+    pub fn reclaimable_bytes(&self) -> u64 {
+        self.size_bytes * (self.paths.len() as u64).saturating_sub(1)
+    }
+
+    /// The copies that would be removed: every path but the first.
+    pub fn redundant_paths(&self) -> &[String] {
+        self.paths.get(1..).unwrap_or_default()
+    }
+}
+
+/// Total space freed by keeping one copy of every group.
+pub fn reclaimable_bytes(groups: &[DuplicateGroup]) -> u64 {
+    groups.iter().map(DuplicateGroup::reclaimable_bytes).sum()
+}
+
+/// What removing the redundant copy of every duplicate would free, for
+/// `--dedup-report`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DedupSavings {
+    /// Same as [`reclaimable_bytes`].
+    pub reclaimable_bytes: u64,
+    pub group_count: usize,
+    /// Copies that would be removed (one per group is kept).
+    pub redundant_files: u64,
+    /// Reclaimable bytes per lowercase extension (`""` for none).
+    pub by_extension: BTreeMap<String, u64>,
+    /// Reclaimable bytes per directory holding a redundant copy.
+    pub by_directory: BTreeMap<String, u64>,
+}
+
+/// Break down the space freed by keeping the first path of each group and
+/// removing the rest (see [`DuplicateGroup::redundant_paths`]).
+pub fn savings(groups: &[DuplicateGroup]) -> DedupSavings {
+    let mut savings = DedupSavings {
+        reclaimable_bytes: reclaimable_bytes(groups),
+        group_count: groups.len(),
+        ..Default::default()
+    };
+    for group in groups {
+        for path in group.redundant_paths() {
+            let path = Path::new(path);
+            let ext = path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            let dir = path
+                .parent()
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default();
+            *savings.by_extension.entry(ext).or_default() += group.size_bytes;
+            *savings.by_directory.entry(dir).or_default() += group.size_bytes;
+            savings.redundant_files += 1;
+        }
+    }
+    savings
+}
+
+/// Every set of two or more identical files under `root`, largest
+/// reclaimable space first. Files that can't be read are left out.
+pub fn find_duplicates(root: &Path, config: &DedupConfig) -> Vec<DuplicateGroup> {
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for entry in WalkDir::new(root).into_iter().flatten() {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.len() >= config.min_size_bytes {
+            by_size.entry(meta.len()).or_default().push(entry.path());
+        }
+    }
+
+    let candidates: Vec<(u64, Vec<PathBuf>)> = by_size
+        .into_iter()
+        .map(|(size, paths)| (size, distinct_files(paths)))
+        .filter(|(_, paths)| paths.len() > 1)
+        .collect();
+
+    let mut groups: Vec<DuplicateGroup> = candidates
+        .into_par_iter()
+        .flat_map_iter(|(size, paths)| {
+            let buckets = if config.prehash && size > PREHASH_BYTES {
+                split_by(paths, prehash)
+                    .into_iter()
+                    .map(|(_, b)| b)
+                    .collect()
+            } else {
+                vec![paths]
+            };
+            buckets
+                .into_iter()
+                .flat_map(|paths| split_by(paths, hash_file))
+                .map(move |(hash, paths)| {
+                    let mut paths: Vec<String> =
+                        paths.iter().map(|p| p.display().to_string()).collect();
+                    paths.sort();
+                    DuplicateGroup {
+                        hash,
+                        size_bytes: size,
+                        paths,
+                    }
+                })
+        })
+        .collect();
+
+    groups.sort_by(|a, b| {
+        b.reclaimable_bytes()
+            .cmp(&a.reclaimable_bytes())
+            .then_with(|| a.paths.cmp(&b.paths))
+    });
+    groups
+}
+
+/// Drop extra hard links so each underlying file appears once.
+fn distinct_files(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    if paths.len() < 2 {
+        return paths;
+    }
+    let mut seen = HashSet::new();
+    paths
+        .into_iter()
+        .filter(|p| file_identity(p).is_none_or(|id| seen.insert(id)))
+        .collect()
+}
+
+/// Partition `paths` by `key`, keeping only partitions with two or more
+/// members. Paths whose key can't be computed are dropped.
+fn split_by(
+    paths: Vec<PathBuf>,
+    key: impl Fn(&Path) -> io::Result<String>,
+) -> Vec<(String, Vec<PathBuf>)> {
+    let mut buckets: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for path in paths {
+        if let Ok(k) = key(&path) {
+            buckets.entry(k).or_default().push(path);
+        }
+    }
+    buckets.into_iter().filter(|(_, b)| b.len() > 1).collect()
+}
+
+fn prehash(path: &Path) -> io::Result<String> {
+    hash_reader(File::open(path)?.take(PREHASH_BYTES))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_identical_files_grouped_unique_file_ignored() {
+        let dir = tempdir().unwrap();
+        let content = vec![7u8; 10_000];
+        fs::write(dir.path().join("a.bin"), &content).unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub/b.bin"), &content).unwrap();
+        // Same size and same first 4KB, different tail.
+        let mut unique = content.clone();
+        unique[9_999] = 8;
+        fs::write(dir.path().join("c.bin"), &unique).unwrap();
+
+        for prehash in [true, false] {
+            let config = DedupConfig {
+                prehash,
+                ..Default::default()
+            };
+            let groups = find_duplicates(dir.path(), &config);
+            assert_eq!(groups.len(), 1);
+            let group = &groups[0];
+            assert_eq!(group.size_bytes, 10_000);
+            assert_eq!(group.hash, hash_file(&dir.path().join("a.bin")).unwrap());
+            assert_eq!(group.paths.len(), 2);
+            assert!(group.paths[0].ends_with("a.bin"));
+            assert!(group.paths[1].ends_with("b.bin"));
+            assert_eq!(reclaimable_bytes(&groups), 10_000);
+        }
+    }
+
+    #[test]
+    fn test_savings_sum_redundant_copies_across_groups() {
+        let groups = [
+            DuplicateGroup {
+                hash: "a".repeat(64),
+                size_bytes: 1_000,
+                paths: vec![
+                    "/data/a/photo.JPG".to_string(),
+                    "/data/b/photo.jpg".to_string(),
+                    "/data/c/photo.jpg".to_string(),
+                ],
+            },
+            DuplicateGroup {
+                hash: "b".repeat(64),
+                size_bytes: 250,
+                paths: vec!["/data/a/notes".to_string(), "/data/b/notes".to_string()],
+            },
+        ];
+
+        let savings = savings(&groups);
+        // (count - 1) * size per group.
+        assert_eq!(savings.reclaimable_bytes, 2 * 1_000 + 250);
+        assert_eq!(savings.group_count, 2);
+        assert_eq!(savings.redundant_files, 3);
+        assert_eq!(savings.by_extension["jpg"], 2_000);
+        assert_eq!(savings.by_extension[""], 250);
+        assert_eq!(savings.by_directory["/data/b"], 1_250);
+        assert_eq!(savings.by_directory["/data/c"], 1_000);
+        assert!(!savings.by_directory.contains_key("/data/a"));
+        assert_eq!(
+            savings.by_directory.values().sum::<u64>(),
+            savings.reclaimable_bytes
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hard_links_are_not_duplicates() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "same contents").unwrap();
+        fs::hard_link(dir.path().join("a.txt"), dir.path().join("b.txt")).unwrap();
+
+        assert!(find_duplicates(dir.path(), &DedupConfig::default()).is_empty());
+    }
+}
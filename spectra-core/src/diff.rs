@@ -0,0 +1,135 @@
+//! Comparison of two scans of the same tree.
+
+use crate::{CaseSensitivity, ExtensionStat, ScanStats};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How one extension changed between two scans.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtensionDelta {
+    pub extension: String,
+    pub count_delta: i64,
+    pub size_delta: i64,
+}
+
+/// The difference between an older and a newer scan.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanDiff {
+    pub total_files_delta: i64,
+    pub total_folders_delta: i64,
+    pub total_size_delta: i64,
+    /// Changed extensions, largest absolute size change first.
+    pub extensions: Vec<ExtensionDelta>,
+}
+
+impl ScanStats {
+    /// Compare this (older) scan with `newer`. Extensions whose count and size
+    /// are both unchanged are omitted.
+    ///
+    /// Extension keys are compared case-insensitively (`JPG` and `jpg` are
+    /// one extension, reported lowercase), so scans taken with different
+    /// case-sensitivity settings don't show spurious deltas. Use
+    /// [`diff_with_case`](Self::diff_with_case) to compare keys exactly.
+    pub fn diff(&self, newer: &ScanStats) -> ScanDiff {
+        self.diff_with_case(newer, CaseSensitivity::Insensitive)
+    }
+
+    /// [`diff`](Self::diff) with extension keys canonicalized by `case`
+    /// before comparing; stats of keys that collapse together are summed.
+    pub fn diff_with_case(&self, newer: &ScanStats, case: CaseSensitivity) -> ScanDiff {
+        let old_exts = canonical_extensions(&self.extensions, case);
+        let new_exts = canonical_extensions(&newer.extensions, case);
+        let mut keys: Vec<&String> = old_exts.keys().collect();
+        keys.extend(new_exts.keys());
+        keys.sort();
+        keys.dedup();
+
+        let mut extensions: Vec<ExtensionDelta> = keys
+            .into_iter()
+            .map(|ext| {
+                let old = old_exts.get(ext).cloned().unwrap_or_default();
+                let new = new_exts.get(ext).cloned().unwrap_or_default();
+                ExtensionDelta {
+                    extension: ext.clone(),
+                    count_delta: delta(old.count, new.count),
+                    size_delta: delta(old.size, new.size),
+                }
+            })
+            .filter(|d| d.count_delta != 0 || d.size_delta != 0)
+            .collect();
+        extensions.sort_by_key(|d| std::cmp::Reverse(d.size_delta.unsigned_abs()));
+
+        ScanDiff {
+            total_files_delta: delta(self.total_files, newer.total_files),
+            total_folders_delta: delta(self.total_folders, newer.total_folders),
+            total_size_delta: delta(self.total_size_bytes, newer.total_size_bytes),
+            extensions,
+        }
+    }
+}
+
+fn canonical_extensions(
+    extensions: &HashMap<String, ExtensionStat>,
+    case: CaseSensitivity,
+) -> HashMap<String, ExtensionStat> {
+    let mut canonical: HashMap<String, ExtensionStat> = HashMap::new();
+    for (ext, stat) in extensions {
+        let entry = canonical
+            .entry(case.normalize(ext).into_owned())
+            .or_default();
+        entry.count += stat.count;
+        entry.size += stat.size;
+    }
+    canonical
+}
+
+fn delta(old: u64, new: u64) -> i64 {
+    new as i64 - old as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::stats_with_extensions as stats;
+
+    #[test]
+    fn test_diff_orders_by_absolute_size_change() {
+        let old = stats(&[("jpg", 10, 1000), ("log", 5, 800), ("txt", 2, 20)]);
+        let new = stats(&[
+            ("jpg", 12, 3000),
+            ("log", 1, 100),
+            ("txt", 2, 20),
+            ("rs", 1, 5),
+        ]);
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.total_size_delta, 2000 - 700 + 5);
+        let order: Vec<&str> = diff
+            .extensions
+            .iter()
+            .map(|d| d.extension.as_str())
+            .collect();
+        assert_eq!(order, vec!["jpg", "log", "rs"]);
+        assert_eq!(diff.extensions[1].size_delta, -700);
+        assert_eq!(diff.extensions[1].count_delta, -4);
+    }
+
+    #[test]
+    fn test_extension_case_normalized_before_comparing() {
+        // Case-sensitive scan kept both spellings; the later scan lowercased.
+        let old = stats(&[("JPG", 3, 300), ("jpg", 2, 200), ("Log", 1, 10)]);
+        let new = stats(&[("jpg", 5, 500), ("log", 1, 10)]);
+
+        let diff = old.diff(&new);
+        assert!(diff.extensions.is_empty(), "{:?}", diff.extensions);
+        assert_eq!(diff.total_size_delta, 0);
+
+        let exact = old.diff_with_case(&new, CaseSensitivity::Sensitive);
+        let changed: Vec<&str> = exact
+            .extensions
+            .iter()
+            .map(|d| d.extension.as_str())
+            .collect();
+        assert_eq!(changed, ["JPG", "jpg", "Log", "log"]);
+    }
+}
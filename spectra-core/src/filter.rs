@@ -0,0 +1,286 @@
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// How path and extension comparisons treat letter case.
+///
+/// The default follows the platform's filesystem convention: case-insensitive
+/// on Windows and macOS, case-sensitive everywhere else. `Sensitive` and
+/// `Insensitive` override that explicitly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaseSensitivity {
+    #[default]
+    Platform,
+    Sensitive,
+    Insensitive,
+}
+
+impl CaseSensitivity {
+    /// Resolve `Platform` to the convention of the current OS.
+    pub fn is_insensitive(self) -> bool {
+        match self {
+            CaseSensitivity::Platform => cfg!(any(windows, target_os = "macos")),
+            CaseSensitivity::Sensitive => false,
+            CaseSensitivity::Insensitive => true,
+        }
+    }
+
+    /// Fold `s` into its comparison key (lowercased when insensitive).
+    pub fn normalize<'a>(self, s: &'a str) -> Cow<'a, str> {
+        if self.is_insensitive() {
+            Cow::Owned(s.to_lowercase())
+        } else {
+            Cow::Borrowed(s)
+        }
+    }
+
+    /// Compare two names under this mode.
+    pub fn matches(self, a: &str, b: &str) -> bool {
+        self.normalize(a) == self.normalize(b)
+    }
+}
+
+impl FromStr for CaseSensitivity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "platform" => Ok(CaseSensitivity::Platform),
+            "sensitive" => Ok(CaseSensitivity::Sensitive),
+            "insensitive" => Ok(CaseSensitivity::Insensitive),
+            other => Err(format!(
+                "unknown case sensitivity '{}' (expected platform, sensitive or insensitive)",
+                other
+            )),
+        }
+    }
+}
+
+/// A set of subtrees that the scanner must not descend into.
+///
+/// Exclusions may be given relative to the current directory or as absolute
+/// paths, and the scan root may be spelled differently again (`./data` vs
+/// `/home/user/data`). Both sides are canonicalized up front so that matching
+/// during the walk is a cheap component-wise prefix check.
+///
+/// Gitignore-style glob patterns can be added with
+/// [`with_globs`](Self::with_globs).
+#[derive(Debug, Clone, Default)]
+pub struct PathExclusions {
+    root: PathBuf,
+    canonical_root: Option<PathBuf>,
+    excluded: Vec<PathBuf>,
+    case: CaseSensitivity,
+    globs: Option<GlobExclusions>,
+}
+
+/// Compiled glob patterns, split by whether they only apply to directories.
+#[derive(Debug, Clone)]
+struct GlobExclusions {
+    any: GlobSet,
+    dirs_only: GlobSet,
+}
+
+impl PathExclusions {
+    pub fn new(root: &Path, excluded: &[PathBuf]) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            canonical_root: std::fs::canonicalize(root).ok(),
+            excluded: excluded.iter().map(|p| normalize(p)).collect(),
+            case: CaseSensitivity::default(),
+            globs: None,
+        }
+    }
+
+    /// Also exclude entries matching gitignore-style `patterns`, matched
+    /// against the path relative to the root:
+    ///
+    /// - a pattern without `/` matches a name at any depth (`node_modules`,
+    ///   `*.log`);
+    /// - a pattern containing `/` is anchored at the root (`build/cache`,
+    ///   `/target`);
+    /// - a trailing `/` matches directories only (`target/`).
+    ///
+    /// `*` doesn't cross `/`; `**` does. Call after
+    /// [`with_case_sensitivity`](Self::with_case_sensitivity) for the
+    /// patterns to follow it.
+    pub fn with_globs<S: AsRef<str>>(mut self, patterns: &[S]) -> Result<Self> {
+        if patterns.is_empty() {
+            return Ok(self);
+        }
+        let mut any = GlobSetBuilder::new();
+        let mut dirs_only = GlobSetBuilder::new();
+        for pattern in patterns {
+            let pattern = pattern.as_ref();
+            let (body, dir_only) = match pattern.strip_suffix('/') {
+                Some(body) => (body, true),
+                None => (pattern, false),
+            };
+            let anchored = match body.strip_prefix('/') {
+                Some(rest) => rest.to_string(),
+                None if body.contains('/') => body.to_string(),
+                None => format!("**/{}", body),
+            };
+            let glob = GlobBuilder::new(&anchored)
+                .literal_separator(true)
+                .case_insensitive(self.case.is_insensitive())
+                .build()
+                .with_context(|| format!("Invalid exclude pattern '{}'", pattern))?;
+            if dir_only {
+                dirs_only.add(glob);
+            } else {
+                any.add(glob);
+            }
+        }
+        self.globs = Some(GlobExclusions {
+            any: any.build()?,
+            dirs_only: dirs_only.build()?,
+        });
+        Ok(self)
+    }
+
+    /// Set how exclusion prefixes are compared against walked paths.
+    pub fn with_case_sensitivity(mut self, case: CaseSensitivity) -> Self {
+        self.case = case;
+        if case.is_insensitive() {
+            self.excluded = self.excluded.iter().map(|p| fold_case(p)).collect();
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.excluded.is_empty() && self.globs.is_none()
+    }
+
+    /// Whether a walked entry is excluded, by subtree or by glob. `is_dir`
+    /// selects whether directory-only patterns apply.
+    pub fn excludes(&self, path: &Path, is_dir: bool) -> bool {
+        if self.contains(path) {
+            return true;
+        }
+        let (Some(globs), Ok(relative)) = (&self.globs, path.strip_prefix(&self.root)) else {
+            return false;
+        };
+        if relative.as_os_str().is_empty() {
+            return false;
+        }
+        globs.any.is_match(relative) || (is_dir && globs.dirs_only.is_match(relative))
+    }
+
+    /// Returns true if `path` (as yielded by a walk of the root) lies inside
+    /// one of the excluded subtrees.
+    pub fn contains(&self, path: &Path) -> bool {
+        if self.excluded.is_empty() {
+            return false;
+        }
+        let resolved = match (&self.canonical_root, path.strip_prefix(&self.root)) {
+            (Some(canonical_root), Ok(rel)) => canonical_root.join(rel),
+            _ => normalize(path),
+        };
+        let resolved = if self.case.is_insensitive() {
+            fold_case(&resolved)
+        } else {
+            resolved
+        };
+        self.excluded.iter().any(|ex| resolved.starts_with(ex))
+    }
+}
+
+fn fold_case(path: &Path) -> PathBuf {
+    PathBuf::from(path.to_string_lossy().to_lowercase())
+}
+
+/// Canonicalize when the path exists, otherwise make it absolute so that
+/// prefix comparisons against canonical walk paths still line up.
+fn normalize(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_exclusion_matches_subtree_only() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("keep")).unwrap();
+        std::fs::create_dir_all(dir.path().join("skip/nested")).unwrap();
+
+        let exclusions = PathExclusions::new(dir.path(), &[dir.path().join("skip")]);
+
+        assert!(exclusions.contains(&dir.path().join("skip")));
+        assert!(exclusions.contains(&dir.path().join("skip/nested/file.txt")));
+        assert!(!exclusions.contains(&dir.path().join("keep")));
+        // Component-wise matching: a sibling sharing the prefix is not excluded.
+        assert!(!exclusions.contains(&dir.path().join("skipper")));
+    }
+
+    #[test]
+    fn test_glob_patterns_follow_gitignore_rules() {
+        let root = Path::new("/data");
+        let exclusions = PathExclusions::new(root, &[])
+            .with_case_sensitivity(CaseSensitivity::Sensitive)
+            .with_globs(&["node_modules", "*.log", "target/", "/build/cache"])
+            .unwrap();
+
+        assert!(exclusions.excludes(&root.join("node_modules"), true));
+        assert!(exclusions.excludes(&root.join("web/node_modules"), true));
+        assert!(exclusions.excludes(&root.join("logs/app.log"), false));
+        assert!(exclusions.excludes(&root.join("crate/target"), true));
+        assert!(!exclusions.excludes(&root.join("crate/target"), false));
+        assert!(exclusions.excludes(&root.join("build/cache"), true));
+        assert!(!exclusions.excludes(&root.join("src/build/cache"), true));
+        assert!(!exclusions.excludes(&root.join("src/main.rs"), false));
+        assert!(!exclusions.excludes(root, true));
+
+        assert!(PathExclusions::new(root, &[]).with_globs(&["a[b"]).is_err());
+    }
+
+    #[test]
+    fn test_explicit_case_overrides() {
+        assert!(CaseSensitivity::Insensitive.matches("JPG", "jpg"));
+        assert!(!CaseSensitivity::Sensitive.matches("JPG", "jpg"));
+        assert_eq!(
+            "Insensitive".parse::<CaseSensitivity>(),
+            Ok(CaseSensitivity::Insensitive)
+        );
+        assert!("loose".parse::<CaseSensitivity>().is_err());
+    }
+
+    #[test]
+    #[cfg(any(windows, target_os = "macos"))]
+    fn test_platform_default_is_case_insensitive() {
+        assert!(CaseSensitivity::default().is_insensitive());
+        assert!(CaseSensitivity::default().matches("photo.JPG", "photo.jpg"));
+    }
+
+    #[test]
+    #[cfg(not(any(windows, target_os = "macos")))]
+    fn test_platform_default_is_case_sensitive() {
+        assert!(!CaseSensitivity::default().is_insensitive());
+        assert!(!CaseSensitivity::default().matches("photo.JPG", "photo.jpg"));
+    }
+
+    #[test]
+    fn test_case_insensitive_exclusion() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("Cache")).unwrap();
+
+        let exclusions = PathExclusions::new(dir.path(), &[dir.path().join("Cache")])
+            .with_case_sensitivity(CaseSensitivity::Insensitive);
+        assert!(exclusions.contains(&dir.path().join("CACHE/file.bin")));
+
+        let exclusions = PathExclusions::new(dir.path(), &[dir.path().join("Cache")])
+            .with_case_sensitivity(CaseSensitivity::Sensitive);
+        assert!(!exclusions.contains(&dir.path().join("CACHE/file.bin")));
+    }
+}
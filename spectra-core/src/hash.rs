@@ -0,0 +1,167 @@
+use crate::analysis::entropy::EntropyProfile;
+use crate::CaseSensitivity;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Chunk size used when streaming file contents through the hasher.
+pub const HASH_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Computes the BLAKE3 content hash of a file, returned as lowercase hex.
+///
+/// The file is streamed in 64KB chunks so memory use is constant regardless
+/// of file size.
+pub fn hash_file(path: &Path) -> io::Result<String> {
+    hash_reader(File::open(path)?)
+}
+
+/// Computes the BLAKE3 hash of everything readable from `reader`.
+pub fn hash_reader<R: Read>(reader: R) -> io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    for_each_chunk(reader, |chunk| {
+        hasher.update(chunk);
+    })?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// BLAKE3 hash, whole-stream Shannon entropy and the block entropy profile
+/// (as [`entropy_profile`]) from a single read: each 64KB chunk feeds both
+/// the hasher and the frequency tables. Use this instead of [`hash_reader`]
+/// plus a separate entropy read when both are wanted. Note the entropy
+/// covers every byte, unlike the 8KB header sample of
+/// [`calculate_shannon_entropy`](crate::analysis::calculate_shannon_entropy).
+///
+/// [`entropy_profile`]: crate::analysis::entropy_profile
+pub fn analyze_stream<R: Read>(
+    reader: R,
+    block_size: usize,
+) -> io::Result<(String, f32, Vec<f32>)> {
+    let mut hasher = blake3::Hasher::new();
+    let mut profile = EntropyProfile::new(block_size);
+    for_each_chunk(reader, |chunk| {
+        hasher.update(chunk);
+        profile.update(chunk);
+    })?;
+    let (entropy, blocks) = profile.finish();
+    Ok((hasher.finalize().to_hex().to_string(), entropy, blocks))
+}
+
+/// [`analyze_stream`] over a file.
+pub fn analyze_file(path: &Path, block_size: usize) -> io::Result<(String, f32, Vec<f32>)> {
+    analyze_stream(File::open(path)?, block_size)
+}
+
+/// Stream `reader` through `f` in [`HASH_CHUNK_SIZE`] chunks.
+pub(crate) fn for_each_chunk<R: Read>(mut reader: R, mut f: impl FnMut(&[u8])) -> io::Result<()> {
+    let mut buffer = vec![0u8; HASH_CHUNK_SIZE];
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            return Ok(());
+        }
+        f(&buffer[..n]);
+    }
+}
+
+/// Limits content hashing to candidate files so a dedup pass only reads what
+/// it needs, e.g. `.zip` backups over 100MB. The default matches every file.
+#[derive(Debug, Clone, Default)]
+pub struct HashFilter {
+    extensions: Vec<String>,
+    min_size_bytes: u64,
+    case_sensitivity: CaseSensitivity,
+}
+
+impl HashFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only hash files with one of these extensions (no leading dot). An
+    /// empty list allows any extension.
+    pub fn with_extensions<S: Into<String>>(
+        mut self,
+        extensions: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.extensions.extend(
+            extensions
+                .into_iter()
+                .map(|e| e.into().trim_start_matches('.').to_string()),
+        );
+        self
+    }
+
+    /// Only hash files of at least `bytes`.
+    pub fn with_min_size(mut self, bytes: u64) -> Self {
+        self.min_size_bytes = bytes;
+        self
+    }
+
+    /// How extensions are compared; follows the platform convention by default.
+    pub fn with_case_sensitivity(mut self, case: CaseSensitivity) -> Self {
+        self.case_sensitivity = case;
+        self
+    }
+
+    pub fn matches(&self, path: &Path, size_bytes: u64) -> bool {
+        if size_bytes < self.min_size_bytes {
+            return false;
+        }
+        if self.extensions.is_empty() {
+            return true;
+        }
+        path.extension().is_some_and(|ext| {
+            let ext = ext.to_string_lossy();
+            self.extensions
+                .iter()
+                .any(|wanted| self.case_sensitivity.matches(&ext, wanted))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::ByteFrequencies;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_hash_file_matches_in_memory_hash() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        // Larger than one chunk to exercise the streaming loop.
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &data).unwrap();
+
+        assert_eq!(
+            hash_file(&path).unwrap(),
+            blake3::hash(&data).to_hex().to_string()
+        );
+    }
+
+    #[test]
+    fn test_analyze_stream_matches_standalone_hash_and_entropy() {
+        // Several chunks of text followed by a random-looking tail.
+        let mut data = b"The quick brown fox jumps over the lazy dog. ".repeat(4000);
+        data.extend((0..70_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8));
+
+        let (hash, entropy, blocks) = analyze_stream(data.as_slice(), 50_000).unwrap();
+        assert_eq!(hash, hash_reader(data.as_slice()).unwrap());
+        let mut standalone = ByteFrequencies::new();
+        standalone.update(&data);
+        assert_eq!(entropy, standalone.entropy());
+        assert_eq!(blocks.len(), data.len().div_ceil(50_000));
+        assert!(blocks[0] < blocks[blocks.len() - 1]);
+
+        // On anything within the 8KB header sample, the full-stream entropy
+        // equals the header-based file entropy.
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("small.txt");
+        std::fs::write(&path, &data[..4096]).unwrap();
+        let (_, small_entropy, _) = analyze_file(&path, 4096).unwrap();
+        assert_eq!(
+            small_entropy,
+            crate::analysis::calculate_shannon_entropy(&path).unwrap()
+        );
+    }
+}
@@ -5,20 +5,46 @@
 // This file is dual-licensed under the MIT and Apache 2.0 licenses.
 // See LICENSE-MIT and LICENSE-APACHE in the repository root for full license texts.
 
-use anyhow::Result;
-use jwalk::WalkDir;
-use serde::{Deserialize, Serialize};
+use anyhow::{Context, Result};
+use jwalk::WalkDirGeneric;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::time::{Duration, Instant};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 
+use backpressure::QueueGate;
+use profile::WorkerCounters;
+use report::{file_identity, FileIdentity};
+use sampling::EntryState;
+
+pub mod analysis;
+mod backpressure;
+pub mod breakdown;
 pub mod cache;
+pub mod category;
+pub mod dedup;
+pub mod diff;
+pub mod filter;
+pub mod hash;
 pub mod path_pool;
+pub mod percentiles;
+pub mod profile;
+pub mod report;
+pub mod sampling;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
 pub mod transport;
 
 pub use cache::ScanCache;
+pub use diff::{ExtensionDelta, ScanDiff};
+pub use filter::{CaseSensitivity, PathExclusions};
 pub use path_pool::PathPool;
+pub use percentiles::{SizeHistogram, SizePercentiles};
+pub use profile::{ScanProfile, WorkerProfile};
+pub use report::{AnalyzedFile, FileAnalysis, ScanReport};
 
 // --- Device-Aware I/O (#6) ---
 
@@ -90,20 +116,55 @@ pub fn recommended_threads(device: DeviceType) -> usize {
 // --- Progress Streaming (#1) ---
 
 /// Progress information emitted during scanning.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct ScanProgress {
     pub files_scanned: u64,
     pub folders_scanned: u64,
     pub bytes_scanned: u64,
+    /// Path of the entry processed just before this update; the scan root
+    /// for the final update.
+    #[serde(default)]
+    pub current_path: String,
 }
 
+/// MIME type for files whose leading bytes match no known signature.
+pub const UNKNOWN_MIME: &str = "application/octet-stream";
+
+/// Leading bytes read to sniff a file's MIME type.
+pub const MIME_SNIFF_BYTES: usize = 16;
+
+/// Default number of processed entries between progress updates.
+pub const DEFAULT_PROGRESS_INTERVAL: u64 = 1000;
+
 // --- Data Models ---
 
 /// Represents a file on disk, sortable by size for "Top N" calculations.
-#[derive(Debug, Eq, PartialEq, Serialize, Deserialize, Clone)]
+#[derive(Debug, Default, Eq, PartialEq, Serialize, Deserialize, Clone)]
 pub struct FileRecord {
     pub path: String,
     pub size_bytes: u64,
+    /// Bytes actually allocated on disk, when the scan was built with
+    /// `Scanner::with_allocated_size(true)` on a platform that reports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allocated_bytes: Option<u64>,
+    /// A directory ranked by rolled-up size (see
+    /// `Scanner::with_directories_in_top`) or a rolled-up `.git` directory.
+    #[serde(default)]
+    pub is_dir: bool,
+}
+
+impl FileRecord {
+    /// Allocated over logical size: below 1 for sparse (or compressed)
+    /// files, above 1 for block slack. `None` without allocation data or for
+    /// empty files.
+    pub fn allocation_ratio(&self) -> Option<f64> {
+        allocation_ratio(self.allocated_bytes?, self.size_bytes)
+    }
+}
+
+/// `allocated / logical`, or `None` when `logical` is zero.
+pub fn allocation_ratio(allocated: u64, logical: u64) -> Option<f64> {
+    (logical > 0).then(|| allocated as f64 / logical as f64)
 }
 
 // Reverse ordering for MinHeap (to keep largest items)
@@ -119,27 +180,442 @@ impl PartialOrd for FileRecord {
     }
 }
 
+/// A directory with the size and file count of everything below it.
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize, Clone)]
+pub struct DirRecord {
+    pub path: String,
+    pub total_size_bytes: u64,
+    pub file_count: u64,
+}
+
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct ExtensionStat {
     pub count: u64,
     pub size: u64,
+    /// Mean Shannon entropy over a bounded sample of this extension's files.
+    /// Only populated when analysis is enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_entropy: Option<f32>,
+}
+
+/// Read a `u128` written as a plain number. `ScanReport` flattens
+/// `ScanStats`, and serde can't buffer `u128` values for flattened fields.
+fn u128_from_u64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+    u64::deserialize(deserializer).map(u128::from)
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct ScanStats {
     pub root_path: String,
+    /// Free-form tag for organizing saved reports (`prod-nfs-weekly`), set
+    /// by the caller; the scanner never fills it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
     pub total_files: u64,
+    /// Directories seen by the scan. Includes the scan root itself unless
+    /// the scan was built with `Scanner::with_root_counted(false)`, so an
+    /// empty directory reports 1 by default.
     pub total_folders: u64,
     pub total_size_bytes: u64,
+    /// On-disk bytes allocated to the counted files; see
+    /// `Scanner::with_allocated_size`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_allocated_bytes: Option<u64>,
+    #[serde(deserialize_with = "u128_from_u64")]
     pub scan_duration_ms: u128,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub extensions: HashMap<String, ExtensionStat>,
+    /// Files grouped by MIME type sniffed from their first bytes, filled
+    /// only when the scan was built with `Scanner::with_mime_detection`.
+    /// Unrecognized content is counted as [`UNKNOWN_MIME`].
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub mime_types: HashMap<String, ExtensionStat>,
+    /// Up to [`Scanner::with_extension_samples`] paths per extension (same
+    /// keys as `extensions`), picked during the walk so analysis can read a
+    /// spread of files without walking the tree again. Any prefix of a list
+    /// is itself a sample. Not serialized.
+    #[serde(skip)]
+    pub extension_samples: HashMap<String, Vec<String>>,
+    /// The per-extension cap `extension_samples` was drawn with, so merged
+    /// samples are cut back to it. Not serialized.
+    #[serde(skip)]
+    pub extension_sample_limit: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub top_files: Vec<FileRecord>,
+    /// Largest directories below the root by recursive size, largest first,
+    /// bounded by the same limit as `top_files`.
+    #[serde(default)]
+    pub top_dirs: Vec<DirRecord>,
     /// Device type detected for the scanned path.
     #[serde(default)]
     pub device_type: Option<DeviceType>,
     /// Number of threads used for this scan.
     #[serde(default)]
     pub threads_used: Option<usize>,
+    /// Directories that were sampled rather than scanned in full. When
+    /// non-zero, `total_size_bytes` and extension sizes are estimates.
+    #[serde(default)]
+    pub sampled_directories: u64,
+    /// Wall-clock time the scan started, in seconds since the Unix epoch.
+    /// Zero for reports saved before this field existed.
+    #[serde(default)]
+    pub scanned_at: i64,
+    /// Symbolic links whose target does not exist. Counted separately rather
+    /// than dropped as read errors.
+    #[serde(default)]
+    pub broken_symlinks: u64,
+    /// Paths of the broken links, collected only when the scan was built with
+    /// `Scanner::with_broken_symlink_list(true)`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub broken_symlink_paths: Vec<String>,
+    /// The scan stopped early (see `Scanner::with_time_budget`); totals
+    /// cover only the part of the tree walked before it did.
+    #[serde(default)]
+    pub partial: bool,
+    /// The scan was stopped through `Scanner::scan_cancellable`; totals
+    /// cover only what was aggregated before the flag was seen.
+    #[serde(default)]
+    pub cancelled: bool,
+    /// Per-worker counts and timings, present when the scan was built with
+    /// `Scanner::with_profile(true)`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<ScanProfile>,
+    /// Entries that couldn't be read (permission denied, vanished mid-scan).
+    /// Their contents are missing from every total, so a non-empty list
+    /// means coverage was incomplete.
+    #[serde(default)]
+    pub errors: Vec<ScanError>,
+    /// Approximate file-size distribution (see [`percentiles`]). Sampled
+    /// files count with their weight.
+    #[serde(default)]
+    pub size_percentiles: SizePercentiles,
+    /// The sketch behind `size_percentiles`, kept so [`merge`](Self::merge)
+    /// can combine distributions. Not serialized: a loaded report has only
+    /// the percentiles.
+    #[serde(skip)]
+    pub size_histogram: SizeHistogram,
+}
+
+/// A path the scan had to skip.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ScanError {
+    pub path: String,
+    pub message: String,
+}
+
+impl ScanError {
+    /// `path` is used when the walk error doesn't carry one.
+    fn from_walk(err: &jwalk::Error, path: Option<&Path>) -> Self {
+        Self {
+            path: err
+                .path()
+                .or(path)
+                .map(|p| p.display().to_string())
+                .unwrap_or_default(),
+            message: err
+                .io_error()
+                .map_or_else(|| err.to_string(), |io| io.to_string()),
+        }
+    }
+}
+
+/// Walked entries between clock checks under `Scanner::with_time_budget`.
+pub const TIME_BUDGET_CHECK_INTERVAL: u64 = 64;
+
+/// Inode count above which a low average file size is worth flagging.
+pub const SMALL_FILES_MIN_INODES: u64 = 100_000;
+/// Average bytes per file below which a large tree counts as "many small files".
+pub const SMALL_FILES_AVG_BYTES: u64 = 64 * 1024;
+
+impl ScanStats {
+    /// Files plus folders seen by the scan: the figure backup tools budget
+    /// against. Special files (sockets, FIFOs, devices) are not counted.
+    pub fn total_inodes(&self) -> u64 {
+        self.total_files + self.total_folders
+    }
+
+    /// `total_allocated_bytes / total_size_bytes`; see
+    /// [`FileRecord::allocation_ratio`].
+    pub fn allocation_ratio(&self) -> Option<f64> {
+        allocation_ratio(self.total_allocated_bytes?, self.total_size_bytes)
+    }
+
+    /// `size` as a percentage of `total_size_bytes`, e.g. an extension's
+    /// share of the scan. Zero for an empty scan.
+    pub fn percent_of_total(&self, size: u64) -> f64 {
+        if self.total_size_bytes == 0 {
+            return 0.0;
+        }
+        size as f64 * 100.0 / self.total_size_bytes as f64
+    }
+
+    /// Whether this report was tagged `label`.
+    pub fn has_label(&self, label: &str) -> bool {
+        self.label.as_deref() == Some(label)
+    }
+
+    /// Whether the tree holds many inodes relative to its size. Per-file
+    /// overhead dominates backups of such trees.
+    pub fn has_many_small_files(&self) -> bool {
+        self.total_inodes() >= SMALL_FILES_MIN_INODES
+            && self.total_size_bytes / self.total_files.max(1) < SMALL_FILES_AVG_BYTES
+    }
+
+    /// Fold another scan's results into this one.
+    ///
+    /// Used to aggregate several roots into a single report. Totals and
+    /// extension stats are summed, and the two top-file (and top-directory)
+    /// lists are combined and re-trimmed to `top_limit`. A label shared by
+    /// both (or set on only one) is kept; conflicting labels are dropped.
+    /// `root_path` is left for the caller to set.
+    pub fn merge(&mut self, other: ScanStats, top_limit: usize) {
+        self.label = match (self.label.take(), other.label) {
+            (Some(mine), Some(theirs)) if mine != theirs => None,
+            (mine, theirs) => mine.or(theirs),
+        };
+        self.total_files += other.total_files;
+        self.total_folders += other.total_folders;
+        self.total_size_bytes += other.total_size_bytes;
+        self.total_allocated_bytes = match (self.total_allocated_bytes, other.total_allocated_bytes)
+        {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+        self.scan_duration_ms += other.scan_duration_ms;
+
+        for (ext, stat) in other.extensions {
+            let entry = self.extensions.entry(ext).or_default();
+            entry.count += stat.count;
+            entry.size += stat.size;
+        }
+        self.extension_sample_limit = self
+            .extension_sample_limit
+            .max(other.extension_sample_limit);
+        sampling::merge_samples(
+            &mut self.extension_samples,
+            other.extension_samples,
+            self.extension_sample_limit,
+        );
+        for (mime, stat) in other.mime_types {
+            let entry = self.mime_types.entry(mime).or_default();
+            entry.count += stat.count;
+            entry.size += stat.size;
+        }
+
+        self.top_files.extend(other.top_files);
+        self.top_files
+            .sort_by_key(|f| std::cmp::Reverse(f.size_bytes));
+        self.top_files.truncate(top_limit);
+        self.top_dirs.extend(other.top_dirs);
+        self.top_dirs
+            .sort_by_key(|d| std::cmp::Reverse(d.total_size_bytes));
+        self.top_dirs.truncate(top_limit);
+
+        if self.device_type != other.device_type {
+            self.device_type = None;
+        }
+        self.threads_used = self.threads_used.max(other.threads_used);
+        self.broken_symlinks += other.broken_symlinks;
+        self.broken_symlink_paths.extend(other.broken_symlink_paths);
+        // Keep the earliest known start.
+        self.scanned_at = match (self.scanned_at, other.scanned_at) {
+            (0, t) | (t, 0) => t,
+            (a, b) => a.min(b),
+        };
+        self.sampled_directories += other.sampled_directories;
+        self.partial |= other.partial;
+        self.cancelled |= other.cancelled;
+        self.errors.extend(other.errors);
+        self.size_histogram.merge(&other.size_histogram);
+        if self.size_histogram.is_empty() {
+            // Loaded reports carry no sketch; only the maximum combines.
+            self.size_percentiles.max = self.size_percentiles.max.max(other.size_percentiles.max);
+        } else {
+            self.size_percentiles = self.size_histogram.percentiles();
+        }
+        self.profile = match (self.profile.take(), other.profile) {
+            (Some(mut mine), Some(theirs)) => {
+                mine.merge(theirs);
+                Some(mine)
+            }
+            (mine, theirs) => mine.or(theirs),
+        };
+    }
+}
+
+/// Directories entered so far under [`SymlinkMode::Follow`], by identity.
+struct VisitedDirs(Mutex<HashSet<FileIdentity>>);
+
+impl VisitedDirs {
+    fn new(root: &Path) -> Self {
+        Self(Mutex::new(file_identity(root).into_iter().collect()))
+    }
+
+    /// Keep directories that were already entered (through a link or
+    /// directly) from being read again.
+    fn prune_revisits<C: jwalk::ClientState>(
+        &self,
+        children: &mut [jwalk::Result<jwalk::DirEntry<C>>],
+    ) {
+        for entry in children.iter_mut().flatten() {
+            if !entry.file_type().is_dir() || entry.read_children_path.is_none() {
+                continue;
+            }
+            let Some(id) = file_identity(&entry.path()) else {
+                continue;
+            };
+            let mut visited = self.0.lock().unwrap_or_else(|e| e.into_inner());
+            if !visited.insert(id) {
+                entry.read_children_path = None;
+            }
+        }
+    }
+}
+
+/// Drop excluded subtrees and, when `.git` rollup re-enables hidden entries,
+/// every hidden entry other than `.git`; then, when following links, stop
+/// directories from being entered twice. `depth` is `None` for the call that
+/// yields the root itself, which is never filtered.
+fn prune_children<C: jwalk::ClientState>(
+    children: &mut Vec<jwalk::Result<jwalk::DirEntry<C>>>,
+    depth: Option<usize>,
+    git_rollup: bool,
+    exclusions: &PathExclusions,
+    visited: Option<&VisitedDirs>,
+) {
+    if git_rollup && depth.is_some() {
+        children.retain(|child| match child {
+            Ok(entry) => {
+                let name = entry.file_name.to_string_lossy();
+                !name.starts_with('.') || name == ".git"
+            }
+            Err(_) => true,
+        });
+    }
+    if !exclusions.is_empty() {
+        children.retain(|child| match child {
+            Ok(entry) => !exclusions.excludes(&entry.path(), entry.file_type().is_dir()),
+            Err(_) => true,
+        });
+    }
+    if let (Some(visited), Some(_)) = (visited, depth) {
+        visited.prune_revisits(children);
+    }
+}
+
+/// The outermost `.git` directory containing `path`, if any (below `root`).
+fn enclosing_git_dir(root: &Path, path: &Path) -> Option<PathBuf> {
+    let relative = path.strip_prefix(root).ok()?;
+    let mut dir = root.to_path_buf();
+    let mut components = relative.components().peekable();
+    while let Some(component) = components.next() {
+        dir.push(component);
+        // The last component is the file itself.
+        if components.peek().is_some() && component.as_os_str() == ".git" {
+            return Some(dir);
+        }
+    }
+    None
+}
+
+/// Roll per-directory totals up to every ancestor below `root`, largest
+/// first. The root itself is left out: its totals are the scan's.
+fn rolled_up_dirs(root: &Path, dir_totals: HashMap<Arc<Path>, (u64, u64)>) -> Vec<DirRecord> {
+    let mut rolled: HashMap<&Path, (u64, u64)> = HashMap::new();
+    for (dir, (size, files)) in &dir_totals {
+        let mut current = Some(&**dir);
+        while let Some(path) = current.filter(|p| *p != root && p.starts_with(root)) {
+            let entry = rolled.entry(path).or_default();
+            entry.0 += size;
+            entry.1 += files;
+            current = path.parent();
+        }
+    }
+    let mut dirs: Vec<DirRecord> = rolled
+        .into_iter()
+        .map(|(path, (total_size_bytes, file_count))| DirRecord {
+            path: path.display().to_string(),
+            total_size_bytes,
+            file_count,
+        })
+        .collect();
+    dirs.sort_by(|a, b| {
+        b.total_size_bytes
+            .cmp(&a.total_size_bytes)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    dirs
+}
+
+/// MIME type of `path` from its leading bytes, or [`UNKNOWN_MIME`] when
+/// they match no signature or can't be read.
+pub(crate) fn sniff_mime(path: &Path) -> &'static str {
+    use std::io::Read;
+    let mut head = Vec::with_capacity(MIME_SNIFF_BYTES);
+    let read = std::fs::File::open(path)
+        .and_then(|file| file.take(MIME_SNIFF_BYTES as u64).read_to_end(&mut head));
+    match read {
+        Ok(_) => infer::get(&head).map_or(UNKNOWN_MIME, |kind| kind.mime_type()),
+        Err(_) => UNKNOWN_MIME,
+    }
+}
+
+/// Bytes allocated on disk for `meta`'s file (512-byte blocks on Unix).
+#[cfg(unix)]
+fn allocated_size(meta: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.blocks() * 512)
+}
+
+#[cfg(not(unix))]
+fn allocated_size(_meta: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// A symlink whose target is missing. A link whose target exists but can't
+/// be read (permissions, loops) is not considered broken.
+fn is_broken_symlink(path: &Path) -> bool {
+    std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink())
+        && std::fs::metadata(path).is_err_and(|e| e.kind() == std::io::ErrorKind::NotFound)
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// How a scan treats symbolic links. Broken links are always counted in
+/// `ScanStats::broken_symlinks`, whatever the mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SymlinkMode {
+    /// Leave links out of `total_files` and `total_size_bytes` entirely.
+    #[default]
+    Skip,
+    /// Follow links to files and directories. Every directory is entered at
+    /// most once, keyed by device and inode, so link cycles terminate and a
+    /// linked directory isn't counted twice.
+    Follow,
+    /// Count each link once as a file, sized by the link itself, without
+    /// following it.
+    CountAsFile,
+}
+
+impl std::str::FromStr for SymlinkMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "skip" => Ok(SymlinkMode::Skip),
+            "follow" => Ok(SymlinkMode::Follow),
+            "count-as-file" => Ok(SymlinkMode::CountAsFile),
+            other => Err(format!(
+                "unknown symlink mode '{}' (expected skip, follow or count-as-file)",
+                other
+            )),
+        }
+    }
 }
 
 // --- Scanner ---
@@ -149,10 +625,35 @@ pub struct Scanner {
     top_limit: usize,
     num_threads: usize,
     device: DeviceType,
+    excluded_paths: Vec<PathBuf>,
+    exclude_globs: Vec<String>,
+    include_extensions: Option<HashSet<String>>,
+    allocated_size: bool,
+    dirs_in_top: bool,
+    detect_mime: bool,
+    case_sensitivity: CaseSensitivity,
+    queue_bound: Option<usize>,
+    rollup_depth: Option<usize>,
+    sampling_fraction: Option<f64>,
+    changed_since: Option<SystemTime>,
+    count_root: bool,
+    symlink_mode: SymlinkMode,
+    list_broken_symlinks: bool,
+    git_rollup: bool,
+    time_budget: Option<Duration>,
+    profile: bool,
+    extension_min_size: u64,
+    extension_samples: usize,
+    summary_only: bool,
     progress_callback: Option<Box<dyn Fn(ScanProgress) + Send>>,
+    progress_interval: u64,
 }
 
 impl Scanner {
+    /// A scanner for `root` that keeps the `top_limit` largest files and
+    /// directories. A limit of 0 skips top-file tracking altogether:
+    /// `top_files` and `top_dirs` come back empty while every total is
+    /// still computed.
     pub fn new(root: impl Into<PathBuf>, top_limit: usize) -> Self {
         let root = root.into();
         let device = detect_device_type(&root);
@@ -163,11 +664,266 @@ impl Scanner {
             top_limit,
             num_threads: threads,
             device,
+            excluded_paths: Vec::new(),
+            exclude_globs: Vec::new(),
+            include_extensions: None,
+            allocated_size: false,
+            dirs_in_top: false,
+            detect_mime: false,
+            case_sensitivity: CaseSensitivity::default(),
+            queue_bound: None,
+            rollup_depth: None,
+            sampling_fraction: None,
+            changed_since: None,
+            count_root: true,
+            symlink_mode: SymlinkMode::default(),
+            list_broken_symlinks: false,
+            git_rollup: false,
+            time_budget: None,
+            profile: false,
+            extension_min_size: 0,
+            extension_samples: 0,
+            summary_only: false,
             progress_callback: None,
+            progress_interval: DEFAULT_PROGRESS_INTERVAL,
         }
     }
 
-    /// Override the auto-detected thread count.
+    /// Skip the given subtrees entirely. Excluded directories are not
+    /// descended into and contribute nothing to the totals.
+    pub fn exclude_paths(mut self, paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.excluded_paths.extend(paths);
+        self
+    }
+
+    /// Skip entries matching gitignore-style glob patterns (`node_modules`,
+    /// `target/`, `*.tmp`; see [`PathExclusions::with_globs`] for the rules).
+    /// A matching directory is pruned with its whole subtree and isn't
+    /// counted in `total_folders`. Invalid patterns fail the scan.
+    pub fn exclude<S: Into<String>>(mut self, patterns: impl IntoIterator<Item = S>) -> Self {
+        self.exclude_globs
+            .extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Only aggregate files with one of these extensions (no leading dot,
+    /// any case), e.g. `["mp4", "mov", "mkv"]`. The whole tree is still
+    /// walked and folders counted, but other files contribute nothing to
+    /// `total_files`, `total_size_bytes`, `extensions` or `top_files`.
+    pub fn with_include_extensions<S: AsRef<str>>(
+        mut self,
+        extensions: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.include_extensions = Some(
+            extensions
+                .into_iter()
+                .map(|e| e.as_ref().trim_start_matches('.').to_lowercase())
+                .collect(),
+        );
+        self
+    }
+
+    /// Also record on-disk allocated sizes (`ScanStats::total_allocated_bytes`
+    /// and `FileRecord::allocated_bytes`) next to logical sizes, to spot
+    /// sparse files and block slack. Unix only: elsewhere the fields stay
+    /// `None`.
+    pub fn with_allocated_size(mut self, allocated: bool) -> Self {
+        self.allocated_size = allocated;
+        self
+    }
+
+    /// Rank directories, by the size of everything below them, alongside
+    /// files in `top_files` (marked `is_dir`), so the largest entries of
+    /// either kind surface. The scan root itself is never listed.
+    pub fn with_directories_in_top(mut self, include: bool) -> Self {
+        self.dirs_in_top = include;
+        self
+    }
+
+    /// Sniff the first [`MIME_SNIFF_BYTES`] of every counted file to group
+    /// them by content type in `ScanStats::mime_types`, which catches
+    /// mislabeled and extensionless files. Costs an open and a small read
+    /// per file, so it's off by default.
+    pub fn with_mime_detection(mut self, detect: bool) -> Self {
+        self.detect_mime = detect;
+        self
+    }
+
+    /// Whether a file named `name` passes the include-only extension filter.
+    fn is_included(&self, name: &std::ffi::OsStr) -> bool {
+        let Some(include) = &self.include_extensions else {
+            return true;
+        };
+        Path::new(name)
+            .extension()
+            .is_some_and(|ext| include.contains(&ext.to_string_lossy().to_lowercase()))
+    }
+
+    /// Control how extensions are grouped and exclusions matched. Defaults to
+    /// the platform convention (case-insensitive on Windows/macOS only).
+    pub fn with_case_sensitivity(mut self, case: CaseSensitivity) -> Self {
+        self.case_sensitivity = case;
+        self
+    }
+
+    /// Cap how many walked entries may be buffered ahead of aggregation.
+    ///
+    /// By default the walk is unbounded: directory readers never wait for the
+    /// aggregator, which is fastest but can hold a huge number of entries in
+    /// memory on pathological trees. With a bound, readers pause once that many
+    /// entries are waiting. The cap is soft and may be exceeded by up to one
+    /// directory listing per thread.
+    pub fn with_queue_bound(mut self, bound: usize) -> Self {
+        self.queue_bound = Some(bound.max(1));
+        self
+    }
+
+    /// Keep directory rollups only down to `depth` levels below the root
+    /// (1 = the root's immediate children) for `ScanStats::top_dirs`.
+    ///
+    /// By default every directory holding a file gets an entry, which on deep
+    /// or wide trees costs memory proportional to the number of directories.
+    /// With a cap, files further down count toward their ancestor at the
+    /// capped depth, so the report can't name a deeper directory: a large
+    /// subtree below the cap shows up as its capped-depth ancestor instead.
+    /// Totals for directories at or above the cap stay exact.
+    pub fn with_rollup_depth(mut self, depth: usize) -> Self {
+        self.rollup_depth = Some(depth.max(1));
+        self
+    }
+
+    /// The directory whose rollup counts files directly in `dir`: `dir`
+    /// itself, or its ancestor at the rollup depth cap.
+    fn rollup_dir<'a>(&self, dir: &'a Path) -> &'a Path {
+        let Some(cap) = self.rollup_depth else {
+            return dir;
+        };
+        let depth = dir
+            .strip_prefix(&self.root)
+            .map_or(0, |rel| rel.components().count());
+        dir.ancestors()
+            .nth(depth.saturating_sub(cap))
+            .unwrap_or(dir)
+    }
+
+    /// Sample directories holding more than
+    /// [`SAMPLING_MIN_FILES`](sampling::SAMPLING_MIN_FILES) files instead of
+    /// stat'ing every file.
+    ///
+    /// Only `fraction` (0.0-1.0] of such a directory's files are read, and
+    /// their sizes are extrapolated to the whole directory. File counts stay
+    /// exact; sizes become estimates and `ScanStats::sampled_directories`
+    /// records how many directories were affected. Sampled-out files never
+    /// appear in `top_files`. Off by default.
+    pub fn with_directory_sampling(mut self, fraction: f64) -> Self {
+        self.sampling_fraction = Some(fraction.clamp(f64::MIN_POSITIVE, 1.0));
+        self
+    }
+
+    /// Quick "recent activity" scan: skip directories whose own mtime is
+    /// older than `cutoff` without reading them, and only count files
+    /// modified at or after it in the directories that are walked.
+    ///
+    /// A directory's mtime changes when entries are created, deleted or
+    /// renamed directly inside it, not when an existing file is rewritten in
+    /// place or when something changes deeper down. Pruning therefore misses
+    /// in-place edits and changes nested below an untouched directory, and
+    /// some filesystems (network mounts, FAT) don't maintain directory mtimes
+    /// reliably. The scan root itself is always read.
+    pub fn with_changed_since(mut self, cutoff: SystemTime) -> Self {
+        self.changed_since = Some(cutoff);
+        self
+    }
+
+    /// Whether the scan root counts towards `total_folders` (default: yes).
+    /// Pass `false` to count only the directories beneath it.
+    pub fn with_root_counted(mut self, count_root: bool) -> Self {
+        self.count_root = count_root;
+        self
+    }
+
+    /// Choose how symbolic links are handled (default: [`SymlinkMode::Skip`]).
+    pub fn with_symlink_mode(mut self, mode: SymlinkMode) -> Self {
+        self.symlink_mode = mode;
+        self
+    }
+
+    /// Shorthand for [`SymlinkMode::Follow`] (`true`) or
+    /// [`SymlinkMode::Skip`] (`false`). A linked file is counted once per
+    /// path that reaches it; see
+    /// [`ScanReport::dedup_top_files`](report::ScanReport::dedup_top_files)
+    /// to collapse such duplicates in the top list.
+    pub fn with_follow_links(mut self, follow: bool) -> Self {
+        self.symlink_mode = if follow {
+            SymlinkMode::Follow
+        } else {
+            SymlinkMode::Skip
+        };
+        self
+    }
+
+    /// Record the path of every broken symlink in
+    /// `ScanStats::broken_symlink_paths` for cleanup (default: count only).
+    pub fn with_broken_symlink_list(mut self, list: bool) -> Self {
+        self.list_broken_symlinks = list;
+        self
+    }
+
+    /// Count `.git` directories, rolled up into a single `top_files` entry
+    /// each (the directory path with its total size) instead of listing
+    /// individual loose and packed objects. Files inside count towards the
+    /// totals and extension stats. Off by default, in which case `.git` is
+    /// skipped like every other hidden entry.
+    pub fn with_git_rollup(mut self, rollup: bool) -> Self {
+        self.git_rollup = rollup;
+        self
+    }
+
+    /// Leave files smaller than `bytes` out of `ScanStats::extensions` so
+    /// swarms of empty or tiny files (lock files, markers) don't skew the
+    /// per-extension picture. They still count in `total_files` and
+    /// `total_size_bytes`. Default 0 (every file counts).
+    pub fn with_extension_min_size(mut self, bytes: u64) -> Self {
+        self.extension_min_size = bytes;
+        self
+    }
+
+    /// Keep up to `per_extension` paths of each extension in
+    /// `ScanStats::extension_samples`, for analysis that reads a sample of
+    /// every file type. Default 0 (none kept).
+    pub fn with_extension_samples(mut self, per_extension: usize) -> Self {
+        self.extension_samples = per_extension;
+        self
+    }
+
+    /// Compute only the totals: no extension stats, top files, top
+    /// directories or size percentiles. The fastest way to answer "how big, how many" on a huge
+    /// tree, since nothing is kept per file or per directory.
+    pub fn with_summary_only(mut self, summary_only: bool) -> Self {
+        self.summary_only = summary_only;
+        self
+    }
+
+    /// Stop walking once `budget` has elapsed and return what was gathered so
+    /// far, with `ScanStats::partial` set. The clock is checked every
+    /// [`TIME_BUDGET_CHECK_INTERVAL`] entries, so the overrun is bounded by
+    /// that many metadata reads.
+    pub fn with_time_budget(mut self, budget: Duration) -> Self {
+        self.time_budget = Some(budget);
+        self
+    }
+
+    /// Collect a [`ScanProfile`]: per-walker-thread listing and file counts,
+    /// plus how long aggregation waited on the walk versus read metadata.
+    /// Adds two clock reads per entry, so it's off by default.
+    pub fn with_profile(mut self, profile: bool) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Override the auto-detected thread count (see
+    /// [`recommended_threads`]). Lower it on NAS mounts and spinning disks,
+    /// where extra readers mostly add seeks.
     pub fn with_threads(mut self, threads: usize) -> Self {
         self.num_threads = threads.max(1);
         self
@@ -175,110 +931,538 @@ impl Scanner {
 
     /// Set a progress callback for streaming scan updates.
     ///
-    /// Called approximately every 1000 items processed OR every 250ms,
-    /// whichever happens first. A final emission is guaranteed at the end
-    /// of the scan as long as at least one item was processed, so even
-    /// very small scans produce at least one update.
+    /// Called approximately every 1000 items processed (see
+    /// [`with_progress_interval`](Self::with_progress_interval)) OR every
+    /// 250ms, whichever happens first. A final emission is guaranteed at the
+    /// end of the scan as long as at least one item was processed, so even
+    /// very small scans produce at least one update. For a callback that
+    /// borrows local state, use [`scan_with_progress`](Self::scan_with_progress).
     pub fn with_progress<F: Fn(ScanProgress) + Send + 'static>(mut self, callback: F) -> Self {
         self.progress_callback = Some(Box::new(callback));
         self
     }
 
+    /// Emit progress every `items` processed entries instead of
+    /// [`DEFAULT_PROGRESS_INTERVAL`]. The 250ms time-based flush still applies.
+    pub fn with_progress_interval(mut self, items: u64) -> Self {
+        self.progress_interval = items.max(1);
+        self
+    }
+
+    /// Fast pre-count of the files a [`scan`](Self::scan) would visit, for an
+    /// exact progress denominator.
+    ///
+    /// Walks the tree with the same exclusions, hidden-entry and link
+    /// settings but reads only directory listings, never file metadata. It
+    /// still doubles directory traversal, so it's opt-in. With
+    /// `with_changed_since` the count is an upper bound, since file mtimes
+    /// aren't checked.
+    pub fn count_files(&self) -> Result<u64> {
+        let count_links = self.symlink_mode == SymlinkMode::CountAsFile;
+        Ok(self
+            .listing_walker()?
+            .into_iter()
+            .flatten()
+            .filter(|entry| {
+                entry.file_type().is_file()
+                    || (count_links
+                        && entry.file_type().is_symlink()
+                        && !is_broken_symlink(&entry.path()))
+            })
+            .filter(|entry| self.is_included(&entry.file_name))
+            .count() as u64)
+    }
+
+    /// A walker over the entries a scan visits: same exclusions, hidden
+    /// entries and link handling, without the scan's bookkeeping. Errors if
+    /// the root can't be read or a glob is invalid.
+    fn listing_walker(&self) -> Result<jwalk::WalkDir> {
+        std::fs::metadata(&self.root)
+            .with_context(|| format!("Cannot scan {}", self.root.display()))?;
+        let exclusions = PathExclusions::new(&self.root, &self.excluded_paths)
+            .with_case_sensitivity(self.case_sensitivity)
+            .with_globs(&self.exclude_globs)?;
+        let git_rollup = self.git_rollup;
+        let follow = self.symlink_mode == SymlinkMode::Follow;
+        let visited = follow.then(|| VisitedDirs::new(&self.root));
+        Ok(jwalk::WalkDir::new(&self.root)
+            .parallelism(jwalk::Parallelism::RayonNewPool(self.num_threads))
+            .follow_links(follow)
+            .skip_hidden(!git_rollup)
+            .process_read_dir(move |depth, _, _, children| {
+                prune_children(children, depth, git_rollup, &exclusions, visited.as_ref());
+            }))
+    }
+
     /// Executes the parallel scan and returns the aggregated statistics.
     /// Thread count is automatically tuned based on device type (SSD vs HDD).
     pub fn scan(&self) -> Result<ScanStats> {
+        self.scan_cancellable(&AtomicBool::new(false))
+    }
+
+    /// Like [`scan`](Self::scan), but stops as soon as `cancel` is set (from
+    /// any thread) and returns what was aggregated so far with
+    /// `ScanStats::cancelled` set. The flag is checked for every walked
+    /// entry.
+    pub fn scan_cancellable(&self, cancel: &AtomicBool) -> Result<ScanStats> {
+        match &self.progress_callback {
+            Some(callback) => self.walk(callback, cancel),
+            None => self.walk(|_| {}, cancel),
+        }
+    }
+
+    /// Like [`scan`](Self::scan), reporting progress to `on_progress` on the
+    /// calling thread instead of the callback set by
+    /// [`with_progress`](Self::with_progress). Being `FnMut` and not `'static`,
+    /// it can update local counters or collect updates directly.
+    pub fn scan_with_progress(&self, on_progress: impl FnMut(ScanProgress)) -> Result<ScanStats> {
+        self.walk(on_progress, &AtomicBool::new(false))
+    }
+
+    fn walk(
+        &self,
+        mut on_progress: impl FnMut(ScanProgress),
+        cancel: &AtomicBool,
+    ) -> Result<ScanStats> {
         let start_time = Instant::now();
+        // jwalk yields nothing for a missing root; fail loudly instead of
+        // reporting an empty tree.
+        std::fs::metadata(&self.root)
+            .with_context(|| format!("Cannot scan {}", self.root.display()))?;
 
         let mut stats = ScanStats {
             root_path: self.root.display().to_string(),
             device_type: Some(self.device),
             threads_used: Some(self.num_threads),
+            scanned_at: unix_now(),
             ..Default::default()
         };
 
         let mut top_files_heap = BinaryHeap::with_capacity(self.top_limit + 1);
+        let mut samples = sampling::ExtensionReservoir::new(self.extension_samples);
+        let mut git_dirs: HashMap<PathBuf, u64> = HashMap::new();
+        // Bytes and files directly inside each directory, rolled up to the
+        // ancestors once the walk is done.
+        let mut dir_totals: HashMap<Arc<Path>, (u64, u64)> = HashMap::new();
         let mut item_counter = 0u64;
         let mut last_progress_emit = Instant::now();
         const PROGRESS_TIME_INTERVAL: Duration = Duration::from_millis(250);
-        const PROGRESS_ITEM_INTERVAL: u64 = 1000;
 
-        let walker = WalkDir::new(&self.root)
-            .parallelism(jwalk::Parallelism::RayonNewPool(self.num_threads));
+        let mut walker = WalkDirGeneric::<((), EntryState)>::new(&self.root)
+            .parallelism(jwalk::Parallelism::RayonNewPool(self.num_threads))
+            .follow_links(self.symlink_mode == SymlinkMode::Follow);
 
-        for dir_entry in walker.into_iter().flatten() {
-            if let Ok(meta) = dir_entry.metadata() {
-                if meta.is_file() {
+        let exclusions = PathExclusions::new(&self.root, &self.excluded_paths)
+            .with_case_sensitivity(self.case_sensitivity)
+            .with_globs(&self.exclude_globs)?;
+        let gate = self
+            .queue_bound
+            .map(|bound| Arc::new(QueueGate::new(bound)));
+        let sampling_fraction = self.sampling_fraction;
+        let sampled_dirs = Arc::new(AtomicU64::new(0));
+        let changed_since = self.changed_since;
+        let git_rollup = self.git_rollup;
+        if git_rollup {
+            walker = walker.skip_hidden(false);
+        }
+        let visited = (self.symlink_mode == SymlinkMode::Follow)
+            .then(|| Arc::new(VisitedDirs::new(&self.root)));
+        let counters = self
+            .profile
+            .then(|| Arc::new(WorkerCounters::new(self.num_threads)));
+        if !exclusions.is_empty()
+            || git_rollup
+            || counters.is_some()
+            || visited.is_some()
+            || gate.is_some()
+            || sampling_fraction.is_some()
+            || changed_since.is_some()
+        {
+            let reader_gate = gate.clone();
+            let sampled_counter = sampled_dirs.clone();
+            let reader_counters = counters.clone();
+            let reader_visited = visited.clone();
+            walker = walker.process_read_dir(move |depth, _, _, children| {
+                prune_children(
+                    children,
+                    depth,
+                    git_rollup,
+                    &exclusions,
+                    reader_visited.as_deref(),
+                );
+                if let Some(cutoff) = changed_since {
+                    children.retain(|child| match child {
+                        Ok(entry) => {
+                            !(entry.file_type().is_dir()
+                                && entry
+                                    .metadata()
+                                    .ok()
+                                    .and_then(|m| m.modified().ok())
+                                    .is_some_and(|mtime| mtime < cutoff))
+                        }
+                        Err(_) => true,
+                    });
+                }
+                if let Some(counters) = &reader_counters {
+                    let worker = counters.current_worker();
+                    if depth.is_some() {
+                        counters.record_listing(worker);
+                    }
+                    for entry in children.iter_mut().flatten() {
+                        entry.client_state.reader = worker;
+                    }
+                }
+                if let Some(fraction) = sampling_fraction {
+                    if sampling::sample_children(children, fraction) {
+                        sampled_counter.fetch_add(1, AtomicOrdering::Relaxed);
+                    }
+                }
+                if let Some(gate) = &reader_gate {
+                    gate.admit(children.len());
+                }
+            });
+        }
+
+        let mut walked = 0u64;
+        let mut worker_files = vec![0u64; counters.as_ref().map_or(0, |c| c.workers())];
+        let mut walk_wait = Duration::ZERO;
+        let mut metadata_time = Duration::ZERO;
+        let mut entries = walker.into_iter();
+        loop {
+            let waited_from = self.profile.then(Instant::now);
+            let Some(entry) = entries.next() else {
+                break;
+            };
+            if let Some(from) = waited_from {
+                walk_wait += from.elapsed();
+            }
+            if let Some(gate) = &gate {
+                gate.release();
+            }
+            if cancel.load(AtomicOrdering::Relaxed) {
+                stats.cancelled = true;
+                break;
+            }
+            if let Some(budget) = self.time_budget {
+                if walked.is_multiple_of(TIME_BUDGET_CHECK_INTERVAL)
+                    && start_time.elapsed() >= budget
+                {
+                    // Dropping the walker stops the reader threads.
+                    stats.partial = true;
+                    break;
+                }
+                walked += 1;
+            }
+            // A dangling link surfaces as a plain symlink entry, or as an
+            // error when links are followed. Anything else that fails to read
+            // (e.g. permission denied) is skipped and recorded in `errors`.
+            let broken_link = match &entry {
+                Ok(dir_entry) if dir_entry.file_type().is_symlink() => {
+                    is_broken_symlink(&dir_entry.path()).then(|| dir_entry.path())
+                }
+                Ok(_) => None,
+                Err(err) => err
+                    .path()
+                    .filter(|path| is_broken_symlink(path))
+                    .map(Path::to_path_buf),
+            };
+            if let Some(path) = broken_link {
+                stats.broken_symlinks += 1;
+                if self.list_broken_symlinks {
+                    stats.broken_symlink_paths.push(path.display().to_string());
+                }
+                continue;
+            }
+            let dir_entry = match entry {
+                Ok(dir_entry) => dir_entry,
+                Err(err) => {
+                    stats.errors.push(ScanError::from_walk(&err, None));
+                    continue;
+                }
+            };
+            if let Some(err) = &dir_entry.read_children_error {
+                let path = dir_entry.path();
+                stats.errors.push(ScanError::from_walk(err, Some(&path)));
+            }
+            let stat_from = self.profile.then(Instant::now);
+            let meta = dir_entry.metadata();
+            if let Some(from) = stat_from {
+                metadata_time += from.elapsed();
+            }
+            if let Err(err) = &meta {
+                let path = dir_entry.path();
+                stats.errors.push(ScanError::from_walk(err, Some(&path)));
+            }
+            if let Ok(meta) = meta {
+                let unchanged = self
+                    .changed_since
+                    .is_some_and(|cutoff| meta.modified().is_ok_and(|mtime| mtime < cutoff));
+                // Unfollowed links report their own metadata here.
+                let is_file = meta.is_file()
+                    || (self.symlink_mode == SymlinkMode::CountAsFile && meta.is_symlink());
+                if is_file && (unchanged || !self.is_included(&dir_entry.file_name)) {
+                    // Outside the --changed-since window or the included
+                    // extensions.
+                } else if is_file {
                     let size = meta.len();
-                    stats.total_files += 1;
-                    stats.total_size_bytes += size;
-
-                    // 1. EXTENSION ANALYTICS
-                    if let Some(ext) = dir_entry.path().extension() {
-                        let ext_string = ext.to_string_lossy().to_string().to_lowercase();
-                        let entry = stats.extensions.entry(ext_string).or_default();
-                        entry.count += 1;
-                        entry.size += size;
+                    // Greater than 1 only for files standing in for a sample.
+                    let weight = dir_entry.client_state.weight;
+                    stats.total_files += weight;
+                    if let Some(files) = worker_files.get_mut(dir_entry.client_state.reader) {
+                        *files += weight;
                     }
+                    stats.total_size_bytes += size * weight;
+                    let allocated = self.allocated_size.then(|| allocated_size(&meta)).flatten();
+                    if let Some(allocated) = allocated {
+                        *stats.total_allocated_bytes.get_or_insert(0) += allocated * weight;
+                    }
+                    // Everything below is per-file bookkeeping skipped by
+                    // summary-only scans.
+                    if !self.summary_only {
+                        let parent = &dir_entry.parent_path;
+                        let rollup = self.rollup_dir(parent);
+                        let dir = match dir_totals.get_mut(rollup) {
+                            Some(dir) => dir,
+                            None if rollup == &**parent => {
+                                dir_totals.entry(parent.clone()).or_default()
+                            }
+                            None => dir_totals.entry(Arc::from(rollup)).or_default(),
+                        };
+                        dir.0 += size * weight;
+                        dir.1 += weight;
+                        stats.size_histogram.record(size, weight);
 
-                    // 2. TOP FILES ANALYTICS
-                    top_files_heap.push(FileRecord {
-                        path: dir_entry.path().display().to_string(),
-                        size_bytes: size,
-                    });
+                        // 1. EXTENSION ANALYTICS
+                        let path = dir_entry.path();
+                        if let Some(ext) = self.extension_key(&path, size) {
+                            samples.offer(&ext, &path);
+                            let entry = stats.extensions.entry(ext).or_default();
+                            entry.count += weight;
+                            entry.size += size * weight;
+                        }
+
+                        if self.detect_mime {
+                            let mime = sniff_mime(&dir_entry.path());
+                            let entry = stats.mime_types.entry(mime.to_string()).or_default();
+                            entry.count += weight;
+                            entry.size += size * weight;
+                        }
+
+                        // 2. TOP FILES ANALYTICS (none kept with a zero
+                        // limit, so skip the heap rather than churn it)
+                        let git_dir = (self.git_rollup && self.top_limit > 0)
+                            .then(|| enclosing_git_dir(&self.root, &dir_entry.path()))
+                            .flatten();
+                        if let Some(git_dir) = git_dir {
+                            *git_dirs.entry(git_dir).or_default() += size * weight;
+                        } else if self.top_limit > 0 {
+                            top_files_heap.push(FileRecord {
+                                path: dir_entry.path().display().to_string(),
+                                size_bytes: size,
+                                allocated_bytes: allocated,
+                                is_dir: false,
+                            });
 
-                    if top_files_heap.len() > self.top_limit {
-                        top_files_heap.pop();
+                            if top_files_heap.len() > self.top_limit {
+                                top_files_heap.pop();
+                            }
+                        }
                     }
-                } else if meta.is_dir() {
+                } else if meta.is_dir() && (dir_entry.depth > 0 || self.count_root) {
                     stats.total_folders += 1;
                 }
 
-                // Emit progress every 1000 items OR every 250ms — whichever
-                // hits first. The time-based flush keeps small scans visible.
+                // Emit progress every N items (1000 by default) OR every
+                // 250ms — whichever hits first. The time-based flush keeps
+                // small scans visible.
                 item_counter += 1;
-                if let Some(cb) = &self.progress_callback {
-                    let by_count = item_counter.is_multiple_of(PROGRESS_ITEM_INTERVAL);
-                    let by_time = last_progress_emit.elapsed() >= PROGRESS_TIME_INTERVAL;
-                    if by_count || by_time {
-                        cb(ScanProgress {
-                            files_scanned: stats.total_files,
-                            folders_scanned: stats.total_folders,
-                            bytes_scanned: stats.total_size_bytes,
-                        });
-                        last_progress_emit = Instant::now();
-                    }
+                let by_count = item_counter.is_multiple_of(self.progress_interval);
+                let by_time = last_progress_emit.elapsed() >= PROGRESS_TIME_INTERVAL;
+                if by_count || by_time {
+                    on_progress(ScanProgress {
+                        files_scanned: stats.total_files,
+                        folders_scanned: stats.total_folders,
+                        bytes_scanned: stats.total_size_bytes,
+                        current_path: dir_entry.path().display().to_string(),
+                    });
+                    last_progress_emit = Instant::now();
                 }
             }
         }
 
-        // Final emission so small scans (under both the 1000-item and 250ms
+        // Final emission so small scans (under both the item and 250ms
         // thresholds) still produce at least one progress update.
-        if let Some(cb) = &self.progress_callback {
-            if item_counter > 0 {
-                cb(ScanProgress {
-                    files_scanned: stats.total_files,
-                    folders_scanned: stats.total_folders,
-                    bytes_scanned: stats.total_size_bytes,
-                });
-            }
+        if item_counter > 0 {
+            on_progress(ScanProgress {
+                files_scanned: stats.total_files,
+                folders_scanned: stats.total_folders,
+                bytes_scanned: stats.total_size_bytes,
+                current_path: stats.root_path.clone(),
+            });
         }
 
         stats.scan_duration_ms = start_time.elapsed().as_millis();
+        stats.extension_samples = samples.into_samples();
+        stats.extension_sample_limit = self.extension_samples;
+        stats.sampled_directories = sampled_dirs.load(AtomicOrdering::Relaxed);
+        stats.profile = counters.map(|counters| {
+            counters.profile(
+                worker_files,
+                walk_wait.as_millis() as u64,
+                metadata_time.as_millis() as u64,
+            )
+        });
 
-        // Finalize top files (sort descending)
+        for (git_dir, size) in git_dirs {
+            top_files_heap.push(FileRecord {
+                path: git_dir.display().to_string(),
+                size_bytes: size,
+                allocated_bytes: None,
+                is_dir: true,
+            });
+            if top_files_heap.len() > self.top_limit {
+                top_files_heap.pop();
+            }
+        }
+
+        // Finalize top files. `FileRecord` orders largest first, so the
+        // sorted vec is already descending by size.
         stats.top_files = top_files_heap.into_sorted_vec();
-        stats.top_files.reverse();
+        if self.sampling_fraction.is_none() {
+            self.reconcile_top_files(&mut stats, &mut dir_totals);
+        }
+        let mut dirs = rolled_up_dirs(&self.root, dir_totals);
+        dirs.truncate(self.top_limit);
+        if self.dirs_in_top {
+            stats.top_files.extend(dirs.iter().map(|dir| FileRecord {
+                path: dir.path.clone(),
+                size_bytes: dir.total_size_bytes,
+                allocated_bytes: None,
+                is_dir: true,
+            }));
+            stats
+                .top_files
+                .sort_by_key(|f| std::cmp::Reverse(f.size_bytes));
+            stats.top_files.truncate(self.top_limit);
+        }
+        stats.top_dirs = dirs;
+        stats.size_percentiles = stats.size_histogram.percentiles();
 
         Ok(stats)
     }
+
+    /// Extension key a file of `size` is aggregated under, if any.
+    fn extension_key(&self, path: &Path, size: u64) -> Option<String> {
+        let ext = path
+            .extension()
+            .filter(|_| size >= self.extension_min_size)?;
+        Some(
+            self.case_sensitivity
+                .normalize(&ext.to_string_lossy())
+                .into_owned(),
+        )
+    }
+
+    /// Re-stat the top files after the walk so the files a report points at
+    /// agree with the totals.
+    ///
+    /// A scan is a best-effort point-in-time snapshot: each file is counted
+    /// once, with the size read when the walker reached it, and a file that
+    /// changes afterwards isn't revisited. The top files are the exception
+    /// because callers act on them (analysis reads their contents, governance
+    /// may delete them). Any that grew or shrank since the walk have their
+    /// size updated and the difference applied to `total_size_bytes` and
+    /// their extension; any that vanished or stopped being files are dropped
+    /// from the list and the totals. Skipped for sampled scans, where the
+    /// totals are estimates anyway.
+    fn reconcile_top_files(
+        &self,
+        stats: &mut ScanStats,
+        dir_totals: &mut HashMap<Arc<Path>, (u64, u64)>,
+    ) {
+        let follow = self.symlink_mode == SymlinkMode::Follow;
+        let count_links = self.symlink_mode == SymlinkMode::CountAsFile;
+        let mut kept = Vec::with_capacity(stats.top_files.len());
+        for mut file in std::mem::take(&mut stats.top_files) {
+            let path = PathBuf::from(&file.path);
+            let meta = if follow {
+                std::fs::metadata(&path)
+            } else {
+                std::fs::symlink_metadata(&path)
+            };
+            let current = match meta {
+                // Rolled-up .git directories aren't files; keep them as-is.
+                Ok(meta) if meta.is_dir() && self.git_rollup => {
+                    kept.push(file);
+                    continue;
+                }
+                Ok(meta) if meta.is_file() || (count_links && meta.is_symlink()) => Some(meta),
+                _ => None,
+            };
+            if current.as_ref().map(|meta| meta.len()) == Some(file.size_bytes) {
+                kept.push(file);
+                continue;
+            }
+
+            let old = file.size_bytes;
+            let mut dir = path
+                .parent()
+                .and_then(|parent| dir_totals.get_mut(self.rollup_dir(parent)));
+            if let Some(dir) = dir.as_deref_mut() {
+                dir.0 = dir.0.saturating_sub(old);
+                dir.1 = dir.1.saturating_sub(1);
+            }
+            stats.total_size_bytes = stats.total_size_bytes.saturating_sub(old);
+            if let (Some(total), Some(allocated)) =
+                (stats.total_allocated_bytes.as_mut(), file.allocated_bytes)
+            {
+                *total = total.saturating_sub(allocated);
+            }
+            if let Some(ext) = self.extension_key(&path, old) {
+                if let Some(stat) = stats.extensions.get_mut(&ext) {
+                    stat.count = stat.count.saturating_sub(1);
+                    stat.size = stat.size.saturating_sub(old);
+                    if stat.count == 0 {
+                        stats.extensions.remove(&ext);
+                    }
+                }
+            }
+            let Some(meta) = current else {
+                stats.total_files = stats.total_files.saturating_sub(1);
+                continue;
+            };
+            let new = meta.len();
+            stats.total_size_bytes += new;
+            file.allocated_bytes = self.allocated_size.then(|| allocated_size(&meta)).flatten();
+            if let (Some(total), Some(allocated)) =
+                (stats.total_allocated_bytes.as_mut(), file.allocated_bytes)
+            {
+                *total += allocated;
+            }
+            if let Some(dir) = dir {
+                dir.0 += new;
+                dir.1 += 1;
+            }
+            if let Some(ext) = self.extension_key(&path, new) {
+                let stat = stats.extensions.entry(ext).or_default();
+                stat.count += 1;
+                stat.size += new;
+            }
+            file.size_bytes = new;
+            kept.push(file);
+        }
+        kept.sort_by_key(|f| std::cmp::Reverse(f.size_bytes));
+        stats.top_files = kept;
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::stats_with_extensions;
     use std::fs::File;
     use std::io::Write;
-    use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering as AtomicOrdering};
     use std::sync::Arc;
     use tempfile::tempdir;
 
@@ -300,6 +1484,295 @@ mod tests {
         assert!(stats.threads_used.is_some());
     }
 
+    #[test]
+    fn test_profile_worker_files_sum_to_total() {
+        let dir = tempdir().unwrap();
+        for d in 0..8 {
+            let sub = dir.path().join(format!("dir_{}", d));
+            std::fs::create_dir(&sub).unwrap();
+            for f in 0..25 {
+                File::create(sub.join(format!("file_{}.txt", f))).unwrap();
+            }
+        }
+
+        let stats = Scanner::new(dir.path(), 5)
+            .with_threads(4)
+            .with_profile(true)
+            .scan()
+            .unwrap();
+        let profile = stats.profile.expect("profile requested");
+        assert_eq!(profile.workers.len(), 4);
+        assert_eq!(profile.files(), stats.total_files);
+        assert_eq!(stats.total_files, 200);
+        // Root plus eight subdirectories.
+        let listings: u64 = profile.workers.iter().map(|w| w.directories).sum();
+        assert_eq!(listings, 9);
+
+        assert!(Scanner::new(dir.path(), 5)
+            .scan()
+            .unwrap()
+            .profile
+            .is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_modes() {
+        use std::os::unix::fs::symlink;
+        let dir = tempdir().unwrap();
+        File::create(dir.path().join("real.txt")).unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        File::create(dir.path().join("sub/inner.txt")).unwrap();
+        symlink(dir.path().join("real.txt"), dir.path().join("file_link")).unwrap();
+        symlink(dir.path().join("sub"), dir.path().join("dir_link")).unwrap();
+        // A cycle back to the root.
+        symlink(dir.path(), dir.path().join("sub/back")).unwrap();
+
+        let scan = |mode| {
+            Scanner::new(dir.path(), 10)
+                .with_symlink_mode(mode)
+                .scan()
+                .unwrap()
+                .total_files
+        };
+        assert_eq!(scan(SymlinkMode::Skip), 2);
+        assert_eq!(scan(SymlinkMode::CountAsFile), 5);
+        // real.txt, sub/inner.txt (reached once, via sub or dir_link) and
+        // file_link; the cycle is not re-entered.
+        assert_eq!(scan(SymlinkMode::Follow), 3);
+
+        for mode in [
+            SymlinkMode::Skip,
+            SymlinkMode::Follow,
+            SymlinkMode::CountAsFile,
+        ] {
+            let scanner = Scanner::new(dir.path(), 10).with_symlink_mode(mode);
+            assert_eq!(scanner.count_files().unwrap(), scan(mode), "{:?}", mode);
+        }
+        assert_eq!(
+            "count-as-file".parse::<SymlinkMode>(),
+            Ok(SymlinkMode::CountAsFile)
+        );
+    }
+
+    #[test]
+    fn test_single_thread_matches_default_totals() {
+        let dir = tempdir().unwrap();
+        for d in 0..4 {
+            let sub = dir.path().join(format!("dir_{}", d));
+            std::fs::create_dir(&sub).unwrap();
+            for f in 0..10 {
+                std::fs::write(sub.join(format!("f_{}.dat", f)), vec![0u8; f * 10]).unwrap();
+            }
+        }
+
+        let default = Scanner::new(dir.path(), 5).scan().unwrap();
+        let single = Scanner::new(dir.path(), 5).with_threads(1).scan().unwrap();
+        assert_eq!(single.threads_used, Some(1));
+        assert_eq!(single.total_files, default.total_files);
+        assert_eq!(single.total_folders, default.total_folders);
+        assert_eq!(single.total_size_bytes, default.total_size_bytes);
+        assert_eq!(single.extensions["dat"].count, 40);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unreadable_directory_is_recorded() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempdir().unwrap();
+        let locked = dir.path().join("locked");
+        std::fs::create_dir(&locked).unwrap();
+        File::create(locked.join("secret.txt")).unwrap();
+        File::create(dir.path().join("open.txt")).unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+
+        // Root ignores directory permissions; nothing to observe then.
+        let readable = std::fs::read_dir(&locked).is_ok();
+        let stats = Scanner::new(dir.path(), 5).scan().unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+        if readable {
+            assert!(stats.errors.is_empty());
+            return;
+        }
+
+        assert_eq!(stats.total_files, 1);
+        assert_eq!(stats.errors.len(), 1);
+        assert_eq!(stats.errors[0].path, locked.display().to_string());
+        assert!(stats.errors[0].message.contains("ermission denied"));
+    }
+
+    #[test]
+    fn test_extension_min_size_skips_tiny_files() {
+        let dir = tempdir().unwrap();
+        for i in 0..5 {
+            File::create(dir.path().join(format!("{}.lock", i))).unwrap();
+        }
+        std::fs::write(dir.path().join("big.lock"), vec![b'x'; 4096]).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), b"hi").unwrap();
+
+        let stats = Scanner::new(dir.path(), 10)
+            .with_extension_min_size(1024)
+            .scan()
+            .unwrap();
+        assert_eq!(stats.total_files, 7);
+        assert_eq!(stats.total_size_bytes, 4098);
+        let lock = &stats.extensions["lock"];
+        assert_eq!((lock.count, lock.size), (1, 4096));
+        assert!(!stats.extensions.contains_key("txt"));
+    }
+
+    #[test]
+    fn test_time_budget_returns_partial_scan() {
+        let dir = tempdir().unwrap();
+        for d in 0..20 {
+            let sub = dir.path().join(format!("dir_{}", d));
+            std::fs::create_dir(&sub).unwrap();
+            for f in 0..100 {
+                File::create(sub.join(format!("file_{}.bin", f))).unwrap();
+            }
+        }
+
+        let full = Scanner::new(dir.path(), 5).scan().unwrap();
+        assert_eq!(full.total_files, 2000);
+        assert!(!full.partial);
+
+        let stats = Scanner::new(dir.path(), 5)
+            .with_time_budget(Duration::from_nanos(1))
+            .scan()
+            .unwrap();
+        assert!(stats.partial);
+        assert!(stats.total_files < full.total_files);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_broken_symlink_counted_not_dropped() {
+        let dir = tempdir().unwrap();
+        File::create(dir.path().join("real.txt")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("gone.txt"), dir.path().join("dangling"))
+            .unwrap();
+
+        for follow in [false, true] {
+            let stats = Scanner::new(dir.path(), 5)
+                .with_follow_links(follow)
+                .with_broken_symlink_list(true)
+                .scan()
+                .unwrap();
+            assert_eq!(stats.broken_symlinks, 1, "follow_links = {}", follow);
+            assert_eq!(stats.total_files, 1);
+            assert_eq!(stats.broken_symlink_paths.len(), 1);
+            assert!(stats.broken_symlink_paths[0].ends_with("dangling"));
+        }
+    }
+
+    #[test]
+    fn test_git_rollup_keeps_objects_out_of_top_files() {
+        let dir = tempdir().unwrap();
+        let objects = dir.path().join("repo/.git/objects/ab");
+        std::fs::create_dir_all(&objects).unwrap();
+        for i in 0..20 {
+            std::fs::write(objects.join(format!("obj{}", i)), vec![0u8; 1000]).unwrap();
+        }
+        std::fs::write(dir.path().join("repo/main.rs"), vec![0u8; 100]).unwrap();
+
+        let plain = Scanner::new(dir.path(), 5).scan().unwrap();
+        // Hidden directories, .git included, are skipped by default.
+        assert_eq!(plain.total_size_bytes, 100);
+
+        let rolled = Scanner::new(dir.path(), 5)
+            .with_git_rollup(true)
+            .scan()
+            .unwrap();
+        assert_eq!(rolled.total_size_bytes, 20_100);
+        assert_eq!(rolled.total_files, 21);
+        assert!(rolled.top_files.iter().all(|f| !f.path.contains("objects")));
+        let git = rolled
+            .top_files
+            .iter()
+            .find(|f| f.path.ends_with(".git"))
+            .expect("rolled-up .git entry");
+        assert_eq!(git.size_bytes, 20_000);
+        assert!(rolled.top_files.iter().any(|f| f.path.ends_with("main.rs")));
+    }
+
+    #[test]
+    fn test_count_files_matches_scan_total() {
+        let dir = tempdir().unwrap();
+        for i in 0..30 {
+            let sub = dir.path().join(format!("d{}/e{}", i % 3, i % 5));
+            std::fs::create_dir_all(&sub).unwrap();
+            std::fs::write(sub.join(format!("f{}.txt", i)), b"x").unwrap();
+        }
+        std::fs::create_dir_all(dir.path().join("skip")).unwrap();
+        std::fs::write(dir.path().join("skip/ignored.txt"), b"x").unwrap();
+        std::fs::write(dir.path().join(".hidden"), b"x").unwrap();
+
+        let scanner = Scanner::new(dir.path(), 5).exclude_paths([dir.path().join("skip")]);
+        let counted = scanner.count_files().unwrap();
+        let stats = scanner.scan().unwrap();
+
+        assert_eq!(counted, 30);
+        assert_eq!(counted, stats.total_files);
+    }
+
+    #[test]
+    fn test_scanned_at_is_recent() {
+        let dir = tempdir().unwrap();
+        let before = unix_now();
+        let stats = Scanner::new(dir.path(), 5).scan().unwrap();
+
+        // Later than 2024-01-01 and no later than now.
+        assert!(stats.scanned_at > 1_704_067_200);
+        assert!(stats.scanned_at >= before && stats.scanned_at <= unix_now());
+    }
+
+    #[test]
+    fn test_total_inodes_counts_files_and_folders() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        File::create(dir.path().join("top.txt")).unwrap();
+        File::create(dir.path().join("a/one.txt")).unwrap();
+        File::create(dir.path().join("a/b/two.txt")).unwrap();
+
+        let stats = Scanner::new(dir.path(), 5).scan().unwrap();
+
+        assert_eq!(stats.total_files, 3);
+        assert_eq!(
+            stats.total_inodes(),
+            stats.total_files + stats.total_folders
+        );
+        assert!(!stats.has_many_small_files());
+
+        let tiny = ScanStats {
+            total_files: SMALL_FILES_MIN_INODES,
+            total_size_bytes: SMALL_FILES_MIN_INODES * 100,
+            ..Default::default()
+        };
+        assert!(tiny.has_many_small_files());
+    }
+
+    #[test]
+    fn test_empty_directory_folder_count_follows_root_rule() {
+        let dir = tempdir().unwrap();
+
+        let counted = Scanner::new(dir.path(), 5).scan().unwrap();
+        assert_eq!(counted.total_folders, 1);
+        assert_eq!(counted.total_files, 0);
+
+        let uncounted = Scanner::new(dir.path(), 5)
+            .with_root_counted(false)
+            .scan()
+            .unwrap();
+        assert_eq!(uncounted.total_folders, 0);
+
+        std::fs::create_dir(dir.path().join("child")).unwrap();
+        let nested = Scanner::new(dir.path(), 5)
+            .with_root_counted(false)
+            .scan()
+            .unwrap();
+        assert_eq!(nested.total_folders, 1);
+    }
+
     #[test]
     fn test_device_detection() {
         let device = detect_device_type(Path::new("."));
@@ -339,4 +1812,589 @@ mod tests {
             "expected at least one progress emission for a small scan"
         );
     }
+
+    #[test]
+    fn test_scan_with_progress_collects_updates() {
+        let dir = tempdir().unwrap();
+        for i in 0..25 {
+            File::create(dir.path().join(format!("file_{}.txt", i))).unwrap();
+        }
+
+        let mut updates = Vec::new();
+        let stats = Scanner::new(dir.path(), 5)
+            .with_progress_interval(10)
+            .scan_with_progress(|progress| updates.push(progress))
+            .unwrap();
+
+        // 26 entries (root + files): updates at 10 and 20, plus the final one.
+        assert!(updates.len() >= 3);
+        let last = updates.last().unwrap();
+        assert_eq!(last.files_scanned, stats.total_files);
+        assert_eq!(stats.total_files, 25);
+        assert!(!last.current_path.is_empty());
+    }
+
+    #[test]
+    fn test_files_changing_mid_scan_are_reconciled() {
+        let dir = tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name);
+        std::fs::write(path("grows.log"), vec![0u8; 1000]).unwrap();
+        std::fs::write(path("shrinks.log"), vec![0u8; 600]).unwrap();
+        std::fs::write(path("vanishes.log"), vec![0u8; 300]).unwrap();
+        std::fs::write(path("steady.txt"), vec![0u8; 10]).unwrap();
+
+        // Each file changes right after the walker has recorded it.
+        let stats = Scanner::new(dir.path(), 10)
+            .with_progress_interval(1)
+            .scan_with_progress(|progress| {
+                let current = Path::new(&progress.current_path);
+                if current == path("grows.log") {
+                    let mut file = std::fs::OpenOptions::new()
+                        .append(true)
+                        .open(current)
+                        .unwrap();
+                    file.write_all(&[0u8; 500]).unwrap();
+                } else if current == path("shrinks.log") {
+                    File::options()
+                        .write(true)
+                        .open(current)
+                        .unwrap()
+                        .set_len(100)
+                        .unwrap();
+                } else if current == path("vanishes.log") {
+                    std::fs::remove_file(current).unwrap();
+                }
+            })
+            .unwrap();
+
+        let sizes: Vec<u64> = stats.top_files.iter().map(|f| f.size_bytes).collect();
+        assert_eq!(sizes, [1500, 100, 10]);
+        assert_eq!(stats.total_files, 3);
+        assert_eq!(stats.total_size_bytes, 1610);
+        assert_eq!(stats.extensions["log"].count, 2);
+        assert_eq!(stats.extensions["log"].size, 1600);
+        let ext_total: u64 = stats.extensions.values().map(|e| e.size).sum();
+        assert_eq!(ext_total, stats.total_size_bytes);
+    }
+
+    #[test]
+    fn test_directories_in_top_rank_by_rolled_up_size() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("many")).unwrap();
+        for i in 0..5 {
+            std::fs::write(dir.path().join(format!("many/{}.bin", i)), vec![0u8; 300]).unwrap();
+        }
+        std::fs::write(dir.path().join("single.bin"), vec![0u8; 1000]).unwrap();
+
+        let stats = Scanner::new(dir.path(), 3)
+            .with_directories_in_top(true)
+            .scan()
+            .unwrap();
+        let top = &stats.top_files;
+        assert_eq!(top.len(), 3);
+        assert!(top[0].is_dir);
+        assert_eq!(Path::new(&top[0].path), dir.path().join("many"));
+        assert_eq!(top[0].size_bytes, 1500);
+        assert!(!top[1].is_dir);
+        assert_eq!(top[1].size_bytes, 1000);
+        // Totals still count files only.
+        assert_eq!(stats.total_size_bytes, 2500);
+
+        let files_only = Scanner::new(dir.path(), 3).scan().unwrap();
+        assert!(files_only.top_files.iter().all(|f| !f.is_dir));
+        assert_eq!(files_only.top_files[0].size_bytes, 1000);
+    }
+
+    #[test]
+    fn test_top_dirs_roll_up_nested_sizes() {
+        let dir = tempdir().unwrap();
+        let parent = dir.path().join("parent");
+        std::fs::create_dir_all(parent.join("a/deep")).unwrap();
+        std::fs::create_dir(parent.join("b")).unwrap();
+        std::fs::create_dir(dir.path().join("other")).unwrap();
+        std::fs::write(parent.join("top.bin"), vec![0u8; 100]).unwrap();
+        std::fs::write(parent.join("a/one.bin"), vec![0u8; 200]).unwrap();
+        std::fs::write(parent.join("a/deep/two.bin"), vec![0u8; 400]).unwrap();
+        std::fs::write(parent.join("b/three.bin"), vec![0u8; 800]).unwrap();
+        std::fs::write(dir.path().join("other/four.bin"), vec![0u8; 50]).unwrap();
+        std::fs::write(dir.path().join("loose.bin"), vec![0u8; 5]).unwrap();
+
+        let stats = Scanner::new(dir.path(), 10).scan().unwrap();
+        let find = |p: &Path| {
+            stats
+                .top_dirs
+                .iter()
+                .find(|d| Path::new(&d.path) == p)
+                .unwrap()
+        };
+
+        let descendants = ["a", "b"]
+            .iter()
+            .map(|d| find(&parent.join(d)).total_size_bytes)
+            .sum::<u64>();
+        assert_eq!(find(&parent).total_size_bytes, 100 + descendants);
+        assert_eq!(find(&parent).total_size_bytes, 1500);
+        assert_eq!(find(&parent).file_count, 4);
+        assert_eq!(find(&parent.join("a")).total_size_bytes, 600);
+        assert_eq!(find(&parent.join("a/deep")).file_count, 1);
+
+        // Root excluded; largest first; bounded by the top limit.
+        let sizes: Vec<u64> = stats.top_dirs.iter().map(|d| d.total_size_bytes).collect();
+        assert_eq!(sizes, [1500, 800, 600, 400, 50]);
+        let bounded = Scanner::new(dir.path(), 2).scan().unwrap();
+        assert_eq!(bounded.top_dirs.len(), 2);
+    }
+
+    #[test]
+    fn test_zero_top_limit_tracks_no_top_files() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("repo/.git")).unwrap();
+        std::fs::write(dir.path().join("repo/.git/HEAD"), b"ref: main").unwrap();
+        std::fs::write(dir.path().join("big.bin"), vec![0u8; 5_000]).unwrap();
+        std::fs::write(dir.path().join("small.txt"), b"hi").unwrap();
+
+        let stats = Scanner::new(dir.path(), 0)
+            .with_git_rollup(true)
+            .with_directories_in_top(true)
+            .scan()
+            .unwrap();
+        assert!(stats.top_files.is_empty());
+        assert!(stats.top_dirs.is_empty());
+        assert_eq!(stats.total_files, 3);
+        assert_eq!(stats.total_size_bytes, 5_011);
+        assert_eq!(stats.extensions["bin"].size, 5_000);
+    }
+
+    #[test]
+    fn test_rollup_depth_attributes_deep_files_to_capped_ancestor() {
+        let dir = tempdir().unwrap();
+        let a = dir.path().join("a");
+        std::fs::create_dir_all(a.join("b/c/d")).unwrap();
+        std::fs::create_dir(dir.path().join("e")).unwrap();
+        std::fs::write(a.join("one.bin"), vec![0u8; 10]).unwrap();
+        std::fs::write(a.join("b/two.bin"), vec![0u8; 100]).unwrap();
+        std::fs::write(a.join("b/c/three.bin"), vec![0u8; 1_000]).unwrap();
+        std::fs::write(a.join("b/c/d/four.bin"), vec![0u8; 5_000]).unwrap();
+        std::fs::write(dir.path().join("e/five.bin"), vec![0u8; 7]).unwrap();
+
+        let stats = Scanner::new(dir.path(), 10)
+            .with_rollup_depth(2)
+            .scan()
+            .unwrap();
+        let dirs: Vec<(PathBuf, u64, u64)> = stats
+            .top_dirs
+            .iter()
+            .map(|d| (PathBuf::from(&d.path), d.total_size_bytes, d.file_count))
+            .collect();
+        assert_eq!(
+            dirs,
+            [
+                (a.clone(), 6_110, 4),
+                (a.join("b"), 6_100, 3),
+                (dir.path().join("e"), 7, 1),
+            ]
+        );
+        // Capped-depth totals match an uncapped scan.
+        let full = Scanner::new(dir.path(), 10).scan().unwrap();
+        assert_eq!(full.top_dirs.len(), 5);
+        assert_eq!(full.top_dirs[..2], stats.top_dirs[..2]);
+        assert_eq!(stats.total_size_bytes, full.total_size_bytes);
+    }
+
+    #[test]
+    fn test_summary_only_computes_totals_alone() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("a.txt"), vec![0u8; 100]).unwrap();
+        std::fs::write(dir.path().join("sub/b.log"), vec![0u8; 250]).unwrap();
+        std::fs::write(dir.path().join("sub/c.log"), vec![0u8; 50]).unwrap();
+
+        let stats = Scanner::new(dir.path(), 10)
+            .with_summary_only(true)
+            .scan()
+            .unwrap();
+        assert_eq!(stats.total_files, 3);
+        assert_eq!(stats.total_size_bytes, 400);
+        assert_eq!(stats.total_folders, 2);
+        assert!(stats.extensions.is_empty());
+        assert!(stats.top_files.is_empty());
+        assert!(stats.top_dirs.is_empty());
+    }
+
+    #[test]
+    fn test_size_percentiles_approximate_known_sizes() {
+        let dir = tempdir().unwrap();
+        // Sizes 1..=1000 bytes: true median 500, p90 900, p99 990.
+        for size in 1..=1000usize {
+            std::fs::write(dir.path().join(format!("f_{}.dat", size)), vec![0u8; size]).unwrap();
+        }
+
+        let stats = Scanner::new(dir.path(), 5).scan().unwrap();
+        let p = stats.size_percentiles;
+        let within = |actual: u64, expected: u64| actual.abs_diff(expected) * 16 <= expected;
+        assert!(within(p.p50, 500), "p50 = {}", p.p50);
+        assert!(within(p.p90, 900), "p90 = {}", p.p90);
+        assert!(within(p.p99, 990), "p99 = {}", p.p99);
+        assert_eq!(p.max, 1000);
+    }
+
+    #[test]
+    fn test_exclude_glob_prunes_target_dir() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("target/debug/deps")).unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("target/debug/app"), vec![0u8; 5000]).unwrap();
+        std::fs::write(
+            dir.path().join("target/debug/deps/lib.rlib"),
+            vec![0u8; 900],
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), vec![0u8; 40]).unwrap();
+        // A file named like the pattern isn't matched by `target/`.
+        std::fs::write(dir.path().join("src/target"), vec![0u8; 2]).unwrap();
+
+        let stats = Scanner::new(dir.path(), 10)
+            .exclude(["target/"])
+            .scan()
+            .unwrap();
+        assert_eq!(stats.total_files, 2);
+        assert_eq!(stats.total_size_bytes, 42);
+        // Root + src only.
+        assert_eq!(stats.total_folders, 2);
+        assert!(!stats.extensions.contains_key("rlib"));
+        assert!(stats.top_files.iter().all(|f| !f.path.contains("debug")));
+
+        assert!(Scanner::new(dir.path(), 10)
+            .exclude(["[bad"])
+            .scan()
+            .is_err());
+    }
+
+    #[test]
+    fn test_include_extensions_limits_aggregation() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("clips")).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), vec![0u8; 10_000]).unwrap();
+        std::fs::write(dir.path().join("clips/a.mp4"), vec![0u8; 300]).unwrap();
+        std::fs::write(dir.path().join("clips/B.MP4"), vec![0u8; 200]).unwrap();
+        std::fs::write(dir.path().join("clips/readme.txt"), vec![0u8; 5]).unwrap();
+
+        let scanner = Scanner::new(dir.path(), 10)
+            .with_case_sensitivity(CaseSensitivity::Insensitive)
+            .with_include_extensions([".mp4"]);
+        let stats = scanner.scan().unwrap();
+
+        assert_eq!(stats.total_files, 2);
+        assert_eq!(stats.total_size_bytes, 500);
+        assert_eq!(stats.extensions.keys().collect::<Vec<_>>(), ["mp4"]);
+        assert_eq!(stats.extensions["mp4"].count, 2);
+        assert_eq!(stats.top_files.len(), 2);
+        assert!(stats.top_files.iter().all(|f| !f.path.ends_with(".txt")));
+        // Traversal is unaffected.
+        assert_eq!(stats.total_folders, 2);
+        assert_eq!(scanner.count_files().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_cancel_flag_stops_scan_early() {
+        let dir = tempdir().unwrap();
+        for d in 0..10 {
+            let sub = dir.path().join(format!("dir_{}", d));
+            std::fs::create_dir(&sub).unwrap();
+            for f in 0..100 {
+                File::create(sub.join(format!("file_{}.bin", f))).unwrap();
+            }
+        }
+        // Slow the walk down so the flag lands mid-scan.
+        let scanner = Scanner::new(dir.path(), 5)
+            .with_progress_interval(1)
+            .with_progress(|_| std::thread::sleep(Duration::from_millis(1)));
+        let cancel = AtomicBool::new(false);
+
+        let stats = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                std::thread::sleep(Duration::from_millis(50));
+                cancel.store(true, AtomicOrdering::Relaxed);
+            });
+            scanner.scan_cancellable(&cancel).unwrap()
+        });
+        assert!(stats.cancelled);
+        assert!(!stats.partial);
+        assert!(stats.total_files < 1000);
+
+        let full = Scanner::new(dir.path(), 5).scan().unwrap();
+        assert!(!full.cancelled);
+        assert_eq!(full.total_files, 1000);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sparse_file_allocates_less_than_logical_size() {
+        let dir = tempdir().unwrap();
+        let sparse = File::create(dir.path().join("sparse.img")).unwrap();
+        sparse.set_len(64 * 1024 * 1024).unwrap();
+
+        let stats = Scanner::new(dir.path(), 10)
+            .with_allocated_size(true)
+            .scan()
+            .unwrap();
+        let file = &stats.top_files[0];
+        assert_eq!(file.size_bytes, 64 * 1024 * 1024);
+        assert!(file.allocated_bytes.unwrap() < file.size_bytes);
+        assert!(file.allocation_ratio().unwrap() < 1.0);
+        assert!(stats.allocation_ratio().unwrap() < 1.0);
+
+        // Off by default.
+        let stats = Scanner::new(dir.path(), 10).scan().unwrap();
+        assert_eq!(stats.top_files[0].allocated_bytes, None);
+        assert_eq!(stats.total_allocated_bytes, None);
+    }
+
+    #[test]
+    fn test_mime_detection_sniffs_content_not_extension() {
+        let dir = tempdir().unwrap();
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        png.resize(100, 0);
+        std::fs::write(dir.path().join("blob.dat"), &png).unwrap();
+        std::fs::write(dir.path().join("notes"), b"plain text, no magic").unwrap();
+
+        let stats = Scanner::new(dir.path(), 10)
+            .with_mime_detection(true)
+            .scan()
+            .unwrap();
+        assert_eq!(stats.mime_types["image/png"].count, 1);
+        assert_eq!(stats.mime_types["image/png"].size, 100);
+        assert_eq!(stats.mime_types[UNKNOWN_MIME].count, 1);
+        assert_eq!(stats.extensions["dat"].count, 1);
+
+        let stats = Scanner::new(dir.path(), 10).scan().unwrap();
+        assert!(stats.mime_types.is_empty());
+    }
+
+    #[test]
+    fn test_exclude_paths_prunes_subtree() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("skip/deeper")).unwrap();
+        std::fs::write(dir.path().join("keep.txt"), b"keep").unwrap();
+        std::fs::write(dir.path().join("skip/a.txt"), b"aaaa").unwrap();
+        std::fs::write(dir.path().join("skip/deeper/b.txt"), b"bbbb").unwrap();
+
+        let stats = Scanner::new(dir.path(), 5)
+            .exclude_paths([dir.path().join("skip")])
+            .scan()
+            .unwrap();
+
+        assert_eq!(stats.total_files, 1);
+        assert_eq!(stats.total_size_bytes, 4);
+        assert!(stats.top_files.iter().all(|f| !f.path.contains("skip")));
+    }
+
+    #[test]
+    fn test_small_queue_bound_completes_wide_tree() {
+        let dir = tempdir().unwrap();
+        for d in 0..30 {
+            let sub = dir.path().join(format!("dir_{}", d));
+            std::fs::create_dir(&sub).unwrap();
+            for f in 0..40 {
+                std::fs::write(sub.join(format!("f_{}.dat", f)), vec![0u8; f + 1]).unwrap();
+            }
+        }
+
+        let unbounded = Scanner::new(dir.path(), 5).with_threads(4).scan().unwrap();
+        let bounded = Scanner::new(dir.path(), 5)
+            .with_threads(4)
+            .with_queue_bound(4)
+            .scan()
+            .unwrap();
+
+        assert_eq!(bounded.total_files, 30 * 40);
+        assert_eq!(bounded.total_folders, unbounded.total_folders);
+        assert_eq!(bounded.total_size_bytes, unbounded.total_size_bytes);
+        assert_eq!(bounded.top_files[0].size_bytes, 40);
+    }
+
+    #[test]
+    fn test_directory_sampling_estimates_total() {
+        let dir = tempdir().unwrap();
+        let big = dir.path().join("big");
+        std::fs::create_dir(&big).unwrap();
+        for i in 0..3000u64 {
+            // Sizes cycle through 100..=1099 bytes.
+            let size = 100 + (i * 37) % 1000;
+            std::fs::write(big.join(format!("f{}.dat", i)), vec![0u8; size as usize]).unwrap();
+        }
+        std::fs::write(dir.path().join("small.txt"), b"hello").unwrap();
+
+        let exact = Scanner::new(dir.path(), 5).scan().unwrap();
+        let sampled = Scanner::new(dir.path(), 5)
+            .with_directory_sampling(0.2)
+            .scan()
+            .unwrap();
+
+        assert_eq!(exact.sampled_directories, 0);
+        assert_eq!(sampled.sampled_directories, 1);
+        assert_eq!(sampled.total_files, exact.total_files);
+        assert_eq!(sampled.extensions["dat"].count, 3000);
+
+        let error = (sampled.total_size_bytes as f64 - exact.total_size_bytes as f64).abs()
+            / exact.total_size_bytes as f64;
+        assert!(error < 0.05, "estimate off by {:.1}%", error * 100.0);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_changed_since_skips_untouched_directories() {
+        use std::fs::File;
+
+        let dir = tempdir().unwrap();
+        let old = SystemTime::now() - Duration::from_secs(2 * 24 * 3600);
+        let cutoff = SystemTime::now() - Duration::from_secs(3600);
+        let set_mtime = |path: &Path| File::open(path).unwrap().set_modified(old).unwrap();
+
+        for sub in ["active", "idle/deep", "stale"] {
+            std::fs::create_dir_all(dir.path().join(sub)).unwrap();
+        }
+        std::fs::write(dir.path().join("active/old.txt"), b"old").unwrap();
+        std::fs::write(dir.path().join("stale/old.txt"), b"old").unwrap();
+        set_mtime(&dir.path().join("active/old.txt"));
+        // Rewritten in place and nested changes: recent files, but their
+        // directories look untouched, so they're missed.
+        std::fs::write(dir.path().join("idle/edited.txt"), b"edited").unwrap();
+        std::fs::write(dir.path().join("idle/deep/nested.txt"), b"nested").unwrap();
+        for sub in ["idle/deep", "idle", "stale"] {
+            set_mtime(&dir.path().join(sub));
+        }
+        // Creating a file bumps only the `active` directory's mtime.
+        std::fs::write(dir.path().join("active/new.txt"), b"fresh!").unwrap();
+
+        let stats = Scanner::new(dir.path(), 5)
+            .with_changed_since(cutoff)
+            .scan()
+            .unwrap();
+
+        assert_eq!(stats.total_files, 1);
+        assert_eq!(stats.total_size_bytes, 6);
+        assert!(stats.top_files[0].path.ends_with("new.txt"));
+        // Only the root and `active` were read: `idle`, `idle/deep` and
+        // `stale` never were.
+        assert_eq!(stats.total_folders, 2);
+        assert_eq!(stats.top_dirs.len(), 1);
+        assert!(stats.top_dirs[0].path.ends_with("active"));
+    }
+
+    #[test]
+    fn test_merge_sums_and_retrims_top_files() {
+        let mut a = ScanStats {
+            top_files: vec![
+                FileRecord {
+                    path: "a/big".into(),
+                    size_bytes: 200,
+                    ..Default::default()
+                },
+                FileRecord {
+                    path: "a/small".into(),
+                    size_bytes: 100,
+                    ..Default::default()
+                },
+            ],
+            ..stats_with_extensions(&[("log", 2, 300)])
+        };
+
+        let b = ScanStats {
+            top_files: vec![FileRecord {
+                path: "b/mid".into(),
+                size_bytes: 150,
+                ..Default::default()
+            }],
+            ..stats_with_extensions(&[("log", 1, 150)])
+        };
+
+        a.merge(b, 2);
+
+        assert_eq!(a.total_files, 3);
+        assert_eq!(a.total_size_bytes, 450);
+        assert_eq!(a.extensions["log"].count, 3);
+        let paths: Vec<&str> = a.top_files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["a/big", "b/mid"]);
+    }
+
+    #[test]
+    fn test_merge_keeps_extension_samples_within_the_limit() {
+        let roots: Vec<_> = (0..2).map(|_| tempdir().unwrap()).collect();
+        for root in &roots {
+            for i in 0..5 {
+                std::fs::write(root.path().join(format!("{}.log", i)), b"x").unwrap();
+            }
+        }
+        let mut scans = roots.iter().map(|root| {
+            Scanner::new(root.path(), 1)
+                .with_extension_samples(3)
+                .scan()
+                .unwrap()
+        });
+        let mut merged = scans.next().unwrap();
+        let other = scans.next().unwrap();
+        let mut all: Vec<String> = merged.extension_samples["log"]
+            .iter()
+            .chain(&other.extension_samples["log"])
+            .cloned()
+            .collect();
+        merged.merge(other, 1);
+
+        // Still a sample: the three lowest hashes of both roots' picks.
+        all.sort_by_cached_key(|path| sampling::path_hash(Path::new(path)));
+        all.truncate(3);
+        assert_eq!(merged.extension_samples["log"], all);
+    }
+
+    #[test]
+    fn test_label_round_trips_and_filters_merged_reports() {
+        let labeled = |label: Option<&str>, size| ScanStats {
+            label: label.map(str::to_string),
+            total_size_bytes: size,
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string(&labeled(Some("prod-nfs-weekly"), 1)).unwrap();
+        let back: ScanStats = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.label.as_deref(), Some("prod-nfs-weekly"));
+        let unlabeled = serde_json::to_value(labeled(None, 1)).unwrap();
+        assert!(unlabeled.get("label").is_none());
+
+        // Merge only the weekly reports among a set of saved ones.
+        let saved = vec![
+            back,
+            labeled(Some("laptop-cleanup"), 10),
+            labeled(Some("prod-nfs-weekly"), 100),
+        ];
+        let mut merged = ScanStats::default();
+        for stats in saved.into_iter().filter(|s| s.has_label("prod-nfs-weekly")) {
+            merged.merge(stats, 10);
+        }
+        assert_eq!(merged.total_size_bytes, 101);
+        assert!(merged.has_label("prod-nfs-weekly"));
+
+        merged.merge(labeled(Some("laptop-cleanup"), 10), 10);
+        assert_eq!(merged.label, None);
+    }
+
+    #[test]
+    fn test_extension_keys_follow_case_sensitivity() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("a.JPG"), b"one").unwrap();
+        std::fs::write(dir.path().join("b.jpg"), b"two").unwrap();
+
+        let folded = Scanner::new(dir.path(), 5)
+            .with_case_sensitivity(CaseSensitivity::Insensitive)
+            .scan()
+            .unwrap();
+        assert_eq!(folded.extensions["jpg"].count, 2);
+
+        let exact = Scanner::new(dir.path(), 5)
+            .with_case_sensitivity(CaseSensitivity::Sensitive)
+            .scan()
+            .unwrap();
+        assert_eq!(exact.extensions["jpg"].count, 1);
+        assert_eq!(exact.extensions["JPG"].count, 1);
+    }
 }
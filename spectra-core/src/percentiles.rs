@@ -0,0 +1,154 @@
+//! Streaming file-size distribution.
+//!
+//! Keeping every size to sort at the end costs 8 bytes per file, which adds
+//! up on trees with tens of millions of files. [`SizeHistogram`] instead
+//! counts sizes into fixed log-scale buckets: sizes below
+//! [`EXACT_BELOW`] get a bucket each, and every power of two above that is
+//! split into [`SUB_BUCKETS`] equal slices. A quantile is reported as the
+//! midpoint of its bucket, so it's within 1/16 (about 6%) of the true value,
+//! in under 4KB however many files are recorded.
+
+use serde::{Deserialize, Serialize};
+
+/// Sizes below this are counted exactly.
+pub const EXACT_BELOW: u64 = 16;
+/// Slices per power of two above [`EXACT_BELOW`].
+pub const SUB_BUCKETS: u64 = 8;
+
+const SUB_BITS: u32 = SUB_BUCKETS.trailing_zeros();
+const EXACT_BITS: u32 = EXACT_BELOW.trailing_zeros();
+
+/// Approximate file-size percentiles. `max` is exact.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SizePercentiles {
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+/// Log-bucketed counts of file sizes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SizeHistogram {
+    /// Grown on demand up to the highest bucket seen.
+    counts: Vec<u64>,
+    total: u64,
+    max: u64,
+}
+
+impl SizeHistogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `weight` files of `size` bytes.
+    pub fn record(&mut self, size: u64, weight: u64) {
+        let bucket = bucket_of(size);
+        if bucket >= self.counts.len() {
+            self.counts.resize(bucket + 1, 0);
+        }
+        self.counts[bucket] += weight;
+        self.total += weight;
+        self.max = self.max.max(size);
+    }
+
+    /// Files recorded so far.
+    pub fn len(&self) -> u64 {
+        self.total
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    pub fn merge(&mut self, other: &SizeHistogram) {
+        if other.counts.len() > self.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        for (mine, theirs) in self.counts.iter_mut().zip(&other.counts) {
+            *mine += theirs;
+        }
+        self.total += other.total;
+        self.max = self.max.max(other.max);
+    }
+
+    /// Approximate size at quantile `q` (0.0 to 1.0); 0 when empty.
+    pub fn quantile(&self, q: f64) -> u64 {
+        if self.total == 0 {
+            return 0;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * self.total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return midpoint(bucket).min(self.max);
+            }
+        }
+        self.max
+    }
+
+    pub fn percentiles(&self) -> SizePercentiles {
+        SizePercentiles {
+            p50: self.quantile(0.50),
+            p90: self.quantile(0.90),
+            p99: self.quantile(0.99),
+            max: self.max,
+        }
+    }
+}
+
+fn bucket_of(size: u64) -> usize {
+    if size < EXACT_BELOW {
+        return size as usize;
+    }
+    let exponent = 63 - size.leading_zeros();
+    let slice = (size >> (exponent - SUB_BITS)) & (SUB_BUCKETS - 1);
+    (EXACT_BELOW + u64::from(exponent - EXACT_BITS) * SUB_BUCKETS + slice) as usize
+}
+
+/// Middle of the size range counted in `bucket`.
+fn midpoint(bucket: usize) -> u64 {
+    let bucket = bucket as u64;
+    if bucket < EXACT_BELOW {
+        return bucket;
+    }
+    let exponent = (bucket - EXACT_BELOW) / SUB_BUCKETS + u64::from(EXACT_BITS);
+    let slice = (bucket - EXACT_BELOW) % SUB_BUCKETS;
+    let width = 1u64 << (exponent - u64::from(SUB_BITS));
+    let low = (1u64 << exponent) + slice * width;
+    low + width / 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets_cover_their_midpoints() {
+        for size in [0, 1, 15, 16, 17, 31, 32, 1000, 4096, 1 << 40, u64::MAX] {
+            let bucket = bucket_of(size);
+            assert_eq!(bucket_of(midpoint(bucket)), bucket, "size {}", size);
+            let error = midpoint(bucket).abs_diff(size) as f64 / size.max(1) as f64;
+            assert!(error <= 1.0 / 16.0, "size {} error {}", size, error);
+        }
+    }
+
+    #[test]
+    fn test_merge_matches_single_histogram() {
+        let mut all = SizeHistogram::new();
+        let mut a = SizeHistogram::new();
+        let mut b = SizeHistogram::new();
+        for size in 0..500u64 {
+            all.record(size * 37, 1);
+            if size % 2 == 0 {
+                a.record(size * 37, 1);
+            } else {
+                b.record(size * 37, 1);
+            }
+        }
+        a.merge(&b);
+        assert_eq!(a, all);
+        assert_eq!(a.percentiles(), all.percentiles());
+    }
+}
@@ -0,0 +1,124 @@
+//! Optional scan instrumentation for tuning thread counts.
+//!
+//! Enabled with `Scanner::with_profile`. Walker threads keep their own
+//! counters (one slot per thread, never contended) which are merged into a
+//! [`ScanProfile`] when the scan finishes. The aggregator, which runs on the
+//! calling thread, times how long it waits on the walkers versus how long it
+//! spends reading file metadata: a scan dominated by walk wait is I/O-bound
+//! on directory reads, one dominated by metadata is bound by the single
+//! aggregating thread.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// What one walker thread did.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct WorkerProfile {
+    /// Walker thread index.
+    pub worker: usize,
+    /// Directory listings the thread read.
+    pub directories: u64,
+    /// Files from those listings counted in `total_files`.
+    pub files: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct ScanProfile {
+    pub workers: Vec<WorkerProfile>,
+    /// Aggregator time spent waiting for the walkers to yield entries.
+    pub walk_wait_ms: u64,
+    /// Aggregator time spent reading file metadata.
+    pub metadata_ms: u64,
+    /// Time spent hashing file contents after the walk (set by callers that hash).
+    #[serde(default)]
+    pub hashing_ms: u64,
+    /// Time spent on entropy/heuristic analysis after the walk (set by callers).
+    #[serde(default)]
+    pub analysis_ms: u64,
+}
+
+impl ScanProfile {
+    /// Sum of per-worker file counts; equals `ScanStats::total_files`.
+    pub fn files(&self) -> u64 {
+        self.workers.iter().map(|w| w.files).sum()
+    }
+
+    /// Fold another root's profile into this one, worker by worker.
+    pub fn merge(&mut self, other: ScanProfile) {
+        for worker in other.workers {
+            match self.workers.iter_mut().find(|w| w.worker == worker.worker) {
+                Some(existing) => {
+                    existing.directories += worker.directories;
+                    existing.files += worker.files;
+                }
+                None => self.workers.push(worker),
+            }
+        }
+        self.workers.sort_by_key(|w| w.worker);
+        self.walk_wait_ms += other.walk_wait_ms;
+        self.metadata_ms += other.metadata_ms;
+        self.hashing_ms += other.hashing_ms;
+        self.analysis_ms += other.analysis_ms;
+    }
+}
+
+/// Per-thread listing counters shared with the walker threads. Files are
+/// attributed by the aggregator instead, so that the per-worker counts sum to
+/// exactly `total_files` after sampling weights and `--changed-since`
+/// filtering.
+#[derive(Debug)]
+pub(crate) struct WorkerCounters {
+    directories: Vec<AtomicU64>,
+}
+
+impl WorkerCounters {
+    pub(crate) fn new(threads: usize) -> Self {
+        Self {
+            directories: (0..threads.max(1)).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    pub(crate) fn workers(&self) -> usize {
+        self.directories.len()
+    }
+
+    /// Index of the calling walker thread. Work done outside the walker pool
+    /// is attributed to worker 0.
+    pub(crate) fn current_worker(&self) -> usize {
+        jwalk::rayon::current_thread_index()
+            .filter(|i| *i < self.directories.len())
+            .unwrap_or(0)
+    }
+
+    /// Called by a walker thread for each directory listing it reads.
+    pub(crate) fn record_listing(&self, worker: usize) {
+        self.directories[worker].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Combine the listing counts with the aggregator's per-worker file
+    /// counts and timings.
+    pub(crate) fn profile(
+        &self,
+        files: Vec<u64>,
+        walk_wait_ms: u64,
+        metadata_ms: u64,
+    ) -> ScanProfile {
+        let workers = self
+            .directories
+            .iter()
+            .zip(files)
+            .enumerate()
+            .map(|(worker, (directories, files))| WorkerProfile {
+                worker,
+                directories: directories.load(Ordering::Relaxed),
+                files,
+            })
+            .collect();
+        ScanProfile {
+            workers,
+            walk_wait_ms,
+            metadata_ms,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,489 @@
+//! The enriched scan model shared by the CLI, GUI and server.
+//!
+//! [`ScanReport`] composes the raw [`ScanStats`] with optional per-file
+//! analysis (entropy, risk, semantic tags). It serializes to the same shape
+//! as `ScanStats`, with each `top_files` entry carrying its analysis fields
+//! inline, so existing consumers of the JSON keep working.
+
+use crate::category;
+use crate::dedup::{self, DedupSavings, DuplicateGroup};
+use crate::hash::{hash_file, HashFilter};
+use crate::{ExtensionStat, FileRecord, ScanStats};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Analysis results for a single file. Every field is optional; an empty
+/// `FileAnalysis` means no analysis ran.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FileAnalysis {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entropy: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub risk_level: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub semantic_tag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub semantic_confidence: Option<f64>,
+    /// All reported semantic labels with scores, best first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub semantic_candidates: Vec<(String, f64)>,
+    /// Whether this file is a statistical entropy outlier (IQR method).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entropy_outlier: Option<bool>,
+    /// Entropy of consecutive blocks of the file, in order (see
+    /// [`block_entropy`](crate::analysis::block_entropy)).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub block_entropy: Vec<f32>,
+    /// Whether the header looks encrypted rather than just compressed
+    /// (high entropy and a chi-square consistent with random bytes; see
+    /// [`detect_encryption`](crate::analysis::detect_encryption)). `None`
+    /// when untested or too small to tell.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_likely_encrypted: Option<bool>,
+    /// Conditions behind the risk level and flags (populated by `--explain`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<String>,
+    /// BLAKE3 content hash (hex). `None` when hashing was off or the file
+    /// didn't match the [`HashFilter`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// Category of the extension (see
+    /// [`category_of`](crate::category::category_of)).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// MIME type sniffed from the file's leading bytes, or
+    /// [`UNKNOWN_MIME`](crate::UNKNOWN_MIME).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+/// A top-N file together with its analysis.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnalyzedFile {
+    #[serde(flatten)]
+    pub record: FileRecord,
+    #[serde(flatten)]
+    pub analysis: FileAnalysis,
+    /// Other listed paths that resolve to the same file (set by
+    /// [`ScanReport::dedup_top_files`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+impl From<FileRecord> for AnalyzedFile {
+    fn from(record: FileRecord) -> Self {
+        Self {
+            record,
+            analysis: FileAnalysis::default(),
+            aliases: Vec::new(),
+        }
+    }
+}
+
+/// Scan statistics plus per-file analysis.
+///
+/// The top files live in [`ScanReport::top_files`]; `stats.top_files` is
+/// always empty, including in a loaded report, so the two lists can't drift
+/// apart. Use [`ScanReport::into_stats`] to get plain `ScanStats` back.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(into = "ReportDocument", from = "ReportDocument")]
+pub struct ScanReport {
+    pub stats: ScanStats,
+    pub top_files: Vec<AnalyzedFile>,
+    /// Entropy cache hits during analysis, when analysis ran.
+    pub cache_hits: Option<usize>,
+    /// Duplicate-file groups, when a dedup pass ran.
+    pub duplicates: Option<Vec<DuplicateGroup>>,
+    /// Per-directory sizes from [`breakdown::summarize_by_depth`], when a
+    /// breakdown was requested.
+    ///
+    /// [`breakdown::summarize_by_depth`]: crate::breakdown::summarize_by_depth
+    pub breakdown: Option<Vec<(String, u64)>>,
+}
+
+impl ScanReport {
+    pub fn new(mut stats: ScanStats) -> Self {
+        let top_files = std::mem::take(&mut stats.top_files)
+            .into_iter()
+            .map(AnalyzedFile::from)
+            .collect();
+        Self {
+            stats,
+            top_files,
+            cache_hits: None,
+            duplicates: None,
+            breakdown: None,
+        }
+    }
+
+    /// Collapse top files that resolve to the same underlying file (symlinks
+    /// followed during the scan, hard links) into one entry. The entry keeps
+    /// the first listed non-symlink path, or the canonical path if every
+    /// listed path is a link, and records the others as `aliases`. Only the
+    /// top list is affected; totals still count each path. Returns how many
+    /// entries were removed.
+    pub fn dedup_top_files(&mut self) -> usize {
+        let before = self.top_files.len();
+        let mut kept: Vec<AnalyzedFile> = Vec::with_capacity(before);
+        let mut seen: HashMap<FileIdentity, usize> = HashMap::new();
+        for file in std::mem::take(&mut self.top_files) {
+            let Some(id) = file_identity(Path::new(&file.record.path)) else {
+                kept.push(file);
+                continue;
+            };
+            match seen.get(&id) {
+                Some(&idx) => {
+                    let existing = &mut kept[idx];
+                    if is_symlink(&existing.record.path) && !is_symlink(&file.record.path) {
+                        let link = std::mem::replace(&mut existing.record.path, file.record.path);
+                        existing.aliases.push(link);
+                    } else {
+                        existing.aliases.push(file.record.path);
+                    }
+                }
+                None => {
+                    seen.insert(id, kept.len());
+                    kept.push(file);
+                }
+            }
+        }
+        for file in &mut kept {
+            if !file.aliases.is_empty() && is_symlink(&file.record.path) {
+                if let Ok(canonical) = std::fs::canonicalize(&file.record.path) {
+                    let link =
+                        std::mem::replace(&mut file.record.path, canonical.display().to_string());
+                    file.aliases.push(link);
+                }
+            }
+        }
+        self.top_files = kept;
+        before - self.top_files.len()
+    }
+
+    /// Set `content_hash` on every top file accepted by `filter`; other files
+    /// are not read and keep `content_hash: None`. Returns how many files were
+    /// hashed. Unreadable files are skipped.
+    pub fn hash_top_files(&mut self, filter: &HashFilter) -> usize {
+        let mut hashed = 0;
+        for file in &mut self.top_files {
+            let path = Path::new(&file.record.path);
+            if !filter.matches(path, file.record.size_bytes) {
+                continue;
+            }
+            if let Ok(hash) = hash_file(path) {
+                file.analysis.content_hash = Some(hash);
+                hashed += 1;
+            }
+        }
+        hashed
+    }
+
+    /// Drop the analysis and return the underlying stats.
+    pub fn into_stats(mut self) -> ScanStats {
+        self.stats.top_files = self.top_files.into_iter().map(|f| f.record).collect();
+        self.stats
+    }
+}
+
+#[cfg(unix)]
+pub(crate) type FileIdentity = (u64, u64);
+#[cfg(not(unix))]
+pub(crate) type FileIdentity = std::path::PathBuf;
+
+/// Device and inode on Unix; the canonical path elsewhere.
+#[cfg(unix)]
+pub(crate) fn file_identity(path: &Path) -> Option<FileIdentity> {
+    use std::os::unix::fs::MetadataExt;
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn file_identity(path: &Path) -> Option<FileIdentity> {
+    std::fs::canonicalize(path).ok()
+}
+
+fn is_symlink(path: &str) -> bool {
+    std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink())
+}
+
+impl From<ScanStats> for ScanReport {
+    fn from(stats: ScanStats) -> Self {
+        Self::new(stats)
+    }
+}
+
+/// The serialized form of a [`ScanReport`]: the stats' fields with the
+/// analyzed `top_files` in place of the plain records, plus the values
+/// derived from the totals. Derived values are ignored when loading.
+#[derive(Serialize, Deserialize)]
+struct ReportDocument {
+    #[serde(flatten)]
+    stats: ScanStats,
+    #[serde(default)]
+    top_files: Vec<DocumentFile>,
+    /// Each extension with its share of the total size.
+    #[serde(default)]
+    extensions: HashMap<String, Share>,
+    #[serde(default, skip_deserializing)]
+    categories: HashMap<String, Share>,
+    #[serde(default, skip_deserializing)]
+    total_inodes: u64,
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    allocation_ratio: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cache_hits: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duplicates: Option<Vec<DuplicateGroup>>,
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    dedup_savings: Option<DedupSavings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    breakdown: Option<Vec<(String, u64)>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DocumentFile {
+    #[serde(flatten)]
+    file: AnalyzedFile,
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    allocation_ratio: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Share {
+    #[serde(flatten)]
+    stat: ExtensionStat,
+    #[serde(default, skip_deserializing)]
+    percent: f64,
+}
+
+impl From<ScanReport> for ReportDocument {
+    fn from(report: ScanReport) -> Self {
+        let ScanReport {
+            mut stats,
+            top_files,
+            cache_hits,
+            duplicates,
+            breakdown,
+        } = report;
+        // Both are written by the document itself, with the derived values.
+        stats.top_files.clear();
+        let extensions = std::mem::take(&mut stats.extensions);
+        let with_percent = |entries: HashMap<String, ExtensionStat>| {
+            entries
+                .into_iter()
+                .map(|(key, stat)| {
+                    let percent = round_percent(stats.percent_of_total(stat.size));
+                    (key, Share { stat, percent })
+                })
+                .collect()
+        };
+        let categories = with_percent(category::rollup(&extensions));
+        let extensions = with_percent(extensions);
+        Self {
+            categories,
+            extensions,
+            top_files: top_files
+                .into_iter()
+                .map(|file| DocumentFile {
+                    allocation_ratio: file.record.allocation_ratio(),
+                    file,
+                })
+                .collect(),
+            total_inodes: stats.total_inodes(),
+            allocation_ratio: stats.allocation_ratio(),
+            dedup_savings: duplicates.as_deref().map(dedup::savings),
+            stats,
+            cache_hits,
+            duplicates,
+            breakdown,
+        }
+    }
+}
+
+impl From<ReportDocument> for ScanReport {
+    fn from(document: ReportDocument) -> Self {
+        let mut stats = document.stats;
+        stats.extensions = document
+            .extensions
+            .into_iter()
+            .map(|(key, share)| (key, share.stat))
+            .collect();
+        Self {
+            stats,
+            top_files: document.top_files.into_iter().map(|f| f.file).collect(),
+            cache_hits: document.cache_hits,
+            duplicates: document.duplicates,
+            breakdown: document.breakdown,
+        }
+    }
+}
+
+/// Two decimal places: enough to tell small extensions apart.
+fn round_percent(percent: f64) -> f64 {
+    (percent * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DeviceType, ExtensionStat};
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dedup_collapses_symlink_to_large_file() {
+        use crate::Scanner;
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        let big = dir.path().join("big.bin");
+        std::fs::write(&big, vec![0u8; 10_000]).unwrap();
+        std::fs::write(dir.path().join("small.txt"), b"hi").unwrap();
+        std::os::unix::fs::symlink(&big, dir.path().join("link.bin")).unwrap();
+
+        let stats = Scanner::new(dir.path(), 10)
+            .with_follow_links(true)
+            .scan()
+            .unwrap();
+        let mut report = ScanReport::new(stats);
+        assert_eq!(report.top_files.len(), 3);
+
+        assert_eq!(report.dedup_top_files(), 1);
+        let large: Vec<_> = report
+            .top_files
+            .iter()
+            .filter(|f| f.record.size_bytes == 10_000)
+            .collect();
+        assert_eq!(large.len(), 1);
+        assert!(large[0].record.path.ends_with("big.bin"));
+        assert_eq!(large[0].aliases.len(), 1);
+        assert!(large[0].aliases[0].ends_with("link.bin"));
+    }
+
+    #[test]
+    fn test_hash_filter_limits_hashing_to_matching_files() {
+        use crate::{CaseSensitivity, Scanner};
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("big.zip"), vec![1u8; 5_000]).unwrap();
+        std::fs::write(dir.path().join("small.zip"), vec![2u8; 100]).unwrap();
+        std::fs::write(dir.path().join("big.log"), vec![3u8; 5_000]).unwrap();
+
+        let mut report = ScanReport::new(Scanner::new(dir.path(), 10).scan().unwrap());
+        let filter = HashFilter::new()
+            .with_extensions([".ZIP"])
+            .with_min_size(1_000)
+            .with_case_sensitivity(CaseSensitivity::Insensitive);
+
+        assert_eq!(report.hash_top_files(&filter), 1);
+        for file in &report.top_files {
+            let hash = file.analysis.content_hash.as_deref();
+            if file.record.path.ends_with("big.zip") {
+                assert_eq!(hash, Some(blake3::hash(&[1u8; 5_000]).to_hex().as_str()));
+            } else {
+                assert_eq!(hash, None, "{}", file.record.path);
+            }
+        }
+    }
+
+    #[test]
+    fn test_report_round_trips_core_and_analysis_fields() {
+        let mut stats = ScanStats {
+            root_path: "/data".to_string(),
+            total_files: 2,
+            total_size_bytes: 3000,
+            device_type: Some(DeviceType::SSD),
+            top_files: vec![
+                FileRecord {
+                    path: "/data/key.pem".to_string(),
+                    size_bytes: 2000,
+                    ..Default::default()
+                },
+                FileRecord {
+                    path: "/data/notes.txt".to_string(),
+                    size_bytes: 1000,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        stats.extensions.insert(
+            "pem".to_string(),
+            ExtensionStat {
+                count: 1,
+                size: 2000,
+                avg_entropy: Some(7.9),
+            },
+        );
+
+        let mut report = ScanReport::new(stats);
+        report.cache_hits = Some(4);
+        report.breakdown = Some(vec![(".".to_string(), 3000)]);
+        report.top_files[0].analysis = FileAnalysis {
+            entropy: Some(7.9),
+            risk_level: Some("Critical".to_string()),
+            reasons: vec!["Critical: filename ends with `.pem`".to_string()],
+            ..Default::default()
+        };
+
+        let json = serde_json::to_value(&report).unwrap();
+        // Analysis fields sit inline on each top file, as in the CLI output.
+        assert_eq!(json["top_files"][0]["risk_level"], "Critical");
+        assert_eq!(json["top_files"][0]["path"], "/data/key.pem");
+        assert!(json["top_files"][1].get("entropy").is_none());
+        assert_eq!(json["total_inodes"], 2);
+        assert_eq!(json["extensions"]["pem"]["percent"], 66.67);
+
+        let back: ScanReport = serde_json::from_value(json).unwrap();
+        assert_eq!(back.stats.root_path, "/data");
+        assert_eq!(back.stats.total_size_bytes, 3000);
+        assert_eq!(back.stats.device_type, Some(DeviceType::SSD));
+        assert_eq!(back.stats.extensions["pem"].avg_entropy, Some(7.9));
+        assert_eq!(back.cache_hits, Some(4));
+        assert_eq!(back.breakdown, report.breakdown);
+        assert_eq!(back.top_files, report.top_files);
+        assert!(back.stats.top_files.is_empty());
+
+        // A plain ScanStats document loads as a report without analysis.
+        let plain = serde_json::to_string(&back.clone().into_stats()).unwrap();
+        let loaded: ScanReport = serde_json::from_str(&plain).unwrap();
+        assert_eq!(loaded.top_files.len(), 2);
+        assert_eq!(loaded.top_files[1].analysis, FileAnalysis::default());
+    }
+
+    #[test]
+    fn test_extension_percentages_sum_to_one_hundred() {
+        use crate::Scanner;
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("a.jpg"), vec![0u8; 6_200]).unwrap();
+        std::fs::write(dir.path().join("b.png"), vec![0u8; 1_234]).unwrap();
+        std::fs::write(dir.path().join("c.rs"), vec![0u8; 333]).unwrap();
+        std::fs::write(dir.path().join("d.txt"), vec![0u8; 2_233]).unwrap();
+
+        let report = ScanReport::new(Scanner::new(dir.path(), 10).scan().unwrap());
+        let json = serde_json::to_value(&report).unwrap();
+        let sum = |key: &str| -> f64 {
+            let entries = json[key].as_object().unwrap();
+            entries
+                .values()
+                .map(|e| e["percent"].as_f64().unwrap())
+                .sum()
+        };
+        assert!((sum("extensions") - 100.0).abs() < 0.05, "{}", json);
+        assert!((sum("categories") - 100.0).abs() < 0.05, "{}", json);
+        assert_eq!(json["extensions"]["jpg"]["percent"], 62.0);
+        assert_eq!(json["categories"]["images"]["percent"], 74.34);
+
+        let back: ScanReport = serde_json::from_value(json).unwrap();
+        assert_eq!(back.stats.extensions["jpg"].size, 6_200);
+
+        let empty = ScanStats::default();
+        assert_eq!(empty.percent_of_total(0), 0.0);
+        let json = serde_json::to_value(ScanReport::new(empty)).unwrap();
+        assert!(json["extensions"].as_object().unwrap().is_empty());
+    }
+}
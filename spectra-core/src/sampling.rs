@@ -0,0 +1,166 @@
+//! Directory sampling for very large, homogeneous directories.
+//!
+//! When a directory holds more than [`SAMPLING_MIN_FILES`] files, only a
+//! fraction of them are stat'ed. The kept files carry an integer weight so
+//! that the weights sum to the directory's true file count; sizes are then
+//! extrapolated as `size * weight`. File counts stay exact, sizes become an
+//! estimate whose accuracy depends on how uniform the directory is.
+//!
+//! The selection is pseudo-random but deterministic: files are ordered by a
+//! hash of their name, so repeated scans of an unchanged tree sample the same
+//! files and produce the same estimate.
+
+use jwalk::{ClientState, DirEntry};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+/// Directories with at most this many files are always scanned in full.
+pub const SAMPLING_MIN_FILES: usize = 1000;
+
+/// Per-entry walk state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct EntryState {
+    /// How many files the entry stands for.
+    pub weight: u64,
+    /// Walker thread that listed the entry (only tracked when profiling).
+    pub reader: usize,
+}
+
+impl Default for EntryState {
+    fn default() -> Self {
+        EntryState {
+            weight: 1,
+            reader: 0,
+        }
+    }
+}
+
+/// Thin out the file entries of one directory listing in place.
+/// Returns true if the directory was sampled.
+pub(crate) fn sample_children<C>(
+    children: &mut Vec<jwalk::Result<DirEntry<C>>>,
+    fraction: f64,
+) -> bool
+where
+    C: ClientState<DirEntryState = EntryState>,
+{
+    let mut files: Vec<(u64, usize)> = children
+        .iter()
+        .enumerate()
+        .filter_map(|(i, child)| match child {
+            Ok(entry) if entry.file_type().is_file() => Some((name_hash(entry), i)),
+            _ => None,
+        })
+        .collect();
+
+    let total = files.len();
+    if total <= SAMPLING_MIN_FILES {
+        return false;
+    }
+    let keep = ((total as f64 * fraction).ceil() as usize).clamp(1, total);
+    if keep == total {
+        return false;
+    }
+
+    files.sort_unstable();
+    files.truncate(keep);
+
+    // Spread `total` over `keep` entries as evenly as integers allow.
+    let base = (total / keep) as u64;
+    let extra = total % keep;
+    for (rank, &(_, i)) in files.iter().enumerate() {
+        if let Ok(entry) = &mut children[i] {
+            entry.client_state.weight = base + u64::from(rank < extra);
+        }
+    }
+
+    let kept: HashSet<usize> = files.into_iter().map(|(_, i)| i).collect();
+    let mut index = 0;
+    children.retain(|child| {
+        let i = index;
+        index += 1;
+        match child {
+            Ok(entry) if entry.file_type().is_file() => kept.contains(&i),
+            _ => true,
+        }
+    });
+    true
+}
+
+/// Up to `limit` paths per extension, drawn during the walk for content
+/// analysis that shouldn't read every file. Like directory sampling, the
+/// pick is by hash (the paths hashing lowest), so it's spread across the
+/// tree, repeatable, and samples of several roots merge by keeping the
+/// lowest hashes again.
+#[derive(Debug, Default)]
+pub(crate) struct ExtensionReservoir {
+    limit: usize,
+    samples: HashMap<String, BinaryHeap<(u64, String)>>,
+}
+
+impl ExtensionReservoir {
+    pub(crate) fn new(limit: usize) -> Self {
+        ExtensionReservoir {
+            limit,
+            samples: HashMap::new(),
+        }
+    }
+
+    /// Consider `path` for `ext`'s sample; the path is only copied if kept.
+    pub(crate) fn offer(&mut self, ext: &str, path: &Path) {
+        if self.limit == 0 {
+            return;
+        }
+        let hash = path_hash(path);
+        let heap = match self.samples.get_mut(ext) {
+            Some(heap) => heap,
+            None => self.samples.entry(ext.to_string()).or_default(),
+        };
+        if heap.len() < self.limit {
+            heap.push((hash, path.display().to_string()));
+        } else if heap.peek().is_some_and(|(highest, _)| hash < *highest) {
+            heap.pop();
+            heap.push((hash, path.display().to_string()));
+        }
+    }
+
+    /// Each extension's sample, lowest hash first.
+    pub(crate) fn into_samples(self) -> HashMap<String, Vec<String>> {
+        self.samples
+            .into_iter()
+            .map(|(ext, heap)| {
+                let paths = heap.into_sorted_vec().into_iter().map(|(_, p)| p).collect();
+                (ext, paths)
+            })
+            .collect()
+    }
+}
+
+/// Merge `other`'s samples into `samples`, keeping every list ordered by
+/// hash and cut back to `limit` paths so it's still a sample.
+pub(crate) fn merge_samples(
+    samples: &mut HashMap<String, Vec<String>>,
+    other: HashMap<String, Vec<String>>,
+    limit: usize,
+) {
+    for (ext, paths) in other {
+        let merged = samples.entry(ext).or_default();
+        merged.extend(paths);
+        merged.sort_by_cached_key(|path| path_hash(Path::new(path)));
+        merged.truncate(limit);
+    }
+}
+
+pub(crate) fn path_hash(path: &Path) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn name_hash<C: ClientState>(entry: &DirEntry<C>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    entry.file_name().hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,38 @@
+//! Fixtures shared by the tests of this crate and of crates built on it
+//! (enable the `test-util` feature from a dev-dependency).
+
+use crate::{ExtensionStat, ScanStats};
+
+/// Stats holding only the given `(extension, count, size)` rows, with
+/// `total_files` and `total_size_bytes` summed from them. Override other
+/// fields with struct update syntax.
+pub fn stats_with_extensions(exts: &[(&str, u64, u64)]) -> ScanStats {
+    let mut stats = ScanStats::default();
+    for &(ext, count, size) in exts {
+        stats.total_files += count;
+        stats.total_size_bytes += size;
+        stats.extensions.insert(
+            ext.to_string(),
+            ExtensionStat {
+                count,
+                size,
+                ..Default::default()
+            },
+        );
+    }
+    stats
+}
+
+/// `len` bytes of deterministic xorshift noise from `seed` (non-zero),
+/// standing in for compressed or encrypted data.
+pub fn noise(len: usize, seed: u64) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 24) as u8
+        })
+        .collect()
+}
@@ -22,6 +22,10 @@ pub enum SpectraCommand {
 }
 
 /// A response returned from any transport layer.
+// `ScanStats` dwarfs the other variants, but responses are built once per
+// command and moved straight out, so the size costs nothing and boxing it
+// would change the public variant's type.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SpectraResponse {
     ScanResult(ScanStats),